@@ -3,15 +3,15 @@ defmodule Svgager do
   SVG to image conversion library using Rustler.
 
   Svgager provides high-performance SVG to image conversion with support for multiple
-  output formats (PNG, JPG, JPEG, GIF, WebP), resolution control, background colors,
-  and SVG preprocessing.
+  output formats (PNG, JPG, JPEG, GIF, WebP, BMP, TIFF, QOI, AVIF, TGA, PPM, farbfeld,
+  OpenEXR), resolution control, background colors, and SVG preprocessing.
 
   ## Features
 
-  - Convert SVG to PNG, JPG, JPEG, GIF, or WebP
+  - Convert SVG to PNG, JPG, JPEG, GIF, WebP, BMP, TIFF, QOI, AVIF, TGA, PPM, farbfeld, or OpenEXR
   - Control output resolution (width, height, or both)
   - Automatic aspect ratio preservation when one dimension is provided
-  - Transparent backgrounds for PNG, configurable backgrounds for other formats
+  - Transparent backgrounds for PNG, TIFF, QOI, TGA, farbfeld, EXR and GIF, configurable backgrounds for other formats
   - Preprocess SVG content with string replacements (useful for color changes)
 
   ## Examples
@@ -50,17 +50,340 @@ defmodule Svgager do
 
   ## Options
 
-  - `:format` - (required) Output format (`:png`, `:jpg`, `:jpeg`, `:gif`, or `:webp`)
+  - `:format` - (required) Output format (`:png`, `:jpg`, `:jpeg`, `:gif`, `:webp`, `:bmp`, `:tiff`, `:tif`, `:qoi`, `:avif`, `:tga`, `:pnm`, `:ppm`, `:ff`, `:farbfeld`, or `:exr`)
   - `:width` - (optional) Output width in pixels
   - `:height` - (optional) Output height in pixels
-  - `:background_color` - (optional) Background color as hex string (ignored for PNG)
-  - `:replacements` - (optional) Map of string replacements for preprocessing
+  - `:background_color` - (optional) Background color as CSS color name, `rgb()`/`rgba()` or hex string (ignored for PNG, TIFF, QOI, TGA, farbfeld, EXR and GIF unless `:flatten` is set)
+  - `:replacements` - (optional) Map, or ordered list of `{search, replace}` / `{search, replace, max}` tuples, of string replacements for preprocessing; a trailing `:attr` or `:text` scope limits a tuple to attribute values or element text
+  - `:regex_replacements` - (optional) Regular expression replacements in the same forms, applied after `:replacements`
+  - `:escape_replacements` - (optional) XML-escape replacement values before substituting them (defaults to `false`)
+  - `:ignore_case_replacements` - (optional) Match `:replacements` search strings regardless of ASCII case (defaults to `false`)
+  - `:resource_dir` - (optional) Directory relative `<image>` hrefs are loaded from; paths escaping it are refused
+  - `:css_vars` - (optional) Map or list of `{name, value}` CSS custom properties overriding the SVG's own; `var()` references are resolved before parsing
+  - `:quality` - (optional) JPEG, AVIF and lossy WebP quality from 1 to 100 (defaults to 90)
+  - `:lossless` - (optional) Whether WebP output is lossless (defaults to `true`)
+  - `:fit` - (optional) `:fill`, `:contain` or `:cover` when both dimensions are given (defaults to the SVG's own `preserveAspectRatio`, or `:fill`)
+  - `:preserve_aspect` - (optional) Letterbox the undistorted SVG inside the box, like `fit: :contain` (defaults to `false`)
+  - `:png_compression` - (optional) `:fast`, `:default` or `:best` for PNG output (defaults to `:fast`)
+  - `:background_gradient` - (optional) `{top, bottom}` colors of a vertical background gradient, overriding `:background_color` for every format
+  - `:flatten` - (optional) Render PNG, TIFF, QOI, TGA, farbfeld, EXR and GIF output over `:background_color` (defaults to `false`)
+  - `:default_fill` - (optional) Color for shapes and text without a fill of their own, instead of black
+  - `:font_family` - (optional) Default font family for text (defaults to "Times New Roman")
+  - `:font_size` - (optional) Default font size for text (defaults to 12)
+  - `:load_system_fonts` - (optional) Make system fonts available for text (defaults to `true`)
+  - `:font_dirs` - (optional) Directories to load additional fonts from
+  - `:node_id` - (optional) Render only the element with this `id`
+  - `:rotate` - (optional) Clockwise rotation of `0`, `90`, `180` or `270` degrees
+  - `:flip_h` / `:flip_v` - (optional) Mirror the output horizontally or vertically (default to `false`)
+  - `:trim` - (optional) Cut empty margins off the rendered image (defaults to `false`)
+  - `:crop` - (optional) `{x, y, width, height}` rectangle to cut out of the rendered image
+  - `:padding` - (optional) Pixels of background added on every side of the output
+  - `:dpi` - (optional) Resolution for physical units such as `mm` in the SVG (defaults to 96)
+  - `:cache` - (optional) Reuse the parsed SVG across conversions of the same content (defaults to `false`)
+  - `:ssaa` - (optional) Supersampling factor from 1 to 8 for smoother edges at small sizes (defaults to 1)
+  - `:resize_filter` - (optional) `:lanczos3` (the default), `:catmull`, `:triangle` or `:nearest` filter for downsampling `:ssaa` renders
+  - `:grayscale` - (optional) Convert the output to grayscale, keeping transparency (defaults to `false`)
+  - `:opacity` - (optional) Multiplier from 0.0 to 1.0 for the alpha of the rendered content (defaults to 1.0)
+  - `:bit_depth` - (optional) Bits per channel, `8` or `16` (16 is PNG only, defaults to `8`)
+  - `:scale` - (optional) Multiplier for the intrinsic size, instead of `:width` and `:height` (defaults to 1.0)
+  - `:progressive` - (optional) Write progressive rather than baseline JPEGs (defaults to `false`)
+  - `:subsampling` - (optional) JPEG chroma subsampling, `"4:4:4"`, `"4:2:2"` or `"4:2:0"` (defaults to `"4:4:4"`)
+  - `:metadata` - (optional) `{keyword, text}` pairs written as PNG text chunks (PNG only)
+  - `:icc_profile` - (optional) Binary ICC profile embedded in PNG or JPEG output
+  - `:srgb` - (optional) Tag PNG or JPEG output as sRGB instead (defaults to `false`)
+  - `:dpi_output` - (optional) Physical resolution recorded in PNG output's `pHYs` chunk (PNG only)
+  - `:shape_rendering` - (optional) Default shape rendering, such as `:crisp_edges` for no anti-aliasing
+  - `:text_rendering` - (optional) Default text rendering, such as `:optimize_speed`
+  - `:image_rendering` - (optional) Default embedded image scaling, such as `:optimize_speed`
+  - `:languages` - (optional) Language tags for picking `<switch>`/`systemLanguage` content (defaults to `["en"]`)
+  - `:antialias` - (optional) Set to `false` to render every edge without anti-aliasing (defaults to `true`)
+  - `:offset_x` / `:offset_y` - (optional) SVG point moved to the top-left corner of the output to pan the view (default to 0)
+  - `:zoom` - (optional) Magnification about the `:offset_x`/`:offset_y` point, keeping the output size (defaults to 1.0)
+  - `:view_box` - (optional) `{min_x, min_y, width, height}` window, in SVG user units, rendered in place of the SVG's own `viewBox`
+  - `:timeout_ms` - (optional) Fail with `:timeout` when the conversion takes longer; the native render keeps running in the background (defaults to no timeout)
+  - `:dither` - (optional) Floyd-Steinberg dither GIF output that needs more than 256 colors (defaults to `false`)
+  - `:max_pixels` - (optional) Largest number of pixels a conversion may allocate (defaults to 64 megapixels)
 
   ## Returns
 
   - `{:ok, binary_data}` - Binary image data on success
-  - `{:error, reason}` - Error message on failure
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
   """
-  @spec convert(String.t(), keyword()) :: {:ok, binary()} | {:error, String.t()}
+  @spec convert(binary(), keyword()) :: {:ok, binary()} | {:error, Converter.error()}
   defdelegate convert(svg_string, opts \\ []), to: Converter
+
+  @doc """
+  Converts SVG like `convert/2`, but also returns the rendered output dimensions, byte size
+  and parse warnings.
+
+  See `Svgager.Converter.convert_with_info/2` for detailed documentation.
+
+  ## Returns
+
+  - `{:ok, %{data: binary_data, width: width, height: height, byte_size: byte_size, warnings: warnings}}` - Binary image data, its dimensions, its length in bytes and warnings such as unresolved references or missing fonts on success
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec convert_with_info(binary(), keyword()) ::
+          {:ok, Converter.info()} | {:error, Converter.error()}
+  defdelegate convert_with_info(svg_string, opts \\ []), to: Converter
+
+  @doc """
+  Converts SVG like `convert/2` and writes the image straight to `path`.
+
+  See `Svgager.Converter.convert_to_file/3` for detailed documentation.
+
+  ## Returns
+
+  - `:ok` - The image was written to `path`
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec convert_to_file(binary(), Path.t(), keyword()) :: :ok | {:error, Converter.error()}
+  defdelegate convert_to_file(svg_string, path, opts \\ []), to: Converter
+
+  @doc """
+  Converts SVG like `convert/2` and returns the image as a base64 `data:` URI.
+
+  See `Svgager.Converter.convert_to_data_uri/2` for detailed documentation.
+
+  ## Returns
+
+  - `{:ok, data_uri}` - The `data:` URI as a string on success
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec convert_to_data_uri(binary(), keyword()) ::
+          {:ok, String.t()} | {:error, Converter.error()}
+  defdelegate convert_to_data_uri(svg_string, opts \\ []), to: Converter
+
+  @doc """
+  Renders SVG and returns the raw RGBA8 or RGB8 pixels, such as for `Nx.from_binary/2`.
+
+  See `Svgager.Converter.convert_to_rgba/2` for detailed documentation.
+
+  ## Returns
+
+  - `{:ok, %{data: pixels, width: width, height: height, channels: channels}}` - The unencoded pixel data and its shape on success
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec convert_to_rgba(binary(), keyword()) ::
+          {:ok, Converter.rgba_image()} | {:error, Converter.error()}
+  defdelegate convert_to_rgba(svg_string, opts \\ []), to: Converter
+
+  @doc """
+  Converts SVG to several formats, parsing and rendering it only once.
+
+  See `Svgager.Converter.convert_multi/3` for detailed documentation.
+
+  ## Returns
+
+  - `{:ok, outputs}` - A keyword list such as `[png: png_data, jpg: jpg_data]`, in the order of `formats`
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec convert_multi(binary(), [atom()], keyword()) ::
+          {:ok, [{atom(), binary()}]} | {:error, Converter.error()}
+  defdelegate convert_multi(svg_string, formats, opts \\ []), to: Converter
+
+  @doc """
+  Returns the MIME type of images converted to `format`.
+
+  See `Svgager.Converter.mime_type/1` for detailed documentation.
+
+  ## Returns
+
+  - `{:ok, mime_type}` - The canonical MIME type, such as `"image/png"`
+  - `{:error, {:unsupported_format, message}}` - The format isn't supported
+  """
+  @spec mime_type(atom()) :: {:ok, String.t()} | {:error, Converter.error()}
+  defdelegate mime_type(format), to: Converter
+
+  @doc """
+  Returns the file extension, without a dot, of images converted to `format`.
+
+  See `Svgager.Converter.extension/1` for detailed documentation.
+
+  ## Returns
+
+  - `{:ok, extension}` - The canonical extension, such as `"jpg"` for both `:jpg` and `:jpeg`
+  - `{:error, {:unsupported_format, message}}` - The format isn't supported
+  """
+  @spec extension(atom()) :: {:ok, String.t()} | {:error, Converter.error()}
+  defdelegate extension(format), to: Converter
+
+  @doc """
+  Returns the versions of the usvg, resvg, tiny_skia and image crates compiled into the NIF.
+
+  See `Svgager.Converter.versions/0` for detailed documentation.
+  """
+  @spec versions() :: %{
+          usvg: String.t(),
+          resvg: String.t(),
+          tiny_skia: String.t(),
+          image: String.t()
+        }
+  defdelegate versions(), to: Converter
+
+  @doc """
+  Returns the intrinsic size of an SVG without rendering it.
+
+  See `Svgager.Converter.intrinsic_size/2` for detailed documentation.
+
+  ## Returns
+
+  - `{:ok, {width, height}}` - The intrinsic width and height in pixels, as floats
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec intrinsic_size(binary(), keyword()) ::
+          {:ok, {float(), float()}} | {:error, Converter.error()}
+  defdelegate intrinsic_size(svg_string, opts \\ []), to: Converter
+
+  @doc """
+  Checks that an SVG can be parsed, without rendering it.
+
+  See `Svgager.Converter.validate/2` for detailed documentation.
+
+  ## Returns
+
+  - `:ok` - The SVG is valid
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec validate(binary(), keyword()) :: :ok | {:error, Converter.error()}
+  defdelegate validate(svg_string, opts \\ []), to: Converter
+
+  @doc """
+  Lists the ids of the elements in an SVG that can be rendered with `:node_id`.
+
+  See `Svgager.Converter.element_ids/2` for detailed documentation.
+
+  ## Returns
+
+  - `{:ok, ids}` - The element ids in document order
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec element_ids(binary(), keyword()) :: {:ok, [String.t()]} | {:error, Converter.error()}
+  defdelegate element_ids(svg_string, opts \\ []), to: Converter
+
+  @doc """
+  Counts the paths, groups, text nodes and images an SVG renders, without rendering it.
+
+  See `Svgager.Converter.complexity/2` for detailed documentation.
+
+  ## Returns
+
+  - `{:ok, counts}` - A map of `:paths`, `:groups`, `:texts` and `:images` counts
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec complexity(binary(), keyword()) ::
+          {:ok,
+           %{
+             paths: non_neg_integer(),
+             groups: non_neg_integer(),
+             texts: non_neg_integer(),
+             images: non_neg_integer()
+           }}
+          | {:error, Converter.error()}
+  defdelegate complexity(svg_string, opts \\ []), to: Converter
+
+  @doc """
+  Converts SVG to an ICO file with one square entry per size.
+
+  See `Svgager.Converter.convert_to_ico/2` for detailed documentation.
+
+  ## Options
+
+  - `:sizes` - (optional) Entry sizes in pixels, from 1 to 256 (defaults to `[16, 32, 48]`)
+
+  ## Returns
+
+  - `{:ok, ico_data}` - Binary ICO data on success
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec convert_to_ico(binary(), keyword()) :: {:ok, binary()} | {:error, Converter.error()}
+  defdelegate convert_to_ico(svg_string, opts \\ []), to: Converter
+
+  @doc """
+  Converts SVG to the standard favicon set: a multi-size ICO plus apple-touch and PWA PNGs.
+
+  See `Svgager.Converter.convert_to_favicon/2` for detailed documentation.
+
+  ## Returns
+
+  - `{:ok, favicon}` - Keyword list with `:favicon`, `:apple_touch_icon`, `:icon_192` and `:icon_512` entries
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec convert_to_favicon(binary(), keyword()) ::
+          {:ok, Converter.favicon()} | {:error, Converter.error()}
+  defdelegate convert_to_favicon(svg_string, opts \\ []), to: Converter
+
+  @doc """
+  Converts a sequence of SVG frames to a single animated GIF.
+
+  See `Svgager.Converter.convert_to_animated_gif/2` for detailed documentation.
+
+  ## Options
+
+  - `:delays` - (optional) Frame delay in milliseconds, or a list with one per frame (defaults to 100)
+  - `:loop` - (optional) `:infinite` or the number of repeats after the first play (defaults to `:infinite`)
+
+  ## Returns
+
+  - `{:ok, gif_data}` - Binary GIF data on success
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec convert_to_animated_gif([binary()], keyword()) ::
+          {:ok, binary()} | {:error, Converter.error()}
+  defdelegate convert_to_animated_gif(svg_frames, opts \\ []), to: Converter
+
+  @doc """
+  Renders a list of SVGs into one PNG sprite sheet and reports where each sprite is.
+
+  See `Svgager.Converter.convert_to_sprite_sheet/2` for detailed documentation.
+
+  ## Options
+
+  - `:cell_size` - (required) Cell size in pixels, as an integer or `{width, height}`
+  - `:columns` - (optional) Number of grid columns (defaults to a square-ish grid)
+  - `:gutter` - (optional) Transparent pixels between cells (defaults to 0)
+
+  ## Returns
+
+  - `{:ok, %{data: png_data, width: width, height: height, sprites: sprites}}` - The sheet and one `%{x, y, width, height}` rect per SVG
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec convert_to_sprite_sheet([binary()], keyword()) ::
+          {:ok, Converter.sprite_sheet()} | {:error, Converter.error()}
+  defdelegate convert_to_sprite_sheet(svgs, opts \\ []), to: Converter
+
+  @doc """
+  Renders an SVG once and slices the image into a grid of PNG tiles.
+
+  See `Svgager.Converter.convert_to_tiles/2` for detailed documentation.
+
+  ## Options
+
+  - `:tile_size` - (optional) Tile size in pixels, as an integer or `{width, height}` (defaults to 256)
+
+  ## Returns
+
+  - `{:ok, tiles}` - A list of `{{column, row}, png_data}` tuples, with edge tiles padded to the full tile size
+  - `{:error, {reason, message}}` - A `t:Svgager.Converter.error_reason/0` atom and a human-readable message on failure
+  """
+  @spec convert_to_tiles(binary(), keyword()) ::
+          {:ok, [Converter.tile()]} | {:error, Converter.error()}
+  defdelegate convert_to_tiles(svg_string, opts \\ []), to: Converter
+
+  @doc """
+  Converts a list of `{svg_string, opts}` jobs in parallel.
+
+  See `Svgager.Converter.convert_batch/2` for detailed documentation.
+
+  ## Options
+
+  - `:max_concurrency` - (optional) Maximum number of jobs rendered at once (defaults to the number of CPUs)
+
+  ## Returns
+
+  A list with one `{:ok, binary_data}` or `{:error, {reason, message}}` result per job, in order
+  """
+  @spec convert_batch([{binary(), keyword()}], keyword()) ::
+          [{:ok, binary()} | {:error, Converter.error()}] | {:error, Converter.error()}
+  defdelegate convert_batch(jobs, opts \\ []), to: Converter
 end
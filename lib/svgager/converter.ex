@@ -2,31 +2,200 @@ defmodule Svgager.Converter do
   @moduledoc """
   High-level API for converting SVG to various image formats.
 
-  Provides a user-friendly interface for converting SVG content to PNG, JPG, JPEG, GIF, or WebP
-  with support for resolution control, background colors, and preprocessing.
+  Provides a user-friendly interface for converting SVG content to PNG, JPG, JPEG, GIF, WebP,
+  BMP, TIFF, QOI, AVIF, TGA, PPM, farbfeld, or OpenEXR with support for resolution control,
+  background colors, and preprocessing.
   """
 
   alias Svgager.Native
 
-  @supported_formats [:png, :jpg, :jpeg, :gif, :webp]
+  require Logger
+
+  @supported_formats [
+    :png,
+    :jpg,
+    :jpeg,
+    :gif,
+    :webp,
+    :bmp,
+    :tiff,
+    :tif,
+    :qoi,
+    :avif,
+    :tga,
+    :pnm,
+    :ppm,
+    :ff,
+    :farbfeld,
+    :exr
+  ]
+  @fit_modes [:fill, :contain, :cover]
+  @png_compressions [:fast, :default, :best]
+  @subsamplings ["4:4:4", "4:2:2", "4:2:0"]
+  @shape_renderings [:optimize_speed, :crisp_edges, :geometric_precision]
+  @text_renderings [:optimize_speed, :optimize_legibility, :geometric_precision]
+  @image_renderings [
+    :optimize_quality,
+    :optimize_speed,
+    :smooth,
+    :high_quality,
+    :crisp_edges,
+    :pixelated
+  ]
+  @resize_filters [:nearest, :triangle, :catmull, :lanczos3]
+  @replacement_scopes [:any, :attr, :text]
+  @rotations [0, 90, 180, 270]
+  @default_ico_sizes [16, 32, 48]
+  @default_frame_delay 100
+  @default_tile_size 256
+  @max_ssaa 8
+
+  @type info :: %{
+          data: binary(),
+          width: pos_integer(),
+          height: pos_integer(),
+          byte_size: pos_integer(),
+          warnings: [String.t()]
+        }
+
+  @type rgba_image :: %{
+          data: binary(),
+          width: pos_integer(),
+          height: pos_integer(),
+          channels: 3 | 4
+        }
+
+  @type sprite :: %{
+          x: non_neg_integer(),
+          y: non_neg_integer(),
+          width: pos_integer(),
+          height: pos_integer()
+        }
+
+  @type sprite_sheet :: %{
+          data: binary(),
+          width: pos_integer(),
+          height: pos_integer(),
+          sprites: [sprite()]
+        }
+
+  @type tile :: {{non_neg_integer(), non_neg_integer()}, binary()}
+
+  @type favicon :: [
+          favicon: binary(),
+          apple_touch_icon: binary(),
+          icon_192: binary(),
+          icon_512: binary()
+        ]
+
+  @typedoc """
+  Machine-readable failure reason, returned alongside a human-readable message.
+
+  - `:parse_failed` - the input couldn't be decompressed, decoded or parsed as SVG
+  - `:unsupported_format` - the output format isn't supported
+  - `:invalid_color` - a background color, the `:default_fill`, a `:color_remap` color or a `:palette` color couldn't be parsed
+  - `:invalid_dimensions` - the output size is invalid or can't be derived from the SVG
+  - `:invalid_option` - any other option has an invalid value
+  - `:node_not_found` - no element matches `:node_id`
+  - `:render_failed` - rendering the SVG failed, including a panic caught inside the native code
+  - `:encode_failed` - encoding the rendered image failed
+  - `:write_failed` - writing the output file failed, such as when its directory doesn't exist
+  - `:timeout` - the conversion didn't finish within `:timeout_ms`
+  """
+  @type error_reason ::
+          :parse_failed
+          | :unsupported_format
+          | :invalid_color
+          | :invalid_dimensions
+          | :invalid_option
+          | :node_not_found
+          | :render_failed
+          | :encode_failed
+          | :write_failed
+          | :timeout
+
+  @type error :: {error_reason(), String.t()}
 
   @doc """
   Converts SVG to the specified image format and returns binary data.
 
+  The input may be SVG markup or gzip-compressed SVGZ data, which is detected by its magic
+  bytes and decompressed before replacements are applied. The binary is passed to the NIF
+  without copying and must decode to valid UTF-8, otherwise an error is returned.
+
   ## Options
 
-  - `:format` - (required) Output format. One of `:png`, `:jpg`, `:jpeg`, `:gif`, or `:webp`
-  - `:width` - (optional) Output width in pixels. If only width is provided, height is calculated to maintain aspect ratio
-  - `:height` - (optional) Output height in pixels. If only height is provided, width is calculated to maintain aspect ratio
-  - `:background_color` - (optional) Background color as hex string (e.g., "FFFFFF" or "#FF0000"). Ignored for PNG format which uses transparency. Defaults to "FFFFFF" (white) for other formats
-  - `:replacements` - (optional) Map of string replacements to apply to SVG before conversion (e.g., `%{"#000000" => "#FF0000"}`)
+  - `:format` - (required) Output format. One of `:png`, `:jpg`, `:jpeg`, `:gif`, `:webp`, `:bmp`, `:tiff`, `:tif`, `:qoi`, `:avif`, `:tga`, `:pnm`, `:ppm`, `:ff`, `:farbfeld`, or `:exr`
+  - `:width` - (optional) Output width in pixels. If only width is provided, height is calculated to maintain aspect ratio, rounded to the nearest pixel. Can also be a string: a number of pixels such as `"800"`, or a percentage of the intrinsic width such as `"150%"`, rounded to the nearest pixel
+  - `:height` - (optional) Output height in pixels. If only height is provided, width is calculated to maintain aspect ratio, rounded to the nearest pixel. Like `:width`, it can be a string of pixels or a percentage of the intrinsic height; a percentage and a pixel size can be mixed, each dimension scaling independently
+  - `:background_color` - (optional) Background color as a CSS color name (case-insensitive, including "transparent"), an `rgb(r, g, b)` / `rgba(r, g, b, a)` string with alpha from 0.0 to 1.0, or a hex string in `RGB`, `RRGGBB` or `RRGGBBAA` form, with or without a leading `#` (e.g., "white", "rgb(255, 128, 0)", "FFF", "#FF0000" or "FF000080"). Ignored for PNG, TIFF, QOI, TGA, farbfeld, EXR and GIF formats which use transparency, unless `:flatten` is set. JPEG, BMP and PPM have no alpha channel, so they use the color at full opacity. Defaults to "FFFFFF" (white) for other formats
+  - `:replacements` - (optional) Map of string replacements to apply to SVG before conversion (e.g., `%{"#000000" => "#FF0000"}`). May also be a list of `{search, replace}` or `{search, replace, max}` tuples, which are applied in order; `max` limits how many occurrences are replaced, starting from the first (e.g., `[{"<title>Old", "<title>New", 1}]`). A scope of `:attr` or `:text` may follow, as in `{search, replace, :text}` or `{search, replace, max, :text}`, to only replace occurrences inside attribute values or inside element text (including CDATA sections), so a color token in a `<text>` label doesn't also rewrite an `id` that happens to contain it. Scoped replacements find attribute values and text with a lightweight scan of the markup, and a match never spans two of them; the default `:any` replaces anywhere in the SVG
+  - `:regex_replacements` - (optional) Map or list of regular expression replacements applied after `:replacements`, in the same forms as `:replacements`. Each key is compiled as a Rust `regex` pattern and every match is replaced; the replacement may reference capture groups as `$1` or `${name}` (e.g., `%{~S(fill="#[0-9a-fA-F]{6}") => ~S(fill="#FF0000")}`)
+  - `:escape_replacements` - (optional) When `true`, the replacement values of `:replacements` and `:regex_replacements` are XML-escaped before they are substituted, turning `&`, `<`, `>`, `"` and `'` into entity references. Use it to inject user-provided text into `<text>` nodes or attribute values without breaking the markup. Search strings and regex patterns are not escaped. Defaults to `false`
+  - `:ignore_case_replacements` - (optional) When `true`, the search strings of `:replacements` match regardless of ASCII case, so `"{{name}}"` also replaces `{{Name}}` and `{{NAME}}`; the rest of the SVG keeps its case. Each replacement then searches a lowercased copy of the whole SVG, which costs an extra copy per replacement but is still cheaper than a case-insensitive `:regex_replacements` pattern. Non-ASCII letters must match exactly. Defaults to `false`
+  - `:resource_dir` - (optional) Directory that `<image>` elements referencing files by a relative `href`, such as `href="./logo.png"`, are loaded from. An `href` that is an absolute path or resolves outside of the directory, through `..` or a symlink, is refused, so untrusted SVGs can't read other files; each refused or unreadable image is left out and reported in the warnings of `convert_with_info/2`. Files are read while parsing, so with `:cache` a tree keeps the images read when it was first parsed. Returns `:invalid_option` if the directory doesn't exist. Without it, relative paths are resolved against the current working directory with no restriction. Defaults to none
+  - `:css_vars` - (optional) Map or list of `{name, value}` CSS custom properties, such as `%{"--brand" => "#FF5500"}`, that override the ones the SVG declares (the leading `--` is optional). The renderer doesn't support `var()` itself, so after replacements every `var(--name)` or `var(--name, fallback)` reference in style sheets, `style` attributes and presentation attributes is substituted with the property's value: the override, else the SVG's own declaration (a declaration applies to the whole document and the last one wins, rather than following the cascade), else the fallback. A reference that resolves to nothing is left as it is and ignored by the renderer. Defaults to none
+  - `:quality` - (optional) Encoding quality from 1 to 100 for JPEG, AVIF and lossy WebP output. Defaults to 90. Ignored for other formats
+  - `:lossless` - (optional) Whether WebP output is encoded losslessly, which keeps logos and flat artwork pixel-exact. Set it to `false` for smaller lossy WebP files that honor `:quality`, at up to 16383x16383 pixels. Ignored for other formats. Defaults to `true`
+  - `:fit` - (optional) How the SVG is fitted when both `:width` and `:height` are provided. One of `:fill` (stretch to the box, the default), `:contain` (scale uniformly to fit inside the box, centered, with the background color in the unused area) or `:cover` (scale uniformly to fill the box, centered, cropping the overflow). Without `:fit` (or `:preserve_aspect`), an SVG whose root element declares `preserveAspectRatio` together with a `viewBox` is fitted the way that attribute asks, as in a browser: `xMidYMid meet` letterboxes like `:contain`, `slice` crops like `:cover`, the `xMin`/`YMax` variants align to that edge, and `none` stretches
+  - `:preserve_aspect` - (optional) When `true` and both `:width` and `:height` are provided, the SVG is scaled without distortion and centered in the box, with the background color in the unused area. This is shorthand for `fit: :contain` and can't be combined with another `:fit`. Defaults to `false`
+  - `:png_compression` - (optional) PNG compression level. One of `:fast` (the default, quickest to encode), `:default` or `:best` (smallest files, slowest to encode). Ignored for other formats
+  - `:background_gradient` - (optional) `{top, bottom}` tuple of colors, in any form `:background_color` accepts, for a vertical linear gradient behind the SVG, such as `{"#FFFFFF", "#D0E4F5"}`. The first row is exactly the top color and the last row the bottom color. It applies to every format, including PNG, TIFF, QOI, TGA, farbfeld, EXR and GIF without `:flatten`, and overrides `:background_color`. The gradient spans the whole output: `:trim` cuts transparent margins before it's drawn and `:padding` is covered by it
+  - `:flatten` - (optional) When `true`, PNG, TIFF, QOI, TGA, farbfeld, EXR and GIF output is rendered over `:background_color` instead of staying transparent. Defaults to `false`
+  - `:default_fill` - (optional) Color, in any form `:background_color` accepts, that shapes and text without a fill of their own are painted in instead of black, such as `"white"` for dark-mode exports. It is set as a `fill` attribute on the root `<svg>` element, so everything that inherits its fill picks it up while explicit fills, `fill="none"` and style sheet rules are kept. An SVG whose root already sets a fill is left as it is. Returns `:invalid_color` for an unparseable color. Defaults to none (black)
+  - `:color_remap` - (optional) List of `{from, to, tolerance}` tuples that recolor near-duplicate shades, such as `[{"#FF0000", "#E10600", 8}]` to normalize every red within 8 of `#FF0000` to one brand red. Each `fill`, `stroke` and gradient `stop-color` color, whether a presentation attribute or in a `style` attribute or style sheet, whose red, green, blue and alpha channels are all within `tolerance` (0 to 255) of `from` is replaced by `to`; the first matching tuple wins. Colors are compared after replacements, `:css_vars` and `:default_fill`, and references such as `url(#gradient)`, `none` and `currentColor` are left alone, though a referenced gradient's stops are recolored. Both colors take any form `:background_color` accepts, returning `:invalid_color` otherwise. Defaults to none
+  - `:font_family` - (optional) Font family used for text that doesn't set its own `font-family`. Defaults to "Times New Roman"
+  - `:font_size` - (optional) Font size used for text that doesn't set its own `font-size`, as a positive number. Defaults to 12
+  - `:load_system_fonts` - (optional) Whether system fonts are available for text rendering. They are loaded once per process on first use. Set to `false` for output that doesn't depend on the host's fonts, in which case text is only rendered with fonts from `:font_dirs`. Defaults to `true`
+  - `:font_dirs` - (optional) List of directories to load additional fonts from, such as bundled brand fonts. Directories that don't exist are skipped with a logged warning
+  - `:node_id` - (optional) `id` of a single element to render, such as one icon from a sprite sheet. Only that element is drawn and the output is sized from its bounding box instead of the whole document
+  - `:rotate` - (optional) Clockwise rotation of the output in degrees, one of `0`, `90`, `180` or `270`. `:width` and `:height` describe the unrotated image, so 90 and 270 swap the output dimensions. Defaults to `0`
+  - `:flip_h` - (optional) When `true`, the output is mirrored horizontally. Defaults to `false`
+  - `:flip_v` - (optional) When `true`, the output is mirrored vertically. Flips are applied before `:rotate`. Defaults to `false`
+  - `:trim` - (optional) When `true`, empty margins are cut off the rendered image: transparent pixels for PNG, TIFF, QOI, TGA, farbfeld, EXR and GIF, or pixels matching the background color otherwise. An image with no content is returned unchanged. Defaults to `false`
+  - `:crop` - (optional) `{x, y, width, height}` rectangle, in output pixels, to cut out of the rendered image (after `:trim`). The rectangle is clamped to the image bounds; an error is returned if it doesn't overlap the image at all
+  - `:padding` - (optional) Number of pixels added on every side of the output (after `:trim` and `:crop`), filled with the background color, or transparent for PNG, TIFF, QOI, TGA, farbfeld, EXR and GIF. The output is `2 * padding` larger than the requested size
+  - `:dpi` - (optional) Resolution used to convert physical units such as `mm`, `cm` and `pt` in the SVG to pixels, which determines the output size when `:width` and `:height` are omitted. Defaults to 96
+  - `:cache` - (optional) When `true`, the parsed SVG is kept in a process-wide cache of the 32 most recently used documents and reused by later conversions of the same SVG (after replacements) with the same font, `:dpi` and rendering hint settings, such as rendering one SVG at several sizes or formats. Defaults to `false`
+  - `:strict` - (optional) When `true`, anything `convert_with_info/2` would warn about, such as a `url(#gradient)` reference to a missing element, a `font-family` with no available font or an image that can't be loaded, fails the conversion with `:parse_failed` and a message listing every problem, instead of rendering without it. Use it to catch broken templates in QA. Also applies to `validate/2`. Defaults to `false`
+  - `:ssaa` - (optional) Supersampling factor from 1 to 8. The SVG is rendered at that many times the output resolution and downsampled with a Lanczos3 filter (see `:resize_filter`), which smooths thin strokes and edges at small sizes, including `shape-rendering="crispEdges"` shapes. Memory use and render time grow with the square of the factor. Defaults to 1 (no supersampling)
+  - `:resize_filter` - (optional) Filter the `:ssaa` render is downsampled with: `:lanczos3` (the sharpest, the default), `:catmull` (Catmull-Rom, nearly as sharp and faster), `:triangle` (bilinear, softer) or `:nearest` (picks one sample per pixel, which is fastest but keeps the jagged edges supersampling is meant to smooth). Only has an effect with `:ssaa` above 1, since other renders draw the vectors at the output size directly
+  - `:grayscale` - (optional) When `true`, every pixel is converted to its luminance before encoding, keeping its transparency. JPEG output is written as a single-channel grayscale JPEG; other formats keep their usual channels with equal red, green and blue values. Defaults to `false`
+  - `:palette` - (optional) List of colors, in any form `:background_color` accepts, that every pixel is mapped to the nearest of (by distance in RGB) after rendering, such as `["black", "#555555", "#AAAAAA", "white"]` for a four-level e-ink display. Alpha is kept, and it applies after `:grayscale` to every format, including `convert_to_rgba/2`, `convert_to_file/3` and the frames of `convert_to_animated_gif/2`; lossy JPEG and AVIF compression brings back colors outside the palette, so use a lossless format when the pixels must match exactly. Returns `:invalid_color` for an unparseable color. Defaults to none
+  - `:palette_dither` - (optional) When `true` with a `:palette`, an ordered (Bayer) dither is applied before each pixel is mapped, so gradients between palette colors become a fine pattern of both instead of flat bands. Defaults to `false`
+  - `:opacity` - (optional) Number from 0.0 to 1.0 the alpha of the rendered content is multiplied by before it is composited over the background, such as for a faded watermark. The background itself stays opaque, so use `:flatten` with `:background_color` for a faded overlay in PNG output. Defaults to 1.0
+  - `:bit_depth` - (optional) Bits per channel, `8` or `16`. 16-bit output is only supported for PNG; each 8-bit channel is widened exactly, for tools and pipelines that expect 16-bit input. Defaults to `8`
+  - `:scale` - (optional) Positive number the SVG's intrinsic size is multiplied by, such as `2.0` for a retina export, instead of giving `:width` or `:height`, with the result rounded to the nearest pixel. It can't be combined with either of them. Defaults to 1.0
+  - `:dpr` - (optional) Device pixel ratio, such as `2.0` for a high-density screen, that turns CSS pixels into physical pixels. The SVG's intrinsic size is its CSS size, so `dpr: 2.0` renders a `width="100"` SVG 200 pixels wide, and a `:width` or `:height` percentage is taken of the intrinsic size at that ratio: `width: "50%", dpr: 2.0` gives 100 pixels. An explicit pixel `:width` or `:height` wins and is used as is, already being physical. Can't be combined with `:scale`. Defaults to 1.0
+  - `:progressive` - (optional) When `true`, JPEG output is written as a progressive JPEG, which browsers can show at low detail while it is still loading. Ignored for other formats. Defaults to `false` (baseline JPEG)
+  - `:subsampling` - (optional) JPEG chroma subsampling, one of `"4:4:4"` (full color resolution, the sharpest colored edges and text), `"4:2:2"` (half horizontal color resolution) or `"4:2:0"` (half horizontal and vertical color resolution, the smallest files). Ignored for other formats. Defaults to `"4:4:4"`
+  - `:metadata` - (optional) Map or list of `{keyword, text}` pairs written to PNG output as text chunks, such as `%{"Title" => "Logo", "Author" => "Jane Doe"}`: a `tEXt` chunk when the text is Latin-1 and an `iTXt` (UTF-8) chunk otherwise. Keywords must be 1 to 79 printable Latin-1 characters. A list keeps its order, and atom keywords are converted to strings. Only supported for PNG; giving metadata with another format returns `:invalid_option`. Defaults to none
+  - `:dpi_output` - (optional) Physical resolution recorded in PNG output as a `pHYs` chunk, in dots per inch (stored as pixels per meter), for print workflows that require one. Unlike `:dpi` it doesn't change how the SVG is rendered or the output size. Only supported for PNG; giving it with another format returns `:invalid_option`. Defaults to no `pHYs` chunk
+  - `:icc_profile` - (optional) Binary ICC profile embedded in PNG output as an `iCCP` chunk and in JPEG output as APP2 segments, so color-managed tools interpret the colors as intended. It must be a valid RGB profile. The pixels are written unchanged, as rendered in sRGB, so the profile should describe sRGB-compatible data unless other tools are meant to reinterpret them. Only supported for PNG and JPEG (not grayscale JPEG); other formats return `:invalid_option`. Defaults to no profile
+  - `:srgb` - (optional) When `true`, PNG output is tagged with an `sRGB` chunk and JPEG output embeds an sRGB ICC profile, declaring the color space the SVG is rendered in. Can't be combined with `:icc_profile` and, like it, only applies to PNG and JPEG. Defaults to `false`
+  - `:shape_rendering` - (optional) Default rendering of shapes whose `shape-rendering` is `auto` (or unset): `:geometric_precision` (anti-aliased, the default), `:crisp_edges` or `:optimize_speed` (both without anti-aliasing, for pixel-crisp diagrams and pixel art)
+  - `:text_rendering` - (optional) Default rendering of text whose `text-rendering` is `auto` (or unset): `:optimize_legibility` (the default), `:optimize_speed` (no anti-aliasing) or `:geometric_precision`
+  - `:image_rendering` - (optional) Default scaling of embedded raster images whose `image-rendering` is `auto` (or unset): `:optimize_quality` (smooth, the default), `:optimize_speed` (nearest neighbor, keeping pixels sharp), `:smooth`, `:high_quality`, `:crisp_edges` or `:pixelated`
+  - `:languages` - (optional) List of BCP 47 language tags, such as `["de", "en"]`, used to pick the content of `<switch>` elements: the first child whose `systemLanguage` contains one of the tags, or a tag followed by a subtag (`"de"` matches `systemLanguage="de-AT"`), is rendered. Defaults to `["en"]`
+  - `:antialias` - (optional) When `false`, every shape and glyph is rendered without anti-aliasing, so each pixel is either fully covered or untouched, for icon sheets and pixel art aligned to the pixel grid. This overrides `:shape_rendering`, `:text_rendering` and any `shape-rendering` or `text-rendering` in the SVG. Supersampling with `:ssaa` blends edges again when downsampling. Defaults to `true`
+  - `:offset_x` / `:offset_y` - (optional) Point of the SVG, in user units, moved to the top-left corner of the output to pan the view (relative to the element's bounding box with `:node_id`). Applied before `:zoom` and the scale that fits the SVG to the output size; content moved off the canvas is clipped. Default to 0
+  - `:zoom` - (optional) Positive number the SVG is magnified by about the `:offset_x`/`:offset_y` point, on top of the scale that fits it to the output size, such as `2.0` with offsets of a quarter of the SVG's size to enlarge its middle. The output size doesn't change, so this renders a zoomed-in region without a `:crop`. Defaults to 1.0
+  - `:view_box` - (optional) `{min_x, min_y, width, height}` window of the SVG's coordinate space, in user units, rendered in place of its own `viewBox`, such as `{50, 0, 50, 100}` for the right half of a `viewBox="0 0 100 100"` SVG. It pans and zooms in vector space, so unlike `:crop` nothing is rendered outside the window and the edges stay sharp. Without `:width` and `:height` the output is the window's size at the SVG's own scale; with them the window is fitted to the box as with `:fit`. Can't be combined with `:node_id`
+  - `:timeout_ms` - (optional) Positive number of milliseconds a conversion may take before it fails with `:timeout`, which keeps a pathological SVG from tying up the caller. The conversion then runs on a separate native thread, which costs a copy of the SVG and skips the pixmap reuse of `convert_batch/2`. resvg can't be interrupted part way, so a conversion that times out keeps running on its thread until it finishes, still using CPU and memory, and its result is discarded. Applies to `convert/2`, `convert_with_info/2`, `convert_to_data_uri/2`, `convert_to_file/3` and each `convert_batch/2` job; other functions ignore it. Defaults to no timeout
+  - `:dither` - (optional) When `true`, GIF output with more colors than fit in its 256-color palette is Floyd-Steinberg dithered, which avoids visible banding in gradients and anti-aliased edges at the cost of a larger file. Images that fit the palette are encoded exactly either way. Ignored for other formats. Defaults to `false`
+  - `:alpha_threshold` - (optional) Alpha from 1 to 255 below which a pixel becomes transparent in formats with on/off transparency; pixels at or above it are made fully opaque. Defaults to 128 for GIF output. ICO entries keep smooth alpha unless it's given, in which case they're thresholded the same way. Raising it makes more of the anti-aliased edge pixels transparent, lowering it keeps more of them opaque. Ignored for other formats
+  - `:max_pixels` - (optional) Largest number of pixels a conversion may allocate, checked before any image memory is allocated, so a hostile or mistaken size fails fast with `:invalid_dimensions` instead of exhausting memory. Supersampling counts toward the limit, as does `:padding`. Defaults to 67,108,864 (64 megapixels)
+
+  When both `:width` and `:height` are provided, the output uses exact dimensions (may distort if aspect ratio doesn't match original unless `:fit` is `:contain` or `:cover`).
 
-  When both `:width` and `:height` are provided, the output uses exact dimensions (may distort if aspect ratio doesn't match original).
+  Otherwise the SVG's intrinsic size is used. If its `width` or `height` is missing or a percentage, the `viewBox` size is used instead; when there is no valid `viewBox` either, an error asks for an explicit `:width` and `:height`.
+
+  PNG output is reproducible: the same SVG and options give byte-identical files on every call and machine, as long as the same fonts are available. No timestamp chunk is written and row filters are chosen from the pixels alone, so PNGs can be compared byte for byte, for example in tests.
 
   ## Returns
 
   - `{:ok, binary_data}` - Binary image data on success
-  - `{:error, reason}` - Error message on failure
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
 
   ## Examples
 
@@ -54,49 +223,956 @@ defmodule Svgager.Converter do
         background_color: "FFFFFF"
       )
   """
-  @spec convert(String.t(), keyword()) :: {:ok, binary()} | {:error, String.t()}
+  @spec convert(binary(), keyword()) :: {:ok, binary()} | {:error, error()}
   def convert(svg_string, opts \\ []) when is_binary(svg_string) do
     with :ok <- validate_opts(opts),
-         {:ok, format, width, height, bg_color, replacements} <- parse_opts(opts) do
-      Native.convert_svg(
-        svg_string,
-        format,
-        width,
-        height,
-        bg_color,
-        replacements
-      )
+         {:ok, options} <- parse_opts(opts) do
+      Native.convert_svg(svg_string, options)
+    end
+  end
+
+  @doc """
+  Converts SVG like `convert/2`, but also returns the rendered output dimensions and the
+  encoded size in bytes.
+
+  This saves decoding the result when only one of `:width` or `:height` is given and the
+  other is derived from the SVG's aspect ratio, and the byte size can be sent as an HTTP
+  `Content-Length` as-is. Accepts the same options as `convert/2`.
+
+  The info also lists warnings about content that was rendered differently than written,
+  because usvg drops what it can't resolve without failing the conversion. Every `url(#id)`
+  or `href="#id"` reference to a missing element is reported, such as a fill pointing at an
+  undefined gradient, as is every `font-family` list with no available font, saying whether
+  a fallback font was used or the text was left out. Embedded `data:` URL images are
+  recognized by their content, so a PNG, JPEG, GIF or WebP renders even when its declared
+  MIME type is generic or wrong, and each one that can't be decoded is reported and left
+  out. The list is empty for a faithful render, and with `strict: true` any warning fails
+  the conversion instead.
+
+  ## Returns
+
+  - `{:ok, %{data: binary_data, width: width, height: height, byte_size: byte_size, warnings: warnings}}` - Binary image data, its dimensions, its length in bytes and a list of warning strings on success
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
+
+  ## Examples
+
+      # Height is derived from the SVG's aspect ratio
+      {:ok, %{data: png_data, width: 800, height: height}} =
+        Svgager.Converter.convert_with_info(svg_string, format: :png, width: 800)
+
+      # A fill pointing at a gradient that isn't defined
+      {:ok, %{warnings: [~S(Unresolved reference to "#glow": no element has that id)]}} =
+        Svgager.Converter.convert_with_info(~S(<svg ...><rect fill="url(#glow)"/></svg>))
+  """
+  @spec convert_with_info(binary(), keyword()) :: {:ok, info()} | {:error, error()}
+  def convert_with_info(svg_string, opts \\ []) when is_binary(svg_string) do
+    with :ok <- validate_opts(opts),
+         {:ok, options} <- parse_opts(opts),
+         {:ok, {data, out_width, out_height, warnings}} <-
+           Native.convert_svg_with_info(svg_string, options) do
+      {:ok,
+       %{
+         data: data,
+         width: out_width,
+         height: out_height,
+         byte_size: byte_size(data),
+         warnings: warnings
+       }}
+    end
+  end
+
+  @doc """
+  Converts SVG like `convert/2` and writes the image straight to `path`.
+
+  The encoded image is written from the native code without being copied into the BEAM, and
+  an existing file at `path` is replaced. The parent directory must already exist. Accepts
+  the same options as `convert/2`.
+
+  PNG output is streamed: rows are encoded and written to the file as they go through a
+  buffer of one row or 64 KiB, whichever is larger, so peak memory is the rendered image
+  (plus any `:ssaa` or filter layers) and never the encoded file as well. This makes it the
+  better choice for very large exports. Other formats are encoded in memory and then written.
+  A conversion that fails part way removes the partial file.
+
+  ## Returns
+
+  - `:ok` - The image was written to `path`
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure, with `:write_failed` when the file couldn't be written
+
+  ## Examples
+
+      :ok = Svgager.Converter.convert_to_file(svg_string, "output.png", format: :png, width: 800)
+
+      {:error, {:write_failed, message}} =
+        Svgager.Converter.convert_to_file(svg_string, "missing/output.png", format: :png)
+  """
+  @spec convert_to_file(binary(), Path.t(), keyword()) :: :ok | {:error, error()}
+  def convert_to_file(svg_string, path, opts \\ []) when is_binary(svg_string) do
+    with :ok <- validate_path(path),
+         :ok <- validate_opts(opts),
+         {:ok, options} <- parse_opts(opts) do
+      Native.convert_svg_to_file(svg_string, options, path)
+    end
+  end
+
+  defp validate_path(path) when is_binary(path) and path != "", do: :ok
+
+  defp validate_path(path) do
+    invalid_option("path must be a non-empty string, got: #{inspect(path)}")
+  end
+
+  @doc """
+  Converts SVG like `convert/2` and returns the image as a base64 `data:` URI.
+
+  The URI starts with the MIME type of the output format, such as `data:image/png;base64,`
+  or `data:image/jpeg;base64,` for `:jpg`, so it can be used directly as an `<img>` `src`.
+  Accepts the same options as `convert/2`.
+
+  ## Returns
+
+  - `{:ok, data_uri}` - The `data:` URI as a string on success
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
+
+  ## Examples
+
+      {:ok, "data:image/png;base64," <> _ = src} =
+        Svgager.Converter.convert_to_data_uri(svg_string, format: :png, width: 32)
+  """
+  @spec convert_to_data_uri(binary(), keyword()) :: {:ok, String.t()} | {:error, error()}
+  def convert_to_data_uri(svg_string, opts \\ []) when is_binary(svg_string) do
+    with :ok <- validate_opts(opts),
+         {:ok, options} <- parse_opts(opts) do
+      Native.convert_svg_to_data_uri(svg_string, options)
+    end
+  end
+
+  @doc """
+  Renders SVG like `convert/2` and returns the raw pixels instead of an encoded image.
+
+  Skipping the encoder makes this the fastest way to get at the pixels, such as to feed them
+  to `Nx.from_binary/2`: the data is 8 bits per channel, row by row from the top-left pixel,
+  with no padding between rows. Accepts the same options as `convert/2`, except that
+  `:format` is ignored and the encoding options don't apply; `:grayscale` still sets the
+  color channels to the luminance, and `:palette` still maps them to the palette.
+
+  ## Options
+
+  - `:channels` - (optional) `4` for RGBA, the default, or `3` for RGB. RGB output is
+    flattened onto `:background_color` (white by default) before the alpha channel is dropped
+  - `:premultiplied` - (optional) When `true`, RGBA color channels are left premultiplied by
+    alpha, as rendered, rather than converted to straight alpha. Defaults to `false`
+
+  ## Returns
+
+  - `{:ok, %{data: pixels, width: width, height: height, channels: channels}}` - The pixel
+    data, `width * height * channels` bytes long, and its shape on success
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
+
+  ## Examples
+
+      {:ok, %{data: data, width: width, height: height, channels: channels}} =
+        Svgager.Converter.convert_to_rgba(svg_string, width: 224, height: 224, channels: 3)
+
+      tensor = data |> Nx.from_binary(:u8) |> Nx.reshape({height, width, channels})
+  """
+  @spec convert_to_rgba(binary(), keyword()) :: {:ok, rgba_image()} | {:error, error()}
+  def convert_to_rgba(svg_string, opts \\ []) when is_binary(svg_string) do
+    channels = Keyword.get(opts, :channels, 4)
+    premultiplied = Keyword.get(opts, :premultiplied, false)
+
+    with :ok <- validate_channels(channels),
+         :ok <- validate_boolean(:premultiplied, premultiplied),
+         {:ok, options} <- parse_opts(Keyword.put(opts, :format, :png)),
+         {:ok, {data, width, height, channels}} <-
+           Native.convert_svg_to_rgba(svg_string, options, channels, premultiplied) do
+      {:ok, %{data: data, width: width, height: height, channels: channels}}
+    end
+  end
+
+  defp validate_channels(channels) when channels in [3, 4], do: :ok
+
+  defp validate_channels(channels) do
+    invalid_option("channels must be 3 or 4, got: #{inspect(channels)}")
+  end
+
+  @doc """
+  Converts SVG to several formats at once, rendering it a single time.
+
+  Parsing and rendering usually dominate a conversion, so this is considerably faster than
+  calling `convert/2` once per format: the SVG is parsed and rendered once and only the
+  encoding is repeated. Accepts the same options as `convert/2`, except that `:format` is
+  ignored in favor of `formats`.
+
+  Transparency is handled per format as in `convert/2`: formats that keep it are encoded
+  from the transparent render, and the others from a copy flattened onto
+  `:background_color`, so `[:png, :jpg]` gives a transparent PNG and a JPEG on white. With
+  `:trim`, transparent margins are trimmed for every format.
+
+  ## Returns
+
+  - `{:ok, outputs}` - A keyword list of `{format, binary_data}` entries in the order of
+    `formats`, such as `[png: png_data, jpg: jpg_data]`
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
+
+  ## Examples
+
+      {:ok, [png: png_data, webp: webp_data]} =
+        Svgager.Converter.convert_multi(svg_string, [:png, :webp], width: 800)
+  """
+  @spec convert_multi(binary(), [atom()], keyword()) ::
+          {:ok, [{atom(), binary()}]} | {:error, error()}
+  def convert_multi(svg_string, formats, opts \\ []) when is_binary(svg_string) do
+    with :ok <- validate_formats(formats),
+         {:ok, options} <- parse_opts(Keyword.put(opts, :format, :png)),
+         {:ok, outputs} <-
+           Native.convert_svg_multi(svg_string, options, Enum.map(formats, &Atom.to_string/1)) do
+      {:ok, Enum.map(outputs, fn {format, data} -> {String.to_existing_atom(format), data} end)}
+    end
+  end
+
+  defp validate_formats([_ | _] = formats) do
+    case Enum.find(formats, &(&1 not in @supported_formats)) do
+      nil -> :ok
+      format -> validate_opts(format: format)
+    end
+  end
+
+  defp validate_formats(formats) do
+    invalid_option("formats must be a non-empty list of formats, got: #{inspect(formats)}")
+  end
+
+  @doc """
+  Returns the MIME type of images converted to `format`, for example to set a
+  `Content-Type` header.
+
+  Takes the same format atoms as the `:format` option of `convert/2`, and returns the same
+  `:unsupported_format` error for anything else.
+
+  ## Returns
+
+  - `{:ok, mime_type}` - The canonical MIME type, such as `"image/png"` or `"image/jpeg"`
+  - `{:error, {:unsupported_format, message}}` - The format isn't supported
+
+  ## Examples
+
+      {:ok, "image/jpeg"} = Svgager.Converter.mime_type(:jpg)
+  """
+  @spec mime_type(atom()) :: {:ok, String.t()} | {:error, error()}
+  def mime_type(format) do
+    with :ok <- validate_opts(format: format) do
+      Native.mime_for_format(Atom.to_string(format))
+    end
+  end
+
+  @doc """
+  Returns the file extension of images converted to `format`, without a leading dot, for
+  example to name downloads.
+
+  Aliases of a format share its canonical extension: `:jpeg` and `:jpg` both give `"jpg"`,
+  `:tif` gives `"tiff"`, `:farbfeld` gives `"ff"` and `:pnm` gives `"ppm"`. Takes the same
+  format atoms as the `:format` option of `convert/2`, and returns the same
+  `:unsupported_format` error for anything else.
+
+  ## Returns
+
+  - `{:ok, extension}` - The canonical extension, such as `"png"` or `"jpg"`
+  - `{:error, {:unsupported_format, message}}` - The format isn't supported
+
+  ## Examples
+
+      {:ok, extension} = Svgager.Converter.extension(:jpeg)
+      filename = "logo." <> extension
+  """
+  @spec extension(atom()) :: {:ok, String.t()} | {:error, error()}
+  def extension(format) do
+    with :ok <- validate_opts(format: format) do
+      Native.extension_for_format(Atom.to_string(format))
+    end
+  end
+
+  @doc """
+  Returns the versions of the Rust crates doing the rendering, as compiled into the NIF.
+
+  Rendering differences between deployments usually come down to these versions, so this
+  is handy in bug reports and health checks. The map has the `:usvg`, `:resvg`,
+  `:tiny_skia` and `:image` keys, each with a version string such as `"0.47.0"`, or
+  `"unknown"` in the unlikely case the build couldn't determine it.
+
+  ## Examples
+
+      Svgager.Converter.versions()
+      # %{usvg: "0.47.0", resvg: "0.47.0", tiny_skia: "0.12.0", image: "0.25.9"}
+  """
+  @spec versions() :: %{
+          usvg: String.t(),
+          resvg: String.t(),
+          tiny_skia: String.t(),
+          image: String.t()
+        }
+  def versions, do: Native.versions()
+
+  @doc """
+  Returns the intrinsic size of an SVG without rendering it.
+
+  This is the size `convert/2` renders at when neither `:width` nor `:height` is given
+  (before rounding to whole pixels), so it can be used to choose output dimensions up
+  front. Replacements, fonts and `:dpi` apply as in `convert/2`; `:format`, `:width`,
+  `:height` and `:node_id` are ignored. When the root `width` or `height` is missing or a
+  percentage, the size is taken from the viewBox.
+
+  ## Returns
+
+  - `{:ok, {width, height}}` - The intrinsic width and height in pixels, as floats
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
+
+  ## Examples
+
+      {:ok, {width, height}} = Svgager.Converter.intrinsic_size(svg_string)
+      {:ok, png_data} = Svgager.Converter.convert(svg_string, format: :png, width: round(width * 2))
+  """
+  @spec intrinsic_size(binary(), keyword()) ::
+          {:ok, {float(), float()}} | {:error, error()}
+  def intrinsic_size(svg_string, opts \\ []) when is_binary(svg_string) do
+    with {:ok, options} <- parse_opts(Keyword.put(opts, :format, :png)) do
+      Native.svg_intrinsic_size(svg_string, options)
+    end
+  end
+
+  @doc """
+  Checks that an SVG can be parsed, without rendering it.
+
+  This is a cheap check for user uploads: the SVG is decompressed, preprocessed with the
+  replacements in `opts` and parsed, but no image is allocated. Accepts the same options as
+  `convert/2`; `:format`, `:width`, `:height` and `:node_id` are ignored. An SVG without a
+  usable intrinsic size is still valid, since it converts with an explicit `:width` and
+  `:height`.
+
+  ## Returns
+
+  - `:ok` - The SVG is valid
+  - `{:error, {reason, message}}` - Usually `:parse_failed` with the parser's error text, or
+    `:invalid_option` for invalid options
+
+  ## Examples
+
+      :ok = Svgager.Converter.validate(svg_string)
+
+      {:error, {:parse_failed, message}} = Svgager.Converter.validate("<svg")
+  """
+  @spec validate(binary(), keyword()) :: :ok | {:error, error()}
+  def validate(svg_string, opts \\ []) when is_binary(svg_string) do
+    with {:ok, options} <- parse_opts(Keyword.put(opts, :format, :png)) do
+      Native.validate_svg(svg_string, options)
+    end
+  end
+
+  @doc """
+  Lists the ids of the elements in an SVG that can be rendered with `:node_id`.
+
+  The SVG is parsed but not rendered. Ids are returned in document order and include nested
+  elements. Elements that are never drawn, such as gradients and other `<defs>` content, or
+  text when no font is available, aren't listed. Replacements and fonts apply as in
+  `convert/2`; `:format`, `:width`, `:height` and `:node_id` are ignored.
+
+  ## Returns
+
+  - `{:ok, ids}` - The element ids as a list of strings
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
+
+  ## Examples
+
+      {:ok, ["home", "search", "settings"]} = Svgager.Converter.element_ids(sprite_svg)
+      {:ok, icon} = Svgager.Converter.convert(sprite_svg, format: :png, node_id: "home")
+  """
+  @spec element_ids(binary(), keyword()) :: {:ok, [String.t()]} | {:error, error()}
+  def element_ids(svg_string, opts \\ []) when is_binary(svg_string) do
+    with {:ok, options} <- parse_opts(Keyword.put(opts, :format, :png)) do
+      Native.svg_element_ids(svg_string, options)
+    end
+  end
+
+  @doc """
+  Counts the paths, groups, text nodes and images an SVG renders, without rendering it.
+
+  This is a cheap complexity metric for rejecting overly complex uploads before converting
+  them. The counts are taken from the tree usvg renders rather than the markup: every shape
+  counts as a path, the contents of clip paths, masks, patterns and embedded SVG images are
+  counted each time they are used, and groups include those usvg creates to wrap clipped,
+  masked and filtered elements and images. Text is only counted when a font is available
+  for it. Replacements and fonts apply as in `convert/2`; `:format`, `:width`, `:height` and
+  `:node_id` are ignored.
+
+  ## Returns
+
+  - `{:ok, %{paths: paths, groups: groups, texts: texts, images: images}}` - The node counts
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
+
+  ## Examples
+
+      {:ok, %{paths: paths}} = Svgager.Converter.complexity(upload)
+      if paths > 10_000, do: {:error, :too_complex}, else: Svgager.Converter.convert(upload)
+  """
+  @spec complexity(binary(), keyword()) ::
+          {:ok,
+           %{
+             paths: non_neg_integer(),
+             groups: non_neg_integer(),
+             texts: non_neg_integer(),
+             images: non_neg_integer()
+           }}
+          | {:error, error()}
+  def complexity(svg_string, opts \\ []) when is_binary(svg_string) do
+    with {:ok, options} <- parse_opts(Keyword.put(opts, :format, :png)) do
+      Native.svg_complexity(svg_string, options)
+    end
+  end
+
+  @doc """
+  Converts many SVGs in one call, rendering them in parallel.
+
+  Each job is an `{svg_string, opts}` tuple taking the same options as `convert/2`. Jobs are
+  validated individually and the valid ones are rendered together in a single native call,
+  which saves the per-call overhead when converting hundreds of images. Jobs rendered one
+  after another reuse the pixel buffers of earlier jobs with the same size.
+
+  ## Options
+
+  - `:max_concurrency` - (optional) Maximum number of jobs rendered at the same time, as a
+    positive integer. Lower it to bound memory use when the batch contains many large images.
+    Defaults to the number of CPUs
+
+  ## Returns
+
+  A list with one `{:ok, binary_data}` or `{:error, {reason, message}}` result per job, in
+  the same order as `jobs`. A failing job doesn't affect the others. An invalid
+  `:max_concurrency` fails the whole call with `{:error, {:invalid_option, message}}`.
+
+  ## Examples
+
+      [{:ok, icon}, {:error, {:parse_failed, _message}}] =
+        Svgager.Converter.convert_batch([
+          {icon_svg, format: :png, width: 64},
+          {"not an svg", format: :png, width: 64}
+        ])
+
+      # Render at most two images at a time
+      results = Svgager.Converter.convert_batch(jobs, max_concurrency: 2)
+  """
+  @spec convert_batch([{binary(), keyword()}], keyword()) ::
+          [{:ok, binary()} | {:error, error()}] | {:error, error()}
+  def convert_batch(jobs, opts \\ []) when is_list(jobs) do
+    max_concurrency = Keyword.get(opts, :max_concurrency)
+
+    with :ok <- validate_max_concurrency(max_concurrency) do
+      prepared = Enum.map(jobs, &prepare_job/1)
+
+      valid_jobs =
+        Enum.flat_map(prepared, fn
+          {:ok, job} -> [job]
+          {:error, _} -> []
+        end)
+
+      with {:ok, rendered} <- Native.convert_svg_batch(valid_jobs, max_concurrency) do
+        merge_batch_results(prepared, rendered)
+      end
+    end
+  end
+
+  defp prepare_job({svg_string, opts}) when is_binary(svg_string) and is_list(opts) do
+    with :ok <- validate_opts(opts),
+         {:ok, options} <- parse_opts(opts) do
+      {:ok, {svg_string, options}}
+    end
+  end
+
+  defp prepare_job(_job), do: invalid_option("batch jobs must be {svg_string, opts} tuples")
+
+  # Puts the native results back in place of the jobs that passed validation
+  defp merge_batch_results([], []), do: []
+
+  defp merge_batch_results([{:ok, _job} | prepared], [result | rendered]),
+    do: [result | merge_batch_results(prepared, rendered)]
+
+  defp merge_batch_results([error | prepared], rendered),
+    do: [error | merge_batch_results(prepared, rendered)]
+
+  @doc """
+  Converts SVG to an ICO file containing one square PNG entry per size, such as a favicon.
+
+  The SVG is parsed once and rendered at every size. Accepts the same options as `convert/2`,
+  except that `:format`, `:width` and `:height` are ignored. Use `fit: :contain` to keep the
+  aspect ratio of a non-square SVG.
+
+  ## Options
+
+  - `:sizes` - (optional) List of entry sizes in pixels, each from 1 to 256. Defaults to
+    `#{inspect(@default_ico_sizes)}`
+
+  ## Returns
+
+  - `{:ok, ico_data}` - Binary ICO data on success
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
+
+  ## Examples
+
+      {:ok, ico_data} = Svgager.Converter.convert_to_ico(svg_string, sizes: [16, 32, 48])
+      File.write!("favicon.ico", ico_data)
+  """
+  @spec convert_to_ico(binary(), keyword()) :: {:ok, binary()} | {:error, error()}
+  def convert_to_ico(svg_string, opts \\ []) when is_binary(svg_string) do
+    sizes = Keyword.get(opts, :sizes, @default_ico_sizes)
+
+    with :ok <- validate_ico_sizes(sizes),
+         {:ok, options} <- parse_opts(Keyword.put(opts, :format, :png)) do
+      Native.convert_svg_to_ico(svg_string, options, sizes)
+    end
+  end
+
+  @doc """
+  Converts SVG to the standard favicon set in one call.
+
+  The SVG is parsed once and every image is rendered from it. Accepts the same options as
+  `convert/2`, except that `:format`, `:width` and `:height` are ignored. Use `fit: :contain`
+  to keep the aspect ratio of a non-square SVG.
+
+  ## Returns
+
+  - `{:ok, favicon}` - A keyword list with, in order:
+    - `:favicon` - ICO with 16x16, 32x32 and 48x48 entries, usually served as `favicon.ico`
+    - `:apple_touch_icon` - 180x180 PNG, usually served as `apple-touch-icon.png`
+    - `:icon_192` and `:icon_512` - 192x192 and 512x512 PNGs for a web app manifest
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
+
+  ## Examples
+
+      {:ok, favicon} = Svgager.Converter.convert_to_favicon(svg_string)
+      File.write!("favicon.ico", favicon[:favicon])
+      File.write!("apple-touch-icon.png", favicon[:apple_touch_icon])
+  """
+  @spec convert_to_favicon(binary(), keyword()) :: {:ok, favicon()} | {:error, error()}
+  def convert_to_favicon(svg_string, opts \\ []) when is_binary(svg_string) do
+    with {:ok, options} <- parse_opts(Keyword.put(opts, :format, :png)) do
+      Native.convert_svg_to_favicon(svg_string, options)
+    end
+  end
+
+  @doc """
+  Converts a sequence of SVG frames to a single animated GIF.
+
+  Every frame is rendered with the same options, as for `convert/2` (`:format` is ignored),
+  and must come out at the same size; give explicit `:width` and `:height` when the frames'
+  intrinsic sizes differ. Frames are flattened onto the background and quantized like GIF
+  output of `convert/2`, so `:dither` and `:alpha_threshold` apply, the latter to a
+  translucent `:background_color`.
+
+  ## Options
+
+  - `:delays` - (optional) How long each frame is shown in milliseconds, either one
+    non-negative integer for every frame or a list with one delay per frame. GIF stores delays
+    in hundredths of a second, so they are rounded down to a multiple of 10 ms. Defaults to
+    #{@default_frame_delay}
+  - `:loop` - (optional) `:infinite`, or how many times the animation repeats after playing
+    once, from 0 to 65535. Defaults to `:infinite`
+
+  ## Returns
+
+  - `{:ok, gif_data}` - Binary GIF data on success
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
+
+  ## Examples
+
+      {:ok, gif_data} =
+        Svgager.Converter.convert_to_animated_gif([frame1, frame2, frame3],
+          width: 200,
+          delays: [100, 100, 500],
+          loop: :infinite
+        )
+  """
+  @spec convert_to_animated_gif([binary()], keyword()) :: {:ok, binary()} | {:error, error()}
+  def convert_to_animated_gif(svg_frames, opts \\ []) when is_list(svg_frames) do
+    delays = Keyword.get(opts, :delays, @default_frame_delay)
+    loop = Keyword.get(opts, :loop, :infinite)
+
+    with :ok <- validate_frames(svg_frames),
+         {:ok, delays} <- frame_delays(delays, length(svg_frames)),
+         {:ok, loop_count} <- loop_count(loop),
+         {:ok, options} <- parse_opts(Keyword.put(opts, :format, :gif)) do
+      Native.convert_svgs_to_animated_gif(svg_frames, delays, options, loop_count)
     end
   end
 
+  defp validate_frames([_ | _] = frames) do
+    if Enum.all?(frames, &is_binary/1), do: :ok, else: invalid_frames()
+  end
+
+  defp validate_frames(_frames), do: invalid_frames()
+
+  defp invalid_frames, do: invalid_option("frames must be a non-empty list of SVG binaries")
+
+  defp frame_delays(delay, count) when is_integer(delay) and delay >= 0,
+    do: {:ok, List.duplicate(delay, count)}
+
+  defp frame_delays(delays, count) when is_list(delays) and length(delays) == count do
+    if Enum.all?(delays, &(is_integer(&1) and &1 >= 0)) do
+      {:ok, delays}
+    else
+      invalid_frame_delays(delays, count)
+    end
+  end
+
+  defp frame_delays(delays, count), do: invalid_frame_delays(delays, count)
+
+  defp invalid_frame_delays(delays, count) do
+    invalid_option(
+      "delays must be a non-negative integer or a list of #{count} non-negative integers, " <>
+        "got: #{inspect(delays)}"
+    )
+  end
+
+  defp loop_count(:infinite), do: {:ok, nil}
+
+  defp loop_count(count) when is_integer(count) and count in 0..65_535, do: {:ok, count}
+
+  defp loop_count(loop) do
+    invalid_option("loop must be :infinite or an integer from 0 to 65535, got: #{inspect(loop)}")
+  end
+
+  @doc """
+  Renders a list of SVGs into one PNG sprite sheet (atlas) and reports where each sprite is.
+
+  Each SVG is rendered into its own cell of a grid, filled left to right and top to bottom,
+  with the same options as `convert/2` (`:format`, `:width` and `:height` are ignored). Use
+  `fit: :contain` to keep the aspect ratio of icons that don't match the cell. Gutters and
+  any unused cells are transparent unless `:flatten` is set.
+
+  ## Options
+
+  - `:cell_size` - (required) Size of each cell in pixels, as a positive integer for square
+    cells or a `{width, height}` tuple
+  - `:columns` - (optional) Number of grid columns. Defaults to the square root of the number
+    of SVGs, rounded up
+  - `:gutter` - (optional) Pixels between neighboring cells. Defaults to 0
+
+  ## Returns
+
+  - `{:ok, %{data: png_data, width: width, height: height, sprites: sprites}}` - The PNG sheet,
+    its dimensions and one `%{x: x, y: y, width: width, height: height}` rect per SVG, in order
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
+
+  ## Examples
+
+      {:ok, %{data: png_data, sprites: [first | _]}} =
+        Svgager.Converter.convert_to_sprite_sheet(icons, cell_size: 32, gutter: 2, fit: :contain)
+  """
+  @spec convert_to_sprite_sheet([binary()], keyword()) ::
+          {:ok, sprite_sheet()} | {:error, error()}
+  def convert_to_sprite_sheet(svgs, opts \\ []) when is_list(svgs) do
+    cell_size = Keyword.get(opts, :cell_size)
+    columns = Keyword.get_lazy(opts, :columns, fn -> default_columns(length(svgs)) end)
+    gutter = Keyword.get(opts, :gutter, 0)
+
+    with :ok <- validate_sprites(svgs),
+         {:ok, cell_size} <- sprite_cell_size(cell_size),
+         :ok <- validate_columns(columns),
+         :ok <- validate_gutter(gutter),
+         {:ok, options} <- parse_opts(Keyword.put(opts, :format, :png)),
+         {:ok, {data, width, height, rects}} <-
+           Native.convert_svgs_to_sprite_sheet(svgs, options, columns, cell_size, gutter) do
+      sprites =
+        for {x, y, sprite_width, sprite_height} <- rects do
+          %{x: x, y: y, width: sprite_width, height: sprite_height}
+        end
+
+      {:ok, %{data: data, width: width, height: height, sprites: sprites}}
+    end
+  end
+
+  defp default_columns(count), do: max(ceil(:math.sqrt(count)), 1)
+
+  defp validate_sprites([_ | _] = svgs) do
+    if Enum.all?(svgs, &is_binary/1), do: :ok, else: invalid_sprites()
+  end
+
+  defp validate_sprites(_svgs), do: invalid_sprites()
+
+  defp invalid_sprites, do: invalid_option("svgs must be a non-empty list of SVG binaries")
+
+  defp sprite_cell_size(nil), do: invalid_option("cell_size option is required")
+
+  defp sprite_cell_size(size) when is_integer(size) and size > 0, do: {:ok, {size, size}}
+
+  defp sprite_cell_size({width, height} = size)
+       when is_integer(width) and width > 0 and is_integer(height) and height > 0,
+       do: {:ok, size}
+
+  defp sprite_cell_size(size) do
+    {:error,
+     {:invalid_dimensions,
+      "cell_size must be a positive integer or a {width, height} tuple, got: #{inspect(size)}"}}
+  end
+
+  defp validate_columns(columns) when is_integer(columns) and columns > 0, do: :ok
+
+  defp validate_columns(columns) do
+    invalid_option("columns must be a positive integer, got: #{inspect(columns)}")
+  end
+
+  defp validate_gutter(gutter) when is_integer(gutter) and gutter >= 0, do: :ok
+
+  defp validate_gutter(gutter) do
+    invalid_option("gutter must be a non-negative integer, got: #{inspect(gutter)}")
+  end
+
+  @doc """
+  Renders an SVG once and slices the image into a grid of PNG tiles, such as for a map viewer.
+
+  The SVG is rendered with the same options as `convert/2` (`:format` is ignored) and cut into
+  tiles from the top-left corner. When the image isn't a multiple of the tile size, the tiles
+  along the right and bottom edges are padded to the full tile size with the background color,
+  which is transparent unless `:flatten` is set.
+
+  ## Options
+
+  - `:tile_size` - (optional) Size of each tile in pixels, as a positive integer for square
+    tiles or a `{width, height}` tuple. Defaults to #{@default_tile_size}
+
+  ## Returns
+
+  - `{:ok, tiles}` - A list of `{{column, row}, png_data}` tuples, row by row from the top-left
+    tile at `{0, 0}`
+  - `{:error, {reason, message}}` - A machine-readable `t:error_reason/0` and a human-readable message on failure
+
+  ## Examples
+
+      {:ok, tiles} = Svgager.Converter.convert_to_tiles(map_svg, width: 1024, tile_size: 256)
+      png_data = :proplists.get_value({1, 2}, tiles)
+  """
+  @spec convert_to_tiles(binary(), keyword()) :: {:ok, [tile()]} | {:error, error()}
+  def convert_to_tiles(svg_string, opts \\ []) when is_binary(svg_string) do
+    with {:ok, tile_size} <- tile_size(Keyword.get(opts, :tile_size, @default_tile_size)),
+         {:ok, options} <- parse_opts(Keyword.put(opts, :format, :png)) do
+      Native.convert_svg_to_tiles(svg_string, options, tile_size)
+    end
+  end
+
+  defp tile_size(size) when is_integer(size) and size > 0, do: {:ok, {size, size}}
+
+  defp tile_size({width, height} = size)
+       when is_integer(width) and width > 0 and is_integer(height) and height > 0,
+       do: {:ok, size}
+
+  defp tile_size(size) do
+    {:error,
+     {:invalid_dimensions,
+      "tile_size must be a positive integer or a {width, height} tuple, got: #{inspect(size)}"}}
+  end
+
   defp validate_opts(opts) do
     format = Keyword.get(opts, :format)
 
     cond do
       is_nil(format) ->
-        {:error, "format option is required"}
+        invalid_option("format option is required")
 
       format not in @supported_formats ->
         {:error,
-         "unsupported format: #{inspect(format)}. Supported formats: #{inspect(@supported_formats)}"}
+         {:unsupported_format,
+          "unsupported format: #{inspect(format)}. Supported formats: #{inspect(@supported_formats)}"}}
 
       true ->
         :ok
     end
   end
 
+  # Builds the options map decoded by the NIF; every key must be present
   defp parse_opts(opts) do
     format = Keyword.get(opts, :format) |> Atom.to_string()
     width = Keyword.get(opts, :width)
     height = Keyword.get(opts, :height)
     bg_color = Keyword.get(opts, :background_color)
     replacements = Keyword.get(opts, :replacements, %{})
+    regex_replacements = Keyword.get(opts, :regex_replacements, %{})
+    quality = Keyword.get(opts, :quality)
+    fit = Keyword.get(opts, :fit)
+    png_compression = Keyword.get(opts, :png_compression)
+    flatten = Keyword.get(opts, :flatten, false)
+    font_family = Keyword.get(opts, :font_family)
+    font_size = Keyword.get(opts, :font_size)
+    load_system_fonts = Keyword.get(opts, :load_system_fonts, true)
+    font_dirs = Keyword.get(opts, :font_dirs, [])
+    dpi = Keyword.get(opts, :dpi)
+    node_id = Keyword.get(opts, :node_id)
+    crop = Keyword.get(opts, :crop)
+    trim = Keyword.get(opts, :trim, false)
+    padding = Keyword.get(opts, :padding)
+    rotate = Keyword.get(opts, :rotate)
+    flip_h = Keyword.get(opts, :flip_h, false)
+    flip_v = Keyword.get(opts, :flip_v, false)
+    cache = Keyword.get(opts, :cache, false)
+    ssaa = Keyword.get(opts, :ssaa)
+    grayscale = Keyword.get(opts, :grayscale, false)
+    opacity = Keyword.get(opts, :opacity)
+    bit_depth = Keyword.get(opts, :bit_depth)
+    max_pixels = Keyword.get(opts, :max_pixels)
+    scale = Keyword.get(opts, :scale)
+    preserve_aspect = Keyword.get(opts, :preserve_aspect, false)
+    dither = Keyword.get(opts, :dither, false)
+    progressive = Keyword.get(opts, :progressive, false)
+    subsampling = Keyword.get(opts, :subsampling)
+    metadata = Keyword.get(opts, :metadata, [])
+    dpi_output = Keyword.get(opts, :dpi_output)
+    shape_rendering = Keyword.get(opts, :shape_rendering)
+    text_rendering = Keyword.get(opts, :text_rendering)
+    image_rendering = Keyword.get(opts, :image_rendering)
+    languages = Keyword.get(opts, :languages, [])
+    antialias = Keyword.get(opts, :antialias, true)
+    background_gradient = Keyword.get(opts, :background_gradient)
+    offset_x = Keyword.get(opts, :offset_x)
+    offset_y = Keyword.get(opts, :offset_y)
+    zoom = Keyword.get(opts, :zoom)
+    escape_replacements = Keyword.get(opts, :escape_replacements, false)
+    ignore_case_replacements = Keyword.get(opts, :ignore_case_replacements, false)
+    timeout_ms = Keyword.get(opts, :timeout_ms)
+    view_box = Keyword.get(opts, :view_box)
+    icc_profile = Keyword.get(opts, :icc_profile)
+    srgb = Keyword.get(opts, :srgb, false)
+    resize_filter = Keyword.get(opts, :resize_filter)
+    css_vars = Keyword.get(opts, :css_vars, [])
+    resource_dir = Keyword.get(opts, :resource_dir)
+    default_fill = Keyword.get(opts, :default_fill)
+    dpr = Keyword.get(opts, :dpr)
+    color_remap = Keyword.get(opts, :color_remap, [])
+    strict = Keyword.get(opts, :strict, false)
+    palette = Keyword.get(opts, :palette, [])
+    palette_dither = Keyword.get(opts, :palette_dither, false)
+    alpha_threshold = Keyword.get(opts, :alpha_threshold)
+    lossless = Keyword.get(opts, :lossless, true)
 
     # Validate width and height if provided
     with :ok <- validate_dimension(:width, width),
          :ok <- validate_dimension(:height, height),
-         {:ok, replacements_list} <- convert_replacements(replacements) do
-      {:ok, format, width, height, bg_color, replacements_list}
+         :ok <- validate_quality(quality),
+         :ok <- validate_fit(fit),
+         :ok <- validate_png_compression(png_compression),
+         :ok <- validate_boolean(:flatten, flatten),
+         :ok <- validate_boolean(:load_system_fonts, load_system_fonts),
+         {:ok, font_dirs} <- existing_font_dirs(font_dirs),
+         :ok <- validate_string(:font_family, font_family),
+         :ok <- validate_string(:node_id, node_id),
+         :ok <- validate_crop(crop),
+         :ok <- validate_boolean(:trim, trim),
+         :ok <- validate_padding(padding),
+         :ok <- validate_rotate(rotate),
+         :ok <- validate_boolean(:flip_h, flip_h),
+         :ok <- validate_boolean(:flip_v, flip_v),
+         :ok <- validate_boolean(:cache, cache),
+         :ok <- validate_ssaa(ssaa),
+         :ok <- validate_boolean(:grayscale, grayscale),
+         :ok <- validate_opacity(opacity),
+         :ok <- validate_bit_depth(bit_depth),
+         :ok <- validate_max_pixels(max_pixels),
+         :ok <- validate_boolean(:preserve_aspect, preserve_aspect),
+         :ok <- validate_boolean(:dither, dither),
+         :ok <- validate_boolean(:progressive, progressive),
+         :ok <- validate_subsampling(subsampling),
+         {:ok, metadata} <- convert_metadata(metadata),
+         :ok <- validate_rendering(:shape_rendering, shape_rendering, @shape_renderings),
+         :ok <- validate_rendering(:text_rendering, text_rendering, @text_renderings),
+         :ok <- validate_rendering(:image_rendering, image_rendering, @image_renderings),
+         :ok <- validate_languages(languages),
+         :ok <- validate_boolean(:antialias, antialias),
+         :ok <- validate_background_gradient(background_gradient),
+         :ok <- validate_positive_number(:font_size, font_size),
+         :ok <- validate_positive_number(:dpi, dpi),
+         :ok <- validate_positive_number(:scale, scale),
+         :ok <- validate_positive_number(:dpi_output, dpi_output),
+         :ok <- validate_number(:offset_x, offset_x),
+         :ok <- validate_number(:offset_y, offset_y),
+         :ok <- validate_positive_number(:zoom, zoom),
+         :ok <- validate_boolean(:escape_replacements, escape_replacements),
+         :ok <- validate_boolean(:ignore_case_replacements, ignore_case_replacements),
+         :ok <- validate_timeout_ms(timeout_ms),
+         :ok <- validate_view_box(view_box),
+         :ok <- validate_icc_profile(icc_profile),
+         :ok <- validate_boolean(:srgb, srgb),
+         :ok <- validate_rendering(:resize_filter, resize_filter, @resize_filters),
+         {:ok, css_vars} <- convert_css_vars(css_vars),
+         :ok <- validate_string(:resource_dir, resource_dir),
+         :ok <- validate_string(:default_fill, default_fill),
+         :ok <- validate_positive_number(:dpr, dpr),
+         :ok <- validate_color_remap(color_remap),
+         :ok <- validate_boolean(:strict, strict),
+         :ok <- validate_palette(palette),
+         :ok <- validate_boolean(:palette_dither, palette_dither),
+         :ok <- validate_alpha_threshold(alpha_threshold),
+         :ok <- validate_boolean(:lossless, lossless),
+         {:ok, replacements_list} <- convert_replacements(:replacements, replacements),
+         {:ok, regex_replacements_list} <-
+           convert_replacements(:regex_replacements, regex_replacements) do
+      {:ok,
+       %{
+         format: format,
+         width: width,
+         height: height,
+         background_color: bg_color,
+         replacements: replacements_list,
+         regex_replacements: regex_replacements_list,
+         quality: quality,
+         fit: fit && Atom.to_string(fit),
+         png_compression: png_compression && Atom.to_string(png_compression),
+         flatten: flatten,
+         font_family: font_family,
+         font_size: font_size && font_size / 1,
+         load_system_fonts: load_system_fonts,
+         font_dirs: font_dirs,
+         dpi: dpi && dpi / 1,
+         node_id: node_id,
+         crop: crop,
+         trim: trim,
+         padding: padding,
+         rotate: rotate,
+         flip_h: flip_h,
+         flip_v: flip_v,
+         cache: cache,
+         ssaa: ssaa,
+         grayscale: grayscale,
+         opacity: opacity && opacity / 1,
+         bit_depth: bit_depth,
+         max_pixels: max_pixels,
+         scale: scale && scale / 1,
+         preserve_aspect: preserve_aspect,
+         dither: dither,
+         progressive: progressive,
+         subsampling: subsampling,
+         metadata: metadata,
+         dpi_output: dpi_output && dpi_output / 1,
+         shape_rendering: shape_rendering && Atom.to_string(shape_rendering),
+         text_rendering: text_rendering && Atom.to_string(text_rendering),
+         image_rendering: image_rendering && Atom.to_string(image_rendering),
+         languages: languages,
+         antialias: antialias,
+         background_gradient: background_gradient,
+         offset_x: offset_x && offset_x / 1,
+         offset_y: offset_y && offset_y / 1,
+         zoom: zoom && zoom / 1,
+         escape_replacements: escape_replacements,
+         ignore_case_replacements: ignore_case_replacements,
+         timeout_ms: timeout_ms,
+         view_box: view_box && float_tuple(view_box),
+         icc_profile: icc_profile,
+         srgb: srgb,
+         resize_filter: resize_filter && Atom.to_string(resize_filter),
+         css_vars: css_vars,
+         resource_dir: resource_dir,
+         default_fill: default_fill,
+         dpr: dpr && dpr / 1,
+         color_remap: color_remap,
+         strict: strict,
+         palette: palette,
+         palette_dither: palette_dither,
+         alpha_threshold: alpha_threshold,
+         lossless: lossless
+       }}
     end
   end
 
@@ -104,20 +1180,380 @@ defmodule Svgager.Converter do
 
   defp validate_dimension(_key, value) when is_integer(value) and value > 0, do: :ok
 
+  # Strings such as "150%" are parsed by the NIF
+  defp validate_dimension(_key, value) when is_binary(value), do: :ok
+
   defp validate_dimension(key, value) do
-    {:error, "#{key} must be a positive integer, got: #{inspect(value)}"}
+    {:error,
+     {:invalid_dimensions,
+      "#{key} must be a positive integer or a string such as \"150%\", got: #{inspect(value)}"}}
+  end
+
+  defp validate_quality(nil), do: :ok
+
+  defp validate_quality(value) when is_integer(value) and value in 1..100, do: :ok
+
+  defp validate_quality(value) do
+    invalid_option("quality must be an integer between 1 and 100, got: #{inspect(value)}")
+  end
+
+  defp validate_ssaa(nil), do: :ok
+
+  defp validate_ssaa(value) when is_integer(value) and value in 1..@max_ssaa, do: :ok
+
+  defp validate_ssaa(value) do
+    invalid_option("ssaa must be an integer between 1 and #{@max_ssaa}, got: #{inspect(value)}")
+  end
+
+  defp validate_opacity(nil), do: :ok
+
+  defp validate_opacity(value) when is_number(value) and value >= 0 and value <= 1, do: :ok
+
+  defp validate_opacity(value) do
+    invalid_option("opacity must be a number between 0 and 1, got: #{inspect(value)}")
+  end
+
+  defp validate_bit_depth(nil), do: :ok
+
+  defp validate_bit_depth(value) when value in [8, 16], do: :ok
+
+  defp validate_bit_depth(value) do
+    invalid_option("bit_depth must be 8 or 16, got: #{inspect(value)}")
+  end
+
+  defp validate_max_pixels(nil), do: :ok
+
+  defp validate_max_pixels(value) when is_integer(value) and value > 0, do: :ok
+
+  defp validate_max_pixels(value) do
+    invalid_option("max_pixels must be a positive integer, got: #{inspect(value)}")
+  end
+
+  defp validate_timeout_ms(nil), do: :ok
+
+  defp validate_timeout_ms(value) when is_integer(value) and value > 0, do: :ok
+
+  defp validate_timeout_ms(value) do
+    invalid_option("timeout_ms must be a positive integer, got: #{inspect(value)}")
+  end
+
+  defp validate_view_box(nil), do: :ok
+
+  defp validate_view_box({min_x, min_y, width, height})
+       when is_number(min_x) and is_number(min_y) and is_number(width) and width > 0 and
+              is_number(height) and height > 0,
+       do: :ok
+
+  defp validate_view_box(value) do
+    invalid_option(
+      "view_box must be a {min_x, min_y, width, height} tuple of numbers with a positive " <>
+        "width and height, got: #{inspect(value)}"
+    )
+  end
+
+  defp validate_icc_profile(nil), do: :ok
+
+  defp validate_icc_profile(value) when is_binary(value) and value != "", do: :ok
+
+  defp validate_icc_profile(value) do
+    invalid_option("icc_profile must be a binary of ICC profile data, got: #{inspect(value)}")
+  end
+
+  defp float_tuple(tuple), do: tuple |> Tuple.to_list() |> Enum.map(&(&1 / 1)) |> List.to_tuple()
+
+  defp validate_subsampling(nil), do: :ok
+
+  defp validate_subsampling(value) when value in @subsamplings, do: :ok
+
+  defp validate_subsampling(value) do
+    invalid_option(
+      "subsampling must be one of #{inspect(@subsamplings)}, got: #{inspect(value)}"
+    )
+  end
+
+  defp convert_metadata(metadata) when is_map(metadata) or is_list(metadata) do
+    Enum.reduce_while(metadata, {:ok, []}, fn
+      {keyword, text}, {:ok, acc}
+      when (is_binary(keyword) or is_atom(keyword)) and is_binary(text) ->
+        {:cont, {:ok, [{to_string(keyword), text} | acc]}}
+
+      entry, _acc ->
+        {:halt,
+         invalid_option(
+           "metadata entries must be {keyword, text} pairs of strings, got: #{inspect(entry)}"
+         )}
+    end)
+    |> case do
+      {:ok, acc} -> {:ok, Enum.reverse(acc)}
+      error -> error
+    end
+  end
+
+  defp convert_metadata(metadata) do
+    invalid_option("metadata must be a map or a keyword list, got: #{inspect(metadata)}")
+  end
+
+  defp convert_css_vars(css_vars) when is_map(css_vars) or is_list(css_vars) do
+    Enum.reduce_while(css_vars, {:ok, []}, fn
+      {name, value}, {:ok, acc} when (is_binary(name) or is_atom(name)) and is_binary(value) ->
+        {:cont, {:ok, [{to_string(name), value} | acc]}}
+
+      entry, _acc ->
+        {:halt,
+         invalid_option(
+           "css_vars entries must be {name, value} pairs of strings, got: #{inspect(entry)}"
+         )}
+    end)
+    |> case do
+      {:ok, acc} -> {:ok, Enum.reverse(acc)}
+      error -> error
+    end
+  end
+
+  defp convert_css_vars(css_vars) do
+    invalid_option("css_vars must be a map or a keyword list, got: #{inspect(css_vars)}")
   end
 
-  defp convert_replacements(replacements) when is_map(replacements) do
+  defp validate_color_remap(color_remap) when is_list(color_remap) do
+    valid? = fn
+      {from, to, tolerance} -> is_binary(from) and is_binary(to) and tolerance in 0..255
+      _entry -> false
+    end
+
+    case Enum.reject(color_remap, valid?) do
+      [] ->
+        :ok
+
+      [invalid | _] ->
+        invalid_option(
+          "color_remap entries must be {from, to, tolerance} tuples of two color strings and " <>
+            "an integer from 0 to 255, got: #{inspect(invalid)}"
+        )
+    end
+  end
+
+  defp validate_color_remap(color_remap) do
+    invalid_option("color_remap must be a list of tuples, got: #{inspect(color_remap)}")
+  end
+
+  defp validate_palette(palette) when is_list(palette) do
+    case Enum.reject(palette, &is_binary/1) do
+      [] -> :ok
+      [invalid | _] -> invalid_option("palette colors must be strings, got: #{inspect(invalid)}")
+    end
+  end
+
+  defp validate_palette(palette) do
+    invalid_option("palette must be a list of color strings, got: #{inspect(palette)}")
+  end
+
+  defp validate_alpha_threshold(nil), do: :ok
+
+  defp validate_alpha_threshold(value) when is_integer(value) and value in 1..255, do: :ok
+
+  defp validate_alpha_threshold(value) do
+    invalid_option("alpha_threshold must be an integer between 1 and 255, got: #{inspect(value)}")
+  end
+
+  defp validate_rendering(_key, nil, _accepted), do: :ok
+
+  defp validate_rendering(key, value, accepted) do
+    if value in accepted,
+      do: :ok,
+      else: invalid_option("#{key} must be one of #{inspect(accepted)}, got: #{inspect(value)}")
+  end
+
+  defp validate_languages(languages) when is_list(languages) do
+    case Enum.reject(languages, &(is_binary(&1) and &1 != "")) do
+      [] -> :ok
+      [invalid | _] -> invalid_option("languages must be strings, got: #{inspect(invalid)}")
+    end
+  end
+
+  defp validate_languages(languages) do
+    invalid_option("languages must be a list of strings, got: #{inspect(languages)}")
+  end
+
+  defp validate_background_gradient(nil), do: :ok
+
+  defp validate_background_gradient({top, bottom}) when is_binary(top) and is_binary(bottom),
+    do: :ok
+
+  defp validate_background_gradient(value) do
+    invalid_option(
+      "background_gradient must be a {top, bottom} tuple of color strings, got: #{inspect(value)}"
+    )
+  end
+
+  defp validate_fit(nil), do: :ok
+
+  defp validate_fit(value) when value in @fit_modes, do: :ok
+
+  defp validate_fit(value) do
+    invalid_option("fit must be one of #{inspect(@fit_modes)}, got: #{inspect(value)}")
+  end
+
+  defp validate_png_compression(nil), do: :ok
+
+  defp validate_png_compression(value) when value in @png_compressions, do: :ok
+
+  defp validate_png_compression(value) do
+    invalid_option(
+      "png_compression must be one of #{inspect(@png_compressions)}, got: #{inspect(value)}"
+    )
+  end
+
+  defp validate_rotate(nil), do: :ok
+
+  defp validate_rotate(value) when value in @rotations, do: :ok
+
+  defp validate_rotate(value) do
+    invalid_option("rotate must be one of #{inspect(@rotations)}, got: #{inspect(value)}")
+  end
+
+  defp validate_padding(nil), do: :ok
+
+  defp validate_padding(value) when is_integer(value) and value >= 0, do: :ok
+
+  defp validate_padding(value) do
+    invalid_option("padding must be a non-negative integer, got: #{inspect(value)}")
+  end
+
+  defp validate_crop(nil), do: :ok
+
+  defp validate_crop({x, y, width, height})
+       when is_integer(x) and x >= 0 and is_integer(y) and y >= 0 and is_integer(width) and
+              width > 0 and is_integer(height) and height > 0,
+       do: :ok
+
+  defp validate_crop(value) do
+    invalid_option(
+      "crop must be an {x, y, width, height} tuple of non-negative integers with a positive " <>
+        "width and height, got: #{inspect(value)}"
+    )
+  end
+
+  defp validate_ico_sizes([_ | _] = sizes) do
+    if Enum.all?(sizes, &(is_integer(&1) and &1 in 1..256)) do
+      :ok
+    else
+      invalid_ico_sizes(sizes)
+    end
+  end
+
+  defp validate_ico_sizes(sizes), do: invalid_ico_sizes(sizes)
+
+  defp invalid_ico_sizes(sizes) do
+    {:error,
+     {:invalid_dimensions,
+      "sizes must be a non-empty list of integers from 1 to 256, got: #{inspect(sizes)}"}}
+  end
+
+  defp validate_max_concurrency(nil), do: :ok
+
+  defp validate_max_concurrency(value) when is_integer(value) and value > 0, do: :ok
+
+  defp validate_max_concurrency(value) do
+    invalid_option("max_concurrency must be a positive integer, got: #{inspect(value)}")
+  end
+
+  defp validate_boolean(_key, value) when is_boolean(value), do: :ok
+
+  defp validate_boolean(key, value) do
+    invalid_option("#{key} must be a boolean, got: #{inspect(value)}")
+  end
+
+  defp validate_string(_key, nil), do: :ok
+
+  defp validate_string(_key, value) when is_binary(value) and value != "", do: :ok
+
+  defp validate_string(key, value) do
+    invalid_option("#{key} must be a non-empty string, got: #{inspect(value)}")
+  end
+
+  defp validate_positive_number(_key, nil), do: :ok
+
+  defp validate_positive_number(_key, value) when is_number(value) and value > 0, do: :ok
+
+  defp validate_positive_number(key, value) do
+    invalid_option("#{key} must be a positive number, got: #{inspect(value)}")
+  end
+
+  defp validate_number(_key, nil), do: :ok
+
+  defp validate_number(_key, value) when is_number(value), do: :ok
+
+  defp validate_number(key, value) do
+    invalid_option("#{key} must be a number, got: #{inspect(value)}")
+  end
+
+  defp existing_font_dirs(dirs) when is_list(dirs) do
+    if Enum.all?(dirs, &is_binary/1) do
+      {existing, missing} = Enum.split_with(dirs, &File.dir?/1)
+
+      Enum.each(missing, fn dir ->
+        Logger.warning("Skipping font directory #{inspect(dir)}: not a directory")
+      end)
+
+      {:ok, existing}
+    else
+      invalid_option("font_dirs must be a list of strings, got: #{inspect(dirs)}")
+    end
+  end
+
+  defp existing_font_dirs(dirs) do
+    invalid_option("font_dirs must be a list of strings, got: #{inspect(dirs)}")
+  end
+
+  defp convert_replacements(key, replacements) when is_map(replacements) do
     replacements_list =
       Enum.map(replacements, fn {search, replace} ->
-        {to_string(search), to_string(replace)}
+        {to_string(search), to_string(replace), nil, "any"}
       end)
 
     {:ok, replacements_list}
   rescue
-    _ -> {:error, "replacements must be a map of strings"}
+    _ -> invalid_option("#{key} must be a map of strings")
+  end
+
+  defp convert_replacements(key, replacements) when is_list(replacements) do
+    Enum.reduce_while(replacements, {:ok, []}, fn replacement, {:ok, acc} ->
+      case convert_replacement(replacement) do
+        {:ok, converted} -> {:cont, {:ok, [converted | acc]}}
+        :error -> {:halt, invalid_option(invalid_replacement_error(key, replacement))}
+      end
+    end)
+    |> case do
+      {:ok, acc} -> {:ok, Enum.reverse(acc)}
+      error -> error
+    end
+  end
+
+  defp convert_replacements(key, _), do: invalid_option("#{key} must be a map or a list")
+
+  defp convert_replacement({search, replace}), do: convert_replacement({search, replace, nil})
+
+  defp convert_replacement({search, replace, scope}) when scope in @replacement_scopes do
+    convert_replacement({search, replace, nil, scope})
+  end
+
+  defp convert_replacement({search, replace, max}),
+    do: convert_replacement({search, replace, max, :any})
+
+  defp convert_replacement({search, replace, max, scope})
+       when (is_nil(max) or (is_integer(max) and max > 0)) and scope in @replacement_scopes do
+    {:ok, {to_string(search), to_string(replace), max, Atom.to_string(scope)}}
+  rescue
+    _ -> :error
+  end
+
+  defp convert_replacement(_), do: :error
+
+  defp invalid_replacement_error(key, replacement) do
+    "#{key} entries must be {search, replace}, {search, replace, max}, " <>
+      "{search, replace, scope} or {search, replace, max, scope} tuples of strings with a " <>
+      "positive integer max and a scope of :any, :attr or :text, got: #{inspect(replacement)}"
   end
 
-  defp convert_replacements(_), do: {:error, "replacements must be a map"}
+  defp invalid_option(message), do: {:error, {:invalid_option, message}}
 end
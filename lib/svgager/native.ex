@@ -1,6 +1,11 @@
 defmodule Svgager.Native do
   @moduledoc """
   Native Implemented Functions (NIFs) for SVG conversion using Rustler.
+
+  All NIFs except `mime_for_format/1`, `extension_for_format/1` and `versions/0` run on a
+  dirty CPU scheduler, so long renders and slow encoders such as AVIF don't block the
+  normal schedulers. Concurrent calls are limited by the number of dirty CPU schedulers
+  (see the `+SDcpu` emulator flag) and queue up once they are all busy.
   """
 
   version = Mix.Project.config()[:version]
@@ -24,17 +29,301 @@ defmodule Svgager.Native do
   This function is implemented in Rust and loaded as a NIF.
 
   ## Parameters
-  - `svg_data`: SVG content as a string
-  - `format`: Output format ("png", "jpg", "jpeg", "gif", "webp")
-  - `width`: Optional output width (maintains aspect ratio if height is nil)
-  - `height`: Optional output height (maintains aspect ratio if width is nil)
-  - `background_color`: Optional hex color string for non-PNG formats (e.g., "FFFFFF")
-  - `replacements`: List of {search, replace} tuples for preprocessing SVG content
+  - `svg_data`: SVG content as a binary, either plain markup or gzip-compressed SVGZ
+  - `options`: Map of conversion options. Every key must be present (use `nil` for unset values):
+    - `format`: Output format ("png", "jpg", "jpeg", "gif", "webp", "bmp", "tiff", "tif", "qoi", "avif", "tga", "pnm", "ppm", "ff", "farbfeld", "exr")
+    - `width`: Optional output width (maintains aspect ratio if height is nil), as an integer or a string of pixels or a percentage of the intrinsic width such as "150%"
+    - `height`: Optional output height (maintains aspect ratio if width is nil), in the same forms as `width`
+    - `background_color`: Optional CSS color name, `rgb()`/`rgba()` string or hex color string for formats without transparency or flattened output (e.g., "white", "rgba(255, 0, 0, 0.5)", "FFF", "FFFFFF" or "FFFFFF80")
+    - `replacements`: List of {search, replace, max, scope} tuples for preprocessing SVG content, where `max` is nil to replace every occurrence and `scope` is "any", "attr" or "text"
+    - `regex_replacements`: List of {pattern, replacement, max, scope} tuples, applied as regular expressions after `replacements`
+    - `escape_replacements`: Boolean, XML-escapes the replacement values of both lists before substitution
+    - `ignore_case_replacements`: Boolean, matches `replacements` search strings regardless of ASCII case
+    - `resource_dir`: Optional directory that relative `<image>` hrefs are resolved against; hrefs resolving outside of it are refused
+    - `css_vars`: List of {name, value} CSS custom properties that override the SVG's declarations when `var()` references are resolved
+    - `quality`: Optional JPEG, AVIF and lossy WebP quality from 1 to 100 (defaults to 90, ignored for other formats)
+    - `lossless`: Whether WebP output is lossless; when false it's lossy and honors `quality`
+    - `fit`: Optional fit mode when both dimensions are given ("fill", "contain" or "cover"); without it the root preserveAspectRatio applies, or "fill"
+    - `preserve_aspect`: Whether to letterbox the SVG as with "contain"; an error if `fit` is another mode
+    - `png_compression`: Optional PNG compression level ("fast", "default" or "best", defaults to "fast")
+    - `flatten`: Whether PNG, TIFF, QOI, TGA, farbfeld, EXR and GIF output is rendered over the background color
+    - `default_fill`: Optional color string set as the root element's `fill`, inherited by shapes and text without a fill of their own
+    - `color_remap`: List of `{from, to, tolerance}` tuples recoloring each fill, stroke and gradient stop whose channels are all within `tolerance` of `from`
+    - `font_family`: Optional default font family for text without one
+    - `font_size`: Optional default font size (a float) for text without one
+    - `load_system_fonts`: Whether text can use system fonts, which are loaded once per process
+    - `font_dirs`: List of directories to load additional fonts from; unreadable ones are skipped
+    - `node_id`: Optional id of the single element to render, sized from its bounding box
+    - `rotate`: Optional clockwise rotation in degrees (0, 90, 180 or 270)
+    - `flip_h`: Whether the output is mirrored horizontally (before rotation)
+    - `flip_v`: Whether the output is mirrored vertically (before rotation)
+    - `trim`: Whether empty margins are cut off the rendered image before cropping
+    - `crop`: Optional {x, y, width, height} tuple cut out of the rendered image, clamped to its bounds
+    - `padding`: Optional number of background pixels added on every side of the output
+    - `dpi`: Optional resolution (a float) for physical units in the SVG (defaults to 96)
+    - `cache`: Whether the parsed tree is cached and reused for the same processed SVG and parse settings
+    - `strict`: Whether any parse warning, such as an unresolved reference or a missing font, is returned as a `:parse_failed` error
+    - `ssaa`: Optional supersampling factor from 1 to 8; the SVG is rendered at that multiple of the output size and downsampled with `resize_filter`
+    - `resize_filter`: Optional downsampling filter for `ssaa` ("nearest", "triangle", "catmull" or "lanczos3", the default)
+    - `grayscale`: Whether the output is converted to luminance before encoding; JPEG output then has a single channel
+    - `palette`: List of color strings every pixel's color is mapped to the nearest of before encoding
+    - `palette_dither`: Whether pixels are ordered-dithered before they are mapped to the palette
+    - `opacity`: Optional multiplier (a float from 0.0 to 1.0) for the alpha of the content before it is composited over the background
+    - `bit_depth`: Optional bits per channel, 8 or 16; 16 is only accepted for PNG
+    - `scale`: Optional multiplier (a float) for the intrinsic size; only accepted when `width` and `height` are nil
+    - `dpr`: Optional device pixel ratio (a float) multiplying the intrinsic size and percentage dimensions, but not pixel dimensions; an error together with `scale`
+    - `progressive`: Whether JPEG output is progressive (SOF2) rather than baseline
+    - `subsampling`: Optional JPEG chroma subsampling ("4:4:4", "4:2:2" or "4:2:0", defaults to "4:4:4")
+    - `metadata`: List of `{keyword, text}` tuples written as PNG tEXt chunks, or iTXt when the text isn't Latin-1; must be empty for other formats
+    - `dpi_output`: Optional resolution (a float) written to PNG output as a pHYs chunk in pixels per meter; must be nil for other formats
+    - `icc_profile`: Optional binary RGB ICC profile embedded in PNG (iCCP) or JPEG (APP2) output; an error for other formats
+    - `srgb`: Whether PNG output gets an sRGB chunk and JPEG output an sRGB ICC profile; an error together with `icc_profile`
+    - `shape_rendering`: Optional default shape rendering ("optimize_speed", "crisp_edges" or "geometric_precision")
+    - `text_rendering`: Optional default text rendering ("optimize_speed", "optimize_legibility" or "geometric_precision")
+    - `image_rendering`: Optional default image rendering ("optimize_quality", "optimize_speed", "smooth", "high_quality", "crisp_edges" or "pixelated")
+    - `languages`: List of BCP 47 tags matched against `systemLanguage` in `switch` elements; empty uses usvg's default of `["en"]`
+    - `antialias`: Whether shapes and text are anti-aliased; `false` forces crisp edges regardless of the SVG's own rendering properties
+    - `background_gradient`: Optional `{top, bottom}` tuple of color strings for a vertical gradient composited under the output for every format, replacing `background_color`
+    - `offset_x` / `offset_y`: Optional SVG user-space point (floats) moved to the output's origin before zooming and fitting
+    - `zoom`: Optional positive magnification (a float) about the offset point, composed with the fit scale
+    - `view_box`: Optional `{min_x, min_y, width, height}` tuple of floats, in user units, that replaces the source viewBox as the rendered region; an error together with `node_id`
+    - `timeout_ms`: Optional positive number of milliseconds before the call returns a `:timeout` error; the render runs on a worker thread that isn't stopped
+    - `dither`: Whether GIF output is dithered when it has to be quantized to 256 colors
+    - `alpha_threshold`: Optional alpha from 1 to 255 below which GIF pixels become transparent (defaults to 128); ICO entries are only thresholded when it's given
+    - `max_pixels`: Optional limit on the pixels of any image allocated, including the supersampled render (defaults to 64 megapixels)
 
   ## Returns
   - `{:ok, binary}` on success
-  - `{:error, reason}` on failure
+  - `{:error, {reason, message}}` on failure, where `reason` is an atom such as `:parse_failed`
+  """
+  def convert_svg(_svg_data, _options), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Converts SVG data to image format and reports the rendered dimensions and parse warnings.
+
+  Takes the same parameters as `convert_svg/2`.
+
+  ## Returns
+  - `{:ok, {binary, width, height, warnings}}` on success, where `warnings` is a list of strings about unresolved references and missing fonts
+  - `{:error, {reason, message}}` on failure
   """
-  def convert_svg(_svg_data, _format, _width, _height, _background_color, _replacements),
+  def convert_svg_with_info(_svg_data, _options), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Renders SVG data and returns the unencoded pixels.
+
+  ## Parameters
+  - `svg_data`: Binary SVG data
+  - `options`: Map of conversion options as for `convert_svg/2`; `format` is ignored
+  - `channels`: `4` for RGBA8 or `3` for RGB8, flattened onto the background color
+  - `premultiplied`: Whether RGBA data keeps premultiplied alpha instead of straight alpha
+
+  ## Returns
+  - `{:ok, {binary, width, height, channels}}` on success, where `binary` is `width * height * channels` bytes
+  - `{:error, {reason, message}}` on failure
+  """
+  def convert_svg_to_rgba(_svg_data, _options, _channels, _premultiplied),
     do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Converts SVG data to image format and writes it to `path`, replacing any existing file.
+
+  Takes the same parameters as `convert_svg/2`, plus the output path.
+
+  ## Returns
+  - `:ok` on success
+  - `{:error, {reason, message}}` on failure, with `:write_failed` if the file couldn't be written
+  """
+  def convert_svg_to_file(_svg_data, _options, _path), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Converts SVG data to image format and returns it as a base64 `data:` URI.
+
+  Takes the same parameters as `convert_svg/2`. The URI carries the MIME type of `format`.
+
+  ## Returns
+  - `{:ok, data_uri}` on success
+  - `{:error, {reason, message}}` on failure
+  """
+  def convert_svg_to_data_uri(_svg_data, _options), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Returns the MIME type of images encoded as `format`, a format string as in `convert_svg/2`.
+
+  Unlike the other NIFs this runs on a normal scheduler, since it does no rendering.
+
+  ## Returns
+  - `{:ok, mime_type}` for a format the encoder supports
+  - `{:error, {:unsupported_format, message}}` otherwise
+  """
+  def mime_for_format(_format), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Returns the canonical file extension, without a dot, of images encoded as `format`, a
+  format string as in `convert_svg/2`. Aliases share one extension, so `"jpeg"` gives `"jpg"`.
+
+  Like `mime_for_format/1`, this runs on a normal scheduler.
+
+  ## Returns
+  - `{:ok, extension}` for a format the encoder supports
+  - `{:error, {:unsupported_format, message}}` otherwise
+  """
+  def extension_for_format(_format), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Returns the versions of the rendering crates this NIF was compiled against, as a map with
+  the `:usvg`, `:resvg`, `:tiny_skia` and `:image` keys and version strings as values.
+
+  The versions are read from Cargo.lock when the NIF is built, and are `"unknown"` if it
+  couldn't be read. Runs on a normal scheduler.
+  """
+  def versions(), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Parses SVG data and reports its intrinsic size without rendering it.
+
+  Takes the same parameters as `convert_svg/2`; `format`, `width`, `height` and `node_id`
+  are ignored. A missing or percentage width or height falls back to the viewBox.
+
+  ## Returns
+  - `{:ok, {width, height}}` on success, as floats in pixels
+  - `{:error, {reason, message}}` on failure
+  """
+  def svg_intrinsic_size(_svg_data, _options), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Checks that SVG data parses, without rendering it.
+
+  Takes the same parameters as `convert_svg/2`; `format`, `width`, `height` and `node_id`
+  are ignored. Replacements are applied before parsing.
+
+  ## Returns
+  - `:ok` if the SVG is valid
+  - `{:error, {reason, message}}` otherwise, with the parser's error text in `message`
+  """
+  def validate_svg(_svg_data, _options), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Parses SVG data and lists the ids of its rendered elements without rendering it.
+
+  Takes the same parameters as `convert_svg/2`; `format`, `width`, `height` and `node_id`
+  are ignored.
+
+  ## Returns
+  - `{:ok, ids}` on success, a list of id strings in document order
+  - `{:error, {reason, message}}` on failure
+  """
+  def svg_element_ids(_svg_data, _options), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Parses SVG data and counts the nodes of its rendered tree without rendering it.
+
+  Takes the same parameters as `convert_svg/2`; `format`, `width`, `height` and `node_id`
+  are ignored.
+
+  ## Returns
+  - `{:ok, %{paths: paths, groups: groups, texts: texts, images: images}}` on success
+  - `{:error, {reason, message}}` on failure
+  """
+  def svg_complexity(_svg_data, _options), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Converts SVG data to an ICO with one square PNG entry per size.
+
+  ## Parameters
+  - `svg_data`: SVG content as a binary, either plain markup or gzip-compressed SVGZ
+  - `options`: Map of conversion options as for `convert_svg/2`; `format`, `width` and `height` are ignored
+  - `sizes`: Non-empty list of entry sizes in pixels, each from 1 to 256
+
+  ## Returns
+  - `{:ok, binary}` on success
+  - `{:error, {reason, message}}` on failure
+  """
+  def convert_svg_to_ico(_svg_data, _options, _sizes), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Converts SVG data to the standard favicon set, rendering every image from one parsed tree.
+
+  Takes the same parameters as `convert_svg/2`; `format`, `width` and `height` are ignored.
+
+  ## Returns
+  - `{:ok, [favicon: ico, apple_touch_icon: png, icon_192: png, icon_512: png]}` on success
+  - `{:error, {reason, message}}` on failure
+  """
+  def convert_svg_to_favicon(_svg_data, _options), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Converts a sequence of SVG frames to an animated GIF.
+
+  ## Parameters
+  - `frames`: Non-empty list of SVG binaries, one per frame
+  - `delays`: List of frame delays in milliseconds, one per frame
+  - `options`: Map of conversion options as for `convert_svg/2`, used for every frame; `format` is ignored
+  - `loop_count`: Number of repeats after the first play, or `nil` to loop forever
+
+  ## Returns
+  - `{:ok, binary}` on success
+  - `{:error, {reason, message}}` on failure, including when the frames render at different sizes
+  """
+  def convert_svgs_to_animated_gif(_frames, _delays, _options, _loop_count),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Renders SVGs into the cells of a grid on one PNG sprite sheet.
+
+  ## Parameters
+  - `svgs`: Non-empty list of SVG binaries, one per sprite
+  - `options`: Map of conversion options as for `convert_svg/2`, used for every sprite; `format`, `width` and `height` are ignored
+  - `columns`: Positive number of grid columns
+  - `cell_size`: `{width, height}` of each cell in pixels
+  - `gutter`: Pixels between neighboring cells
+
+  ## Returns
+  - `{:ok, {binary, width, height, sprites}}` on success, where `sprites` holds an `{x, y, width, height}` tuple per SVG
+  - `{:error, {reason, message}}` on failure
+  """
+  def convert_svgs_to_sprite_sheet(_svgs, _options, _columns, _cell_size, _gutter),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Renders an SVG once and slices the image into a grid of PNG tiles.
+
+  ## Parameters
+  - `svg_data`: Binary SVG data
+  - `options`: Map of conversion options as for `convert_svg/2`; `format` is ignored
+  - `tile_size`: `{width, height}` of each tile in pixels
+
+  ## Returns
+  - `{:ok, tiles}` on success, a list of `{{column, row}, binary}` tuples row by row from the top-left tile
+  - `{:error, {reason, message}}` on failure
+  """
+  def convert_svg_to_tiles(_svg_data, _options, _tile_size),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Renders an SVG once and encodes the image to several formats.
+
+  ## Parameters
+  - `svg_data`: Binary SVG data
+  - `options`: Map of conversion options as for `convert_svg/2`; `format` is ignored
+  - `formats`: Non-empty list of format strings, such as `["png", "jpg"]`
+
+  ## Returns
+  - `{:ok, outputs}` on success, a list of `{format, binary}` tuples in the order of `formats`
+  - `{:error, {reason, message}}` on failure
+  """
+  def convert_svg_multi(_svg_data, _options, _formats), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Converts a list of `{svg_data, options}` jobs in parallel on a thread pool.
+
+  ## Parameters
+  - `jobs`: List of `{svg_data, options}` tuples, each taking the same parameters as `convert_svg/2`
+  - `max_concurrency`: Optional positive number of jobs rendered at once; `nil` uses one thread per CPU
+
+  ## Returns
+  - `{:ok, results}` with one `{:ok, binary}` or `{:error, {reason, message}}` result per job, in order
+  - `{:error, {reason, message}}` if the thread pool can't be created
+  """
+  def convert_svg_batch(_jobs, _max_concurrency), do: :erlang.nif_error(:nif_not_loaded)
 end
@@ -38,8 +38,8 @@ defmodule Svgager.MixProject do
         "Issues" => "https://github.com/OutdoorMap/svgager/issues"
       },
       files: ~w(lib native/svgager_native/.cargo native/svgager_native/src
-                native/svgager_native/Cargo.* .formatter.exs mix.exs README.md
-                LICENSE checksum-*.exs config)
+                native/svgager_native/Cargo.* native/svgager_native/build.rs .formatter.exs
+                mix.exs README.md LICENSE checksum-*.exs config)
     ]
   end
 
@@ -0,0 +1,93 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Dependencies whose resolved versions are compiled in for `versions/0`, with the
+/// environment variable each one is exposed as
+const REPORTED: [(&str, &str); 4] = [
+    ("usvg", "SVGAGER_USVG_VERSION"),
+    ("resvg", "SVGAGER_RESVG_VERSION"),
+    ("tiny-skia", "SVGAGER_TINY_SKIA_VERSION"),
+    ("image", "SVGAGER_IMAGE_VERSION"),
+];
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo");
+    let lock_path = Path::new(&manifest_dir).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock_path.display());
+
+    // Cargo writes the lock file while resolving, before any build script runs
+    let lock = fs::read_to_string(&lock_path).unwrap_or_default();
+    let packages = packages(&lock);
+    let this_package = env::var("CARGO_PKG_NAME").expect("CARGO_PKG_NAME is set by cargo");
+    let dependencies = packages
+        .iter()
+        .find(|package| package.name == this_package)
+        .map(|package| package.dependencies.as_slice())
+        .unwrap_or_default();
+
+    for (name, variable) in REPORTED {
+        let version =
+            resolved_version(&packages, dependencies, name).unwrap_or_else(|| "unknown".into());
+        println!("cargo:rustc-env={}={}", variable, version);
+    }
+}
+
+struct Package {
+    name: String,
+    version: String,
+    dependencies: Vec<String>,
+}
+
+/// Reads the `[[package]]` entries of a lock file, which only needs the name, version and
+/// dependency lines
+fn packages(lock: &str) -> Vec<Package> {
+    let mut packages = Vec::new();
+    let mut in_dependencies = false;
+    for line in lock.lines().map(str::trim) {
+        if line == "[[package]]" {
+            packages.push(Package {
+                name: String::new(),
+                version: String::new(),
+                dependencies: Vec::new(),
+            });
+            in_dependencies = false;
+            continue;
+        }
+        let Some(package) = packages.last_mut() else {
+            continue;
+        };
+        if in_dependencies {
+            match line {
+                "]" => in_dependencies = false,
+                dependency => package
+                    .dependencies
+                    .push(unquote(dependency.trim_end_matches(','))),
+            }
+        } else if let Some(name) = line.strip_prefix("name = ") {
+            package.name = unquote(name);
+        } else if let Some(version) = line.strip_prefix("version = ") {
+            package.version = unquote(version);
+        } else if line == "dependencies = [" {
+            in_dependencies = true;
+        }
+    }
+    packages
+}
+
+/// Returns the version of `name` this crate was resolved against. The lock file only names
+/// the version of a dependency when several versions of it are in the graph.
+fn resolved_version(packages: &[Package], dependencies: &[String], name: &str) -> Option<String> {
+    let dependency = dependencies
+        .iter()
+        .find(|dependency| dependency.split(' ').next() == Some(name))?;
+    let version = dependency.split(' ').nth(1);
+    packages
+        .iter()
+        .find(|package| package.name == name && version.is_none_or(|v| v == package.version))
+        .map(|package| package.version.clone())
+}
+
+fn unquote(value: &str) -> String {
+    value.trim().trim_matches('"').to_string()
+}
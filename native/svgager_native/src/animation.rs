@@ -0,0 +1,86 @@
+use crate::converter::{
+    catch_panics, gif_frame, output_pixels, parse_svg, render_pixmap, validate_alpha_threshold,
+    ConvertOptions, DEFAULT_ALPHA_THRESHOLD,
+};
+use crate::error::ConverterError;
+
+/// Converts a sequence of SVGs to an animated GIF, showing each frame for the delay in
+/// milliseconds at the same index. Every frame is rendered with `options` (whose format is
+/// ignored) and must come out at the same size, and is quantized like a static GIF, so
+/// `dither` and `alpha_threshold` apply. `loop_count` is how many times the animation
+/// repeats after the first play, and `None` loops forever.
+pub fn convert_svgs_to_animated_gif(
+    frames: &[&[u8]],
+    delays: &[u32],
+    options: &ConvertOptions,
+    loop_count: Option<u16>,
+) -> Result<Vec<u8>, ConverterError> {
+    catch_panics(|| {
+        if frames.is_empty() {
+            return Err(ConverterError::InvalidOption(
+                "Invalid frames: at least one frame is required".to_string(),
+            ));
+        }
+        if delays.len() != frames.len() {
+            return Err(ConverterError::InvalidOption(format!(
+                "Invalid delays: expected one delay per frame ({}), got {}",
+                frames.len(),
+                delays.len()
+            )));
+        }
+
+        // Frames are flattened onto the background color, since transparent areas would
+        // otherwise show the previous frame through them
+        let options = ConvertOptions {
+            format: "gif".to_string(),
+            flatten: true,
+            ..options.clone()
+        };
+        let alpha_threshold =
+            validate_alpha_threshold(options.alpha_threshold)?.unwrap_or(DEFAULT_ALPHA_THRESHOLD);
+        let mut gif_frames = Vec::with_capacity(frames.len());
+        let mut frame_size = None;
+        for (index, (svg_data, &delay)) in frames.iter().zip(delays).enumerate() {
+            let parsed = parse_svg(svg_data, &options)?;
+            let pixmap = render_pixmap(&parsed, &options)?;
+
+            let (width, height) = (pixmap.width(), pixmap.height());
+            let (first_width, first_height) = *frame_size.get_or_insert((width, height));
+            if (width, height) != (first_width, first_height) {
+                return Err(ConverterError::InvalidDimensions(format!(
+                    "Invalid frames: frame {} is {}x{}, but the first frame is {}x{}",
+                    index, width, height, first_width, first_height
+                )));
+            }
+
+            // A translucent background leaves translucent pixels, which become transparent
+            // below the alpha threshold
+            let data = output_pixels(&pixmap, &options)?;
+            let mut frame = gif_frame(&data, width, height, true, alpha_threshold, options.dither)?;
+            // GIF delays are in hundredths of a second
+            frame.delay = u16::try_from(delay / 10).unwrap_or(u16::MAX);
+            // Each frame is cleared before the next, whose transparent areas would otherwise
+            // show it
+            frame.dispose = gif::DisposalMethod::Background;
+            gif_frames.push(frame);
+        }
+
+        let (width, height) = (gif_frames[0].width, gif_frames[0].height);
+        let mut output = Vec::new();
+        {
+            let encode_error = |e: gif::EncodingError| {
+                ConverterError::EncodeFailed(format!("Failed to encode GIF: {}", e))
+            };
+            let mut encoder =
+                gif::Encoder::new(&mut output, width, height, &[]).map_err(encode_error)?;
+            encoder
+                .set_repeat(loop_count.map_or(gif::Repeat::Infinite, gif::Repeat::Finite))
+                .map_err(encode_error)?;
+            for frame in &gif_frames {
+                encoder.write_frame(frame).map_err(encode_error)?;
+            }
+        }
+
+        Ok(output)
+    })
+}
@@ -0,0 +1,143 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use crate::converter::{ConvertOptions, ParsedSvg};
+
+/// Number of parsed trees kept before the least recently used one is evicted
+const CAPACITY: usize = 32;
+
+static TREE_CACHE: OnceLock<Mutex<LruCache>> = OnceLock::new();
+
+/// The options that affect parsing. Text is converted to paths, and the rendering hints,
+/// anti-aliasing, `switch` languages and external images are resolved while parsing, so they
+/// are part of the cache key alongside the font settings and the SVG itself.
+#[derive(Clone, PartialEq, Hash)]
+struct ParseSettings {
+    font_family: Option<String>,
+    font_size: Option<u32>,
+    dpi: Option<u32>,
+    load_system_fonts: bool,
+    font_dirs: Vec<String>,
+    shape_rendering: Option<String>,
+    text_rendering: Option<String>,
+    image_rendering: Option<String>,
+    languages: Vec<String>,
+    antialias: bool,
+    resource_dir: Option<String>,
+}
+
+impl ParseSettings {
+    fn from_options(options: &ConvertOptions) -> Self {
+        ParseSettings {
+            font_family: options.font_family.clone(),
+            font_size: options.font_size.map(f32::to_bits),
+            dpi: options.dpi.map(f32::to_bits),
+            load_system_fonts: options.load_system_fonts,
+            font_dirs: options.font_dirs.clone(),
+            shape_rendering: options.shape_rendering.clone(),
+            text_rendering: options.text_rendering.clone(),
+            image_rendering: options.image_rendering.clone(),
+            languages: options.languages.clone(),
+            antialias: options.antialias,
+            resource_dir: options.resource_dir.clone(),
+        }
+    }
+}
+
+struct CacheEntry {
+    svg: String,
+    settings: ParseSettings,
+    parsed: ParsedSvg,
+}
+
+/// A small least-recently-used cache of parsed trees, keyed by a hash of the processed SVG
+/// and its parse settings. Entries keep their full key so a hash collision is a miss rather
+/// than a wrong tree.
+struct LruCache {
+    entries: HashMap<u64, CacheEntry>,
+    order: VecDeque<u64>,
+}
+
+impl LruCache {
+    fn new() -> Self {
+        LruCache {
+            entries: HashMap::with_capacity(CAPACITY),
+            order: VecDeque::with_capacity(CAPACITY),
+        }
+    }
+
+    fn get(&mut self, key: u64, svg: &str, settings: &ParseSettings) -> Option<ParsedSvg> {
+        let entry = self.entries.get(&key)?;
+        if entry.svg != svg || entry.settings != *settings {
+            return None;
+        }
+        let parsed = entry.parsed.clone();
+        self.touch(key);
+        Some(parsed)
+    }
+
+    fn insert(&mut self, key: u64, entry: CacheEntry) {
+        if self.entries.insert(key, entry).is_some() {
+            self.touch(key);
+            return;
+        }
+        self.order.push_back(key);
+        if self.order.len() > CAPACITY {
+            if let Some(evicted) = self.order.pop_front() {
+                self.entries.remove(&evicted);
+            }
+        }
+    }
+
+    fn touch(&mut self, key: u64) {
+        if let Some(position) = self.order.iter().position(|k| *k == key) {
+            self.order.remove(position);
+        }
+        self.order.push_back(key);
+    }
+}
+
+/// Returns the cached tree for `svg` parsed with `options`, calling `parse` and caching its
+/// result (including its parse warnings) on a miss.
+///
+/// `usvg::Tree` is expensive to deep-clone, so trees are shared behind an `Arc` instead;
+/// rendering only needs a shared reference. The tradeoff is memory: each entry holds the
+/// processed SVG (to verify hits) and the parsed tree, including its font database, until
+/// it is evicted. Parsing happens outside the lock, so two concurrent misses for the same
+/// SVG both parse it and the later one wins. Fonts added to `font_dirs` after a tree is
+/// cached aren't picked up until that entry is evicted.
+pub fn get_or_parse<E>(
+    svg: &str,
+    options: &ConvertOptions,
+    parse: impl FnOnce() -> Result<ParsedSvg, E>,
+) -> Result<ParsedSvg, E> {
+    let settings = ParseSettings::from_options(options);
+    let mut hasher = DefaultHasher::new();
+    svg.hash(&mut hasher);
+    settings.hash(&mut hasher);
+    let key = hasher.finish();
+
+    let cache = TREE_CACHE.get_or_init(|| Mutex::new(LruCache::new()));
+    if let Some(parsed) = lock(cache).get(key, svg, &settings) {
+        return Ok(parsed);
+    }
+
+    let parsed = parse()?;
+    lock(cache).insert(
+        key,
+        CacheEntry {
+            svg: svg.to_string(),
+            settings,
+            parsed: parsed.clone(),
+        },
+    );
+    Ok(parsed)
+}
+
+// A panic while holding the lock can't leave the cache half-updated, so a poisoned lock is
+// still safe to use
+fn lock(cache: &Mutex<LruCache>) -> std::sync::MutexGuard<'_, LruCache> {
+    cache.lock().unwrap_or_else(PoisonError::into_inner)
+}
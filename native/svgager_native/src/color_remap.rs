@@ -0,0 +1,157 @@
+use std::ops::Range;
+
+use crate::color::{parse_color, svg_color};
+use crate::converter::parse_document;
+use crate::error::ConverterError;
+
+/// The properties whose colors are remapped, including the colors of gradient stops
+const PROPERTIES: [&str; 3] = ["fill", "stroke", "stop-color"];
+
+/// A `(from, to, tolerance)` remapping: any fill, stroke or gradient stop whose channels are
+/// each within `tolerance` of `from`, alpha included, is painted `to` instead
+pub type ColorRemap = (String, String, u8);
+
+/// Recolors the fills, strokes and gradient stops of the SVG that are near one of the
+/// `remaps` colors, so shades such as `#FE0101` and `#FF0000` can be normalized to one color.
+/// usvg's tree can't be modified once parsed, so the parsed document is walked instead and
+/// the matching values are rewritten: `fill`, `stroke` and `stop-color` attributes, and the
+/// same properties in style attributes and style sheets. The first remapping that matches a
+/// color wins. References such as `url(#gradient)`, `none` and `currentColor` are left
+/// alone, though the referenced gradient's stops are remapped. Returns `None` when nothing
+/// was recolored.
+pub fn remap_colors(svg: &str, remaps: &[ColorRemap]) -> Result<Option<String>, ConverterError> {
+    let remaps = remaps
+        .iter()
+        .map(|(from, to, tolerance)| {
+            let from = parse_color(from).map_err(ConverterError::InvalidColor)?;
+            let to = parse_color(to).map_err(ConverterError::InvalidColor)?;
+            Ok((from, svg_color(to), *tolerance))
+        })
+        .collect::<Result<Vec<_>, ConverterError>>()?;
+    if remaps.is_empty() {
+        return Ok(None);
+    }
+    let Some(doc) = parse_document(svg) else {
+        return Ok(None);
+    };
+
+    let remap = |value: &str| -> Option<&str> {
+        let value = value.trim();
+        // The color parser also takes bare hex digits, which SVG doesn't
+        if !value.starts_with('#') && value.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let color = parse_color(value).ok()?;
+        remaps
+            .iter()
+            .find(|(from, _, tolerance)| within(color, *from, *tolerance))
+            .map(|(_, to, _)| to.as_str())
+    };
+
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    for node in doc.descendants() {
+        if node.is_text()
+            && node
+                .parent_element()
+                .is_some_and(|p| p.has_tag_name("style"))
+        {
+            let range = node.range();
+            if let Some(css) = remap_declarations(&svg[range.clone()], &remap) {
+                edits.push((range, css));
+            }
+        }
+        for attribute in node.attributes().filter(|a| a.namespace().is_none()) {
+            let range = attribute.range_value();
+            if PROPERTIES.contains(&attribute.name()) {
+                if let Some(color) = remap(attribute.value()) {
+                    edits.push((range, color.to_string()));
+                }
+            } else if attribute.name() == "style" {
+                if let Some(style) = remap_declarations(&svg[range.clone()], &remap) {
+                    edits.push((range, style));
+                }
+            }
+        }
+    }
+    if edits.is_empty() {
+        return Ok(None);
+    }
+
+    edits.sort_by_key(|(range, _)| range.start);
+    let mut remapped = String::with_capacity(svg.len());
+    let mut end = 0;
+    for (range, value) in edits {
+        remapped.push_str(&svg[end..range.start]);
+        remapped.push_str(&value);
+        end = range.end;
+    }
+    remapped.push_str(&svg[end..]);
+    Ok(Some(remapped))
+}
+
+/// Rewrites the colors of the `fill`, `stroke` and `stop-color` declarations in a style
+/// attribute or style sheet, keeping any `!important`. Returns `None` when none of them were remapped.
+fn remap_declarations<'a>(css: &str, remap: &impl Fn(&str) -> Option<&'a str>) -> Option<String> {
+    let mut remapped = String::with_capacity(css.len());
+    let mut rest = css;
+    let mut changed = false;
+    while let Some((start, property)) = next_property(rest) {
+        let value_start = start + property;
+        let value_end = rest[value_start..]
+            .find([';', '}'])
+            .map_or(rest.len(), |end| value_start + end);
+        let value = &rest[value_start..value_end];
+        let color = value.split('!').next().unwrap_or(value);
+
+        remapped.push_str(&rest[..value_start]);
+        match remap(color) {
+            Some(to) => {
+                changed = true;
+                // Keep the whitespace around the color, so `fill: red !important` stays valid
+                let leading = color.len() - color.trim_start().len();
+                let trailing = color.len() - color.trim_end().len();
+                remapped.push_str(&color[..leading]);
+                remapped.push_str(to);
+                remapped.push_str(&value[color.len() - trailing..]);
+            }
+            None => remapped.push_str(value),
+        }
+        rest = &rest[value_end..];
+    }
+    remapped.push_str(rest);
+    changed.then_some(remapped)
+}
+
+/// Finds the next `fill`, `stroke` or `stop-color` declaration in `css`, returning where it
+/// starts and the length of its name and colon
+fn next_property(css: &str) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    while offset < css.len() {
+        let (index, property) = PROPERTIES
+            .iter()
+            .filter_map(|property| Some((css[offset..].find(property)? + offset, *property)))
+            .min_by_key(|(index, _)| *index)?;
+        offset = index + property.len();
+
+        // A declaration starts the style or follows a block, another declaration or space,
+        // which also rules out longer names such as `paint-fill`
+        let starts_declaration = css[..index]
+            .chars()
+            .next_back()
+            .is_none_or(|c| c.is_whitespace() || matches!(c, '{' | ';'));
+        let after = &css[offset..];
+        let colon = after.len() - after.trim_start().len();
+        if starts_declaration && after[colon..].starts_with(':') {
+            return Some((index, property.len() + colon + 1));
+        }
+    }
+    None
+}
+
+fn within(color: (u8, u8, u8, u8), target: (u8, u8, u8, u8), tolerance: u8) -> bool {
+    let (r, g, b, a) = color;
+    let (tr, tg, tb, ta) = target;
+    [(r, tr), (g, tg), (b, tb), (a, ta)]
+        .iter()
+        .all(|(channel, target)| channel.abs_diff(*target) <= tolerance)
+}
@@ -1,143 +1,2837 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+#[cfg(feature = "avif")]
+use image::codecs::avif::AvifEncoder;
+#[cfg(feature = "exr")]
+use image::codecs::openexr::OpenExrEncoder;
 use image::{
-    codecs::jpeg::JpegEncoder, codecs::png::PngEncoder, ImageBuffer, ImageEncoder, ImageFormat,
-    Rgba,
+    codecs::bmp::BmpEncoder,
+    codecs::farbfeld::FarbfeldEncoder,
+    codecs::pnm::{PnmEncoder, PnmSubtype, SampleEncoding},
+    codecs::qoi::QoiEncoder,
+    codecs::tga::TgaEncoder,
+    codecs::tiff::TiffEncoder,
+    codecs::webp::WebPEncoder,
+    imageops::FilterType as ResizeFilter,
+    ExtendedColorType, ImageEncoder, RgbaImage,
 };
-use std::io::Cursor;
+use jpeg_encoder::SamplingFactor;
+use regex::Regex;
+use rustler::NifMap;
+use std::borrow::Cow;
+use std::io::{Cursor, Write};
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use usvg::{ImageRendering, ShapeRendering, TextRendering};
 
+use crate::cache;
+use crate::color::{parse_color, svg_color};
+use crate::color_remap::{remap_colors, ColorRemap};
+use crate::css_vars::resolve_css_vars;
+use crate::error::ConverterError;
+use crate::fonts::font_database;
+use crate::palette::{parse_palette, quantize};
+use crate::pixmap_pool::PixmapPool;
+use crate::warnings;
+
+/// Raw bytes passed in as a binary. They're copied out of the term so the options don't
+/// borrow the NIF environment and can move to a worker thread.
+#[derive(Clone, Default)]
+pub struct Bytes(pub Vec<u8>);
+
+impl<'a> rustler::Decoder<'a> for Bytes {
+    fn decode(term: rustler::Term<'a>) -> rustler::NifResult<Self> {
+        Ok(Bytes(term.decode::<rustler::Binary>()?.as_slice().to_vec()))
+    }
+}
+
+// Options are only ever decoded, but the `NifMap` derive needs both directions
+impl rustler::Encoder for Bytes {
+    fn encode<'a>(&self, env: rustler::Env<'a>) -> rustler::Term<'a> {
+        let mut binary = rustler::OwnedBinary::new(self.0.len()).expect("binary allocation failed");
+        binary.as_mut_slice().copy_from_slice(&self.0);
+        binary.release(env).encode(env)
+    }
+}
+
+/// An output width or height as given: a number of pixels, or a string that holds either a
+/// number of pixels or a percentage of the intrinsic size such as `"150%"`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Dimension {
+    Pixels(u32),
+    Text(String),
+}
+
+impl<'a> rustler::Decoder<'a> for Dimension {
+    fn decode(term: rustler::Term<'a>) -> rustler::NifResult<Self> {
+        match term.decode::<u32>() {
+            Ok(pixels) => Ok(Dimension::Pixels(pixels)),
+            Err(_) => Ok(Dimension::Text(term.decode()?)),
+        }
+    }
+}
+
+impl rustler::Encoder for Dimension {
+    fn encode<'a>(&self, env: rustler::Env<'a>) -> rustler::Term<'a> {
+        match self {
+            Dimension::Pixels(pixels) => pixels.encode(env),
+            Dimension::Text(text) => text.encode(env),
+        }
+    }
+}
+
+/// A `(search, replace, max, scope)` replacement; `max` limits how many occurrences are
+/// replaced, front to back, and `None` replaces all of them. `scope` is `any`, or `attr` or
+/// `text` to only match inside attribute values or element text.
+pub type Replacement = (String, String, Option<usize>, String);
+
+#[derive(Clone, Default, NifMap)]
+pub struct ConvertOptions {
+    pub format: String,
+    pub width: Option<Dimension>,
+    pub height: Option<Dimension>,
+    pub background_color: Option<String>,
+    pub replacements: Vec<Replacement>,
+    pub regex_replacements: Vec<Replacement>,
+    pub quality: Option<u8>,
+    pub fit: Option<String>,
+    pub png_compression: Option<String>,
+    pub flatten: bool,
+    pub font_family: Option<String>,
+    pub font_size: Option<f32>,
+    pub load_system_fonts: bool,
+    pub font_dirs: Vec<String>,
+    pub dpi: Option<f32>,
+    pub node_id: Option<String>,
+    pub crop: Option<(u32, u32, u32, u32)>,
+    pub trim: bool,
+    pub padding: Option<u32>,
+    pub rotate: Option<u16>,
+    pub flip_h: bool,
+    pub flip_v: bool,
+    pub cache: bool,
+    pub ssaa: Option<u8>,
+    pub grayscale: bool,
+    pub opacity: Option<f32>,
+    pub bit_depth: Option<u8>,
+    pub max_pixels: Option<u64>,
+    pub scale: Option<f32>,
+    pub preserve_aspect: bool,
+    pub dither: bool,
+    pub progressive: bool,
+    pub subsampling: Option<String>,
+    pub metadata: Vec<(String, String)>,
+    pub dpi_output: Option<f32>,
+    pub shape_rendering: Option<String>,
+    pub text_rendering: Option<String>,
+    pub image_rendering: Option<String>,
+    pub languages: Vec<String>,
+    pub antialias: bool,
+    pub background_gradient: Option<(String, String)>,
+    pub offset_x: Option<f32>,
+    pub offset_y: Option<f32>,
+    pub zoom: Option<f32>,
+    pub escape_replacements: bool,
+    pub ignore_case_replacements: bool,
+    pub timeout_ms: Option<u64>,
+    pub view_box: Option<(f32, f32, f32, f32)>,
+    pub icc_profile: Option<Bytes>,
+    pub srgb: bool,
+    pub resize_filter: Option<String>,
+    pub css_vars: Vec<(String, String)>,
+    pub resource_dir: Option<String>,
+    pub default_fill: Option<String>,
+    pub dpr: Option<f32>,
+    pub color_remap: Vec<ColorRemap>,
+    pub strict: bool,
+    pub palette: Vec<String>,
+    pub palette_dither: bool,
+    pub alpha_threshold: Option<u8>,
+    pub lossless: bool,
+}
+
+pub struct ConvertedImage {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// Parse warnings about content that was dropped or substituted, such as unresolved
+    /// references and missing fonts
+    pub warnings: Vec<String>,
+}
+
+/// An SVG that has been preprocessed and parsed, ready to be rendered at any size
+#[derive(Clone)]
+pub struct ParsedSvg {
+    tree: Arc<usvg::Tree>,
+    warnings: Arc<[String]>,
+    viewport: Option<Viewport>,
+}
+
+/// Converts SVG data to an image, turning any panic raised while decoding, rendering or
+/// encoding into a `RenderFailed` error so untrusted input can't take down the scheduler
 pub fn convert_svg_to_image(
-    svg_data: String,
-    format: String,
-    width: Option<u32>,
-    height: Option<u32>,
-    background_color: Option<String>,
-    replacements: Vec<(String, String)>,
-) -> Result<Vec<u8>, String> {
-    // Step 1: Preprocess SVG with string replacements
-    let mut processed_svg = svg_data;
-    for (search, replace) in replacements {
-        processed_svg = processed_svg.replace(&search, &replace);
-    }
-
-    // Step 2: Parse SVG
-    let opt = usvg::Options::default();
-    let tree = usvg::Tree::from_str(&processed_svg, &opt)
-        .map_err(|e| format!("Failed to parse SVG: {}", e))?;
+    svg_data: &[u8],
+    options: &ConvertOptions,
+) -> Result<ConvertedImage, ConverterError> {
+    convert_svg_to_image_pooled(svg_data, options, &mut PixmapPool::default())
+}
+
+/// Converts SVG data to an image like `convert_svg_to_image`, rendering into pixmaps taken
+/// from `pool` and handing them back once the image is encoded
+pub fn convert_svg_to_image_pooled(
+    svg_data: &[u8],
+    options: &ConvertOptions,
+    pool: &mut PixmapPool,
+) -> Result<ConvertedImage, ConverterError> {
+    // A worker thread can't borrow the pool, so timed conversions allocate their own pixmaps
+    if options.timeout_ms.is_some() {
+        return with_timeout(svg_data, options, convert_svg_to_image);
+    }
+
+    catch_panics(|| {
+        let parsed = parse_svg(svg_data, options)?;
+        let pixmap = render_pixmap_pooled(&parsed, options, pool)?;
+        let image = encode_pixmap(&pixmap, options);
+        pool.put(pixmap);
+        Ok(ConvertedImage {
+            warnings: parsed.warnings.to_vec(),
+            ..image?
+        })
+    })
+}
+
+/// Parses SVG data and returns its intrinsic `(width, height)` in pixels without rendering.
+/// Replacements and DPI apply, and a missing or percentage size falls back to the viewBox
+/// as in a conversion without `width` and `height`; the format and size in `options` are
+/// ignored.
+pub fn svg_intrinsic_size(
+    svg_data: &[u8],
+    options: &ConvertOptions,
+) -> Result<(f32, f32), ConverterError> {
+    catch_panics(|| {
+        let options = ConvertOptions {
+            width: None,
+            height: None,
+            node_id: None,
+            ..options.clone()
+        };
+        let size = parse_svg(svg_data, &options)?.tree.size();
+
+        Ok((size.width(), size.height()))
+    })
+}
+
+/// Checks that SVG data can be decompressed, preprocessed and parsed, without rendering it.
+/// A missing or percentage intrinsic size isn't an error here, since the SVG still converts
+/// with an explicit width and height.
+pub fn validate_svg(svg_data: &[u8], options: &ConvertOptions) -> Result<(), ConverterError> {
+    catch_panics(|| {
+        let options = ConvertOptions {
+            width: Some(Dimension::Pixels(1)),
+            height: Some(Dimension::Pixels(1)),
+            node_id: None,
+            ..options.clone()
+        };
+
+        parse_svg(svg_data, &options).map(|_| ())
+    })
+}
+
+/// Parses SVG data and returns the id of every rendered element that has one, in document
+/// order, without rendering. These are the ids `node_id` accepts; ids only used inside
+/// `<defs>`, such as gradients, aren't included.
+pub fn svg_element_ids(
+    svg_data: &[u8],
+    options: &ConvertOptions,
+) -> Result<Vec<String>, ConverterError> {
+    catch_panics(|| {
+        // Ids don't depend on the intrinsic size, so skip normalizing it by fixing both
+        // dimensions
+        let options = ConvertOptions {
+            width: Some(Dimension::Pixels(1)),
+            height: Some(Dimension::Pixels(1)),
+            node_id: None,
+            ..options.clone()
+        };
+        let parsed = parse_svg(svg_data, &options)?;
+
+        let mut ids = Vec::new();
+        collect_element_ids(parsed.tree.root(), &mut ids);
+        Ok(ids)
+    })
+}
+
+fn collect_element_ids(group: &usvg::Group, ids: &mut Vec<String>) {
+    for node in group.children() {
+        if !node.id().is_empty() {
+            ids.push(node.id().to_string());
+        }
+        if let usvg::Node::Group(group) = node {
+            collect_element_ids(group, ids);
+        }
+    }
+}
+
+/// Counts of the nodes an SVG renders, by kind
+#[derive(Default, NifMap)]
+pub struct Complexity {
+    pub paths: u32,
+    pub groups: u32,
+    pub texts: u32,
+    pub images: u32,
+}
+
+/// Parses SVG data and counts the paths, groups, text nodes and images in the tree usvg
+/// renders, without rendering it. Shapes count as paths, and the contents of clip paths,
+/// masks, patterns, filter images and embedded SVG images are counted every time they are
+/// used, as the renderer draws them each time. The glyphs of a text node aren't counted as
+/// paths, and the root group isn't counted. Groups are counted as usvg creates them, which
+/// also wraps clipped, masked and filtered elements and images in groups of their own.
+pub fn svg_complexity(
+    svg_data: &[u8],
+    options: &ConvertOptions,
+) -> Result<Complexity, ConverterError> {
+    catch_panics(|| {
+        // The counts don't depend on the intrinsic size, so skip normalizing it by fixing
+        // both dimensions
+        let options = ConvertOptions {
+            width: Some(Dimension::Pixels(1)),
+            height: Some(Dimension::Pixels(1)),
+            node_id: None,
+            ..options.clone()
+        };
+        let parsed = parse_svg(svg_data, &options)?;
+
+        let mut complexity = Complexity::default();
+        count_nodes(parsed.tree.root(), &mut complexity);
+        Ok(complexity)
+    })
+}
+
+fn count_nodes(group: &usvg::Group, complexity: &mut Complexity) {
+    for node in group.children() {
+        match node {
+            usvg::Node::Group(_) => complexity.groups += 1,
+            usvg::Node::Path(_) => complexity.paths += 1,
+            usvg::Node::Image(_) => complexity.images += 1,
+            // A text node's subroot is its glyph outlines, which are part of the text
+            usvg::Node::Text(_) => {
+                complexity.texts += 1;
+                continue;
+            }
+        }
+        node.subroots(|subroot| count_nodes(subroot, complexity));
+        if let usvg::Node::Group(group) = node {
+            count_nodes(group, complexity);
+        }
+    }
+}
+
+/// Smallest buffer PNG output is streamed to a file through, which is also the most IDAT
+/// data each chunk holds
+const PNG_STREAM_BUFFER_LEN: usize = 64 * 1024;
+
+/// Converts SVG data like `convert_svg_to_image` and writes the encoded image to `path`,
+/// replacing any existing file. PNG output is encoded straight into the file a row at a time
+/// through a buffer of one row or 64 KiB, whichever is larger, so only the rendered pixmap is
+/// held in memory and never the encoded image. Other formats are encoded in memory first.
+pub fn convert_svg_to_file(
+    svg_data: &[u8],
+    options: &ConvertOptions,
+    path: &Path,
+) -> Result<(), ConverterError> {
+    if !options.format.eq_ignore_ascii_case("png") {
+        let image = convert_svg_to_image(svg_data, options)?;
+        return std::fs::write(path, &image.data).map_err(|e| write_error(path, e));
+    }
+
+    catch_panics(|| {
+        let settings = PngSettings::from_options(options)?;
+        let palette = parse_palette(&options.palette)?;
+        let pixmap = with_timeout(svg_data, options, |svg_data, options| {
+            let parsed = parse_svg(svg_data, options)?;
+            render_pixmap(&parsed, options)
+        })?;
+
+        let file = std::fs::File::create(path).map_err(|e| write_error(path, e))?;
+        // The stream writer already buffers whole chunks, so the file needs no buffer of its own
+        let encoder = settings.encoder(file, pixmap.width(), pixmap.height())?;
+        let result = stream_png(encoder, &pixmap, options, palette.as_deref(), &settings);
+        // Don't leave a truncated image behind
+        if result.is_err() {
+            let _ = std::fs::remove_file(path);
+        }
+        result.map_err(|e| match e {
+            png::EncodingError::IoError(e) => write_error(path, e),
+            e => png_encode_error(e),
+        })
+    })
+}
+
+/// Encodes a pixmap with `encoder` one row at a time, converting each row to straight
+/// alpha, grayscale, the parsed `palette` of `options` and the PNG's bit depth as it goes,
+/// like `output_pixels` does for the whole image
+fn stream_png<W: Write + 'static>(
+    encoder: png::Encoder<W>,
+    pixmap: &tiny_skia::Pixmap,
+    options: &ConvertOptions,
+    palette: Option<&[[u8; 3]]>,
+    settings: &PngSettings,
+) -> Result<(), png::EncodingError> {
+    let row_len = pixmap.width() as usize * 4;
+    let stream_len = row_len * settings.bit_depth as usize / 8;
+    let mut stream = encoder
+        .write_header()?
+        .into_stream_writer_with_size(stream_len.max(PNG_STREAM_BUFFER_LEN))?;
+
+    for (y, row) in pixmap.data().chunks_exact(row_len).enumerate() {
+        let row = straight_alpha(row);
+        let row: Cow<[u8]> = if options.grayscale {
+            Cow::Owned(rgba_to_grayscale(&row))
+        } else {
+            row
+        };
+        let row = match palette {
+            Some(palette) => {
+                let mut quantized = row.into_owned();
+                let width = pixmap.width() as usize;
+                quantize(&mut quantized, width, y, palette, options.palette_dither);
+                Cow::Owned(quantized)
+            }
+            None => row,
+        };
+        stream.write_all(&settings.samples(&row))?;
+    }
+    stream.finish()?;
+    Ok(())
+}
+
+/// Describes a failure to write `path`, naming a missing parent directory explicitly
+fn write_error(path: &Path, e: std::io::Error) -> ConverterError {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => {
+            ConverterError::WriteFailed(format!(
+                "Failed to write {}: directory {} does not exist",
+                path.display(),
+                parent.display()
+            ))
+        }
+        _ => ConverterError::WriteFailed(format!("Failed to write {}: {}", path.display(), e)),
+    }
+}
+
+/// Converts SVG data like `convert_svg_to_image` and returns the encoded image as a base64
+/// `data:` URI with the MIME type of the output format
+pub fn convert_svg_to_data_uri(
+    svg_data: &[u8],
+    options: &ConvertOptions,
+) -> Result<String, ConverterError> {
+    let mime = mime_type(&options.format)?;
+    let image = convert_svg_to_image(svg_data, options)?;
+
+    Ok(format!(
+        "data:{};base64,{}",
+        mime,
+        BASE64.encode(&image.data)
+    ))
+}
+
+/// Returns the MIME type of images encoded as `format`, accepting the same format names as
+/// `encode_image`
+pub fn mime_type(format: &str) -> Result<&'static str, ConverterError> {
+    format_details(format).map(|(mime, _)| mime)
+}
+
+/// Returns the canonical file extension of images encoded as `format`, without a leading
+/// dot, so aliases such as `jpeg` and `jpg` get the same one
+pub fn extension(format: &str) -> Result<&'static str, ConverterError> {
+    format_details(format).map(|(_, extension)| extension)
+}
+
+// Both are kept in one table so neither can fall out of step with the formats encoded
+fn format_details(format: &str) -> Result<(&'static str, &'static str), ConverterError> {
+    let details = match format.to_lowercase().as_str() {
+        "png" => ("image/png", "png"),
+        "jpg" | "jpeg" => ("image/jpeg", "jpg"),
+        "gif" => ("image/gif", "gif"),
+        "webp" => ("image/webp", "webp"),
+        "tiff" | "tif" => ("image/tiff", "tiff"),
+        #[cfg(feature = "avif")]
+        "avif" => ("image/avif", "avif"),
+        "qoi" => ("image/qoi", "qoi"),
+        "tga" => ("image/x-tga", "tga"),
+        "pnm" | "ppm" => ("image/x-portable-pixmap", "ppm"),
+        "bmp" => ("image/bmp", "bmp"),
+        "ff" | "farbfeld" => ("image/x-farbfeld", "ff"),
+        #[cfg(feature = "exr")]
+        "exr" => ("image/x-exr", "exr"),
+        _ => return Err(unsupported_format(format)),
+    };
+
+    Ok(details)
+}
+
+/// Runs `f`, turning a panic into a `RenderFailed` error with the panic message
+pub fn catch_panics<T>(f: impl FnOnce() -> Result<T, ConverterError>) -> Result<T, ConverterError> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown cause");
+
+        Err(ConverterError::RenderFailed(format!(
+            "Conversion panicked: {}",
+            message
+        )))
+    })
+}
+
+/// Runs `job` on the SVG data and options, on a worker thread when `options` sets
+/// `timeout_ms` so the caller gets a `Timeout` error once the budget runs out.
+///
+/// resvg can't be interrupted part way, so a job that runs over keeps going on its thread,
+/// holding its copy of the SVG and its pixmaps, and its result is dropped when it finishes.
+/// The timeout frees the caller, not the CPU.
+pub fn with_timeout<T: Send + 'static>(
+    svg_data: &[u8],
+    options: &ConvertOptions,
+    job: fn(&[u8], &ConvertOptions) -> Result<T, ConverterError>,
+) -> Result<T, ConverterError> {
+    let Some(timeout_ms) = options.timeout_ms else {
+        return job(svg_data, options);
+    };
+
+    let svg_data = svg_data.to_vec();
+    let options = ConvertOptions {
+        timeout_ms: None,
+        ..options.clone()
+    };
+    let (sender, receiver) = mpsc::sync_channel(1);
+    std::thread::Builder::new()
+        .name("svgager-render".to_string())
+        .spawn(move || {
+            // The receiver is gone once the caller has timed out, so a late result is dropped
+            let _ = sender.send(catch_panics(|| job(&svg_data, &options)));
+        })
+        .map_err(|e| {
+            ConverterError::RenderFailed(format!("Failed to start render thread: {}", e))
+        })?;
+
+    match receiver.recv_timeout(Duration::from_millis(timeout_ms)) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(ConverterError::Timeout(format!(
+            "Conversion timed out after {} ms",
+            timeout_ms
+        ))),
+        Err(RecvTimeoutError::Disconnected) => Err(ConverterError::RenderFailed(
+            "Render thread exited without a result".to_string(),
+        )),
+    }
+}
+
+/// Decompresses, preprocesses and parses the SVG. When `options` leaves a dimension unset,
+/// the intrinsic size it falls back to is normalized here
+pub fn parse_svg(svg_data: &[u8], options: &ConvertOptions) -> Result<ParsedSvg, ConverterError> {
+    let (width, height) = output_lengths(options)?;
+    validate_replacement_limits(&options.replacements)?;
+    validate_replacement_limits(&options.regex_replacements)?;
+    let regex_replacements = compile_regex_replacements(&options.regex_replacements)?;
+    validate_font_size(options.font_size)?;
+    validate_dpi(options.dpi)?;
+    rendering_hints(options)?;
+    validate_languages(&options.languages)?;
+
+    // Step 1: Decompress SVGZ input and preprocess SVG with string and regex replacements
+    // Plain input is borrowed straight from the caller's binary; only decompression and
+    // replacements allocate
+    let svg_data: Cow<[u8]> = if svg_data.starts_with(&GZIP_MAGIC) {
+        Cow::Owned(usvg::decompress_svgz(svg_data).map_err(|e| {
+            ConverterError::ParseFailed(format!("Failed to decompress SVGZ: {}", e))
+        })?)
+    } else {
+        Cow::Borrowed(svg_data)
+    };
+    let svg_text = std::str::from_utf8(&svg_data).map_err(|e| {
+        ConverterError::ParseFailed(format!(
+            "Failed to parse SVG: data is not valid UTF-8 (invalid byte sequence at offset {})",
+            e.valid_up_to()
+        ))
+    })?;
+
+    let mut processed_svg = Cow::Borrowed(svg_text);
+    for (search, replace, max, scope) in &options.replacements {
+        let scope = ReplacementScope::parse(search, scope)?;
+        let replace = replacement_value(replace, options);
+        let ignore_case = options.ignore_case_replacements && !search.is_empty();
+        let replace_part = |part: &str, max: Option<usize>| match max {
+            _ if ignore_case => replace_ignore_case(part, search, &replace, max),
+            Some(max) => part.replacen(search.as_str(), &replace, max),
+            None => part.replace(search.as_str(), &replace),
+        };
+        processed_svg = Cow::Owned(match scope {
+            ReplacementScope::Any => replace_part(&processed_svg, *max),
+            scope => replace_in_scope(&processed_svg, scope, *max, |part, max| {
+                let count = match max {
+                    Some(max) if ignore_case => part
+                        .to_ascii_lowercase()
+                        .matches(&search.to_ascii_lowercase())
+                        .take(max)
+                        .count(),
+                    Some(max) => part.matches(search.as_str()).take(max).count(),
+                    None => 0,
+                };
+                (replace_part(part, max), count)
+            }),
+        });
+    }
+    for (pattern, replace, max, scope) in &regex_replacements {
+        let replace = replacement_value(replace, options);
+        let replaced = match scope {
+            ReplacementScope::Any => replace_regex(pattern, &processed_svg, &replace, *max),
+            scope => Cow::Owned(replace_in_scope(
+                &processed_svg,
+                *scope,
+                *max,
+                |part, max| {
+                    let count = max.map_or(0, |max| pattern.find_iter(part).take(max).count());
+                    (
+                        replace_regex(pattern, part, &replace, max).into_owned(),
+                        count,
+                    )
+                },
+            )),
+        };
+        if let Cow::Owned(replaced) = replaced {
+            processed_svg = Cow::Owned(replaced);
+        }
+    }
+
+    // usvg leaves `var()` unresolved, so custom properties are substituted beforehand
+    if let Some(resolved) = resolve_css_vars(&processed_svg, &options.css_vars)? {
+        processed_svg = Cow::Owned(resolved);
+    }
+    if let Some(color) = &options.default_fill {
+        if let Some(filled) = apply_default_fill(&processed_svg, color)? {
+            processed_svg = Cow::Owned(filled);
+        }
+    }
+    if let Some(remapped) = remap_colors(&processed_svg, &options.color_remap)? {
+        processed_svg = Cow::Owned(remapped);
+    }
+
+    // The intrinsic size only matters when at least one output dimension is derived from it
+    // and the whole document is rendered, rather than an element or a view box
+    let is_pixels = |length| matches!(length, Some(Length::Pixels(_)));
+    if options.node_id.is_none()
+        && options.view_box.is_none()
+        && !(is_pixels(width) && is_pixels(height))
+    {
+        if let Some(normalized) = normalize_intrinsic_size(&processed_svg)? {
+            processed_svg = Cow::Owned(normalized);
+        }
+    }
+
+    // Step 2: Parse SVG, reusing a cached tree for the same processed SVG when requested
+    let parsed = if options.cache {
+        cache::get_or_parse(&processed_svg, options, || {
+            parse_tree(&processed_svg, options)
+        })
+    } else {
+        parse_tree(&processed_svg, options)
+    }?;
+
+    // Strict mode fails on anything the parse dropped or substituted instead of rendering
+    // without it
+    if options.strict && !parsed.warnings.is_empty() {
+        return Err(ConverterError::ParseFailed(format!(
+            "Failed to parse SVG in strict mode: {}",
+            parsed.warnings.join("; ")
+        )));
+    }
+    Ok(parsed)
+}
+
+/// Renders a parsed SVG with the given options and encodes it to the requested format
+pub fn render_image(
+    parsed: &ParsedSvg,
+    options: &ConvertOptions,
+) -> Result<ConvertedImage, ConverterError> {
+    let pixmap = render_pixmap(parsed, options)?;
+
+    encode_pixmap(&pixmap, options)
+}
+
+/// Encodes a rendered pixmap to the format, quality and compression in `options`
+pub fn encode_pixmap(
+    pixmap: &tiny_skia::Pixmap,
+    options: &ConvertOptions,
+) -> Result<ConvertedImage, ConverterError> {
+    let format = options.format.as_str();
+    let quality = validate_quality(options.quality)?;
+    let subsampling = parse_subsampling(options.subsampling.as_deref())?;
+    let png_settings = PngSettings::from_options(options)?;
+    let alpha_threshold = validate_alpha_threshold(options.alpha_threshold)?;
+
+    // Step 6: Encode to requested format
+    let (out_width, out_height) = (pixmap.width(), pixmap.height());
+    let image_data = output_pixels(pixmap, options)?;
+    let data = if format.eq_ignore_ascii_case("png") {
+        encode_png(&image_data, out_width, out_height, &png_settings)?
+    } else {
+        encode_image(
+            &image_data,
+            out_width,
+            out_height,
+            format,
+            preserves_transparency(format) && !options.flatten,
+            quality,
+            options.lossless,
+            options.grayscale,
+            options.dither,
+            alpha_threshold.unwrap_or(DEFAULT_ALPHA_THRESHOLD),
+            options.progressive,
+            subsampling,
+            png_settings.color_profile,
+        )?
+    };
+
+    Ok(ConvertedImage {
+        data,
+        width: out_width,
+        height: out_height,
+        warnings: Vec::new(),
+    })
+}
+
+/// Converts a rendered pixmap to the straight RGBA8 data the encoders take, applying the
+/// `grayscale` and `palette` options
+pub fn output_pixels<'a>(
+    pixmap: &'a tiny_skia::Pixmap,
+    options: &ConvertOptions,
+) -> Result<Cow<'a, [u8]>, ConverterError> {
+    let palette = parse_palette(&options.palette)?;
+    let data = straight_alpha(pixmap.data());
+    let data: Cow<[u8]> = if options.grayscale {
+        Cow::Owned(rgba_to_grayscale(&data))
+    } else {
+        data
+    };
+    Ok(match palette {
+        Some(palette) => {
+            let mut quantized = data.into_owned();
+            quantize(
+                &mut quantized,
+                pixmap.width() as usize,
+                0,
+                &palette,
+                options.palette_dither,
+            );
+            Cow::Owned(quantized)
+        }
+        None => data,
+    })
+}
+
+/// Renders a parsed SVG into a pixmap with the given options, including trimming, cropping
+/// and padding. The background is filled unless the options' format keeps transparency, and
+/// a background gradient is composited under the finished image for every format.
+pub fn render_pixmap(
+    parsed: &ParsedSvg,
+    options: &ConvertOptions,
+) -> Result<tiny_skia::Pixmap, ConverterError> {
+    render_pixmap_pooled(parsed, options, &mut PixmapPool::default())
+}
+
+/// Renders a parsed SVG into a pixmap like `render_pixmap`, taking the canvas and any
+/// intermediate pixmaps from `pool` and returning the intermediate ones to it
+fn render_pixmap_pooled(
+    parsed: &ParsedSvg,
+    options: &ConvertOptions,
+    pool: &mut PixmapPool,
+) -> Result<tiny_skia::Pixmap, ConverterError> {
+    let (width, height) = output_lengths(options)?;
+    let fit = Fit::from_options(options)?;
+    let rotation = Rotation::parse(options.rotate)?;
+    let ssaa = validate_ssaa(options.ssaa)?;
+    let resize_filter = parse_resize_filter(options.resize_filter.as_deref())?;
+    let opacity = validate_opacity(options.opacity)?;
+    let dpr = validate_dpr(options)?;
+    let scale = validate_scale(options)? * dpr;
+    let gradient = background_gradient(options)?;
+    let view = view_transform(options)?;
+    let tree = &parsed.tree;
+
+    // Step 3: Determine output dimensions
+    // The region of the canvas to render: the whole document, or just the requested node
+    let node = options
+        .node_id
+        .as_deref()
+        .map(|id| {
+            tree.node_by_id(id).ok_or_else(|| {
+                ConverterError::NodeNotFound(format!(
+                    "Invalid node_id: no element with id {:?}",
+                    id
+                ))
+            })
+        })
+        .transpose()?;
+    let region = match (node, options.view_box) {
+        (Some(_), Some(_)) => {
+            return Err(ConverterError::InvalidOption(
+                "Invalid view_box: can't be combined with node_id".to_string(),
+            ))
+        }
+        (Some(node), None) => node_region(node).ok_or_else(|| {
+            ConverterError::InvalidOption(format!(
+                "Invalid node_id: element {:?} has an empty bounding box",
+                node.id()
+            ))
+        })?,
+        (None, Some(view_box)) => view_box_region(view_box, parsed)?,
+        (None, None) => tree.size().to_non_zero_rect(0.0, 0.0),
+    };
+    let svg_size = region.size();
+    // Percentages are of the CSS size, which the device pixel ratio turns into pixels
+    let width = width.map(|w| w.resolve(svg_size.width() * dpr));
+    let height = height.map(|h| h.resolve(svg_size.height() * dpr));
+
+    // Derived dimensions, like percentages, are rounded to the nearest pixel, so the output
+    // keeps the closest aspect ratio, and clamped to at least 1px so extreme aspect ratios
+    // never produce an empty pixmap
+    let (out_width, out_height) = match (width, height) {
+        (Some(w), Some(h)) => (w, h),
+        (Some(w), None) => {
+            let aspect_ratio = svg_size.height() / svg_size.width();
+            (w, ((w as f32 * aspect_ratio).round() as u32).max(1))
+        }
+        (None, Some(h)) => {
+            let aspect_ratio = svg_size.width() / svg_size.height();
+            (((h as f32 * aspect_ratio).round() as u32).max(1), h)
+        }
+        (None, None) => (
+            ((svg_size.width() * scale).round() as u32).max(1),
+            ((svg_size.height() * scale).round() as u32).max(1),
+        ),
+    };
+
+    // Step 4: Create pixmap and render SVG; quarter turns swap the output dimensions and
+    // supersampling renders at a multiple of them
+    let (pixmap_width, pixmap_height) = if rotation.swaps_dimensions() {
+        (out_height, out_width)
+    } else {
+        (out_width, out_height)
+    };
+    check_pixel_limit(
+        pixmap_width as u64 * ssaa as u64,
+        pixmap_height as u64 * ssaa as u64,
+        options,
+    )?;
+    let mut pixmap = pixmap_width
+        .checked_mul(ssaa)
+        .zip(pixmap_height.checked_mul(ssaa))
+        .and_then(|(width, height)| pool.take(width, height))
+        .ok_or_else(|| {
+            ConverterError::InvalidDimensions(format!(
+                "Failed to create pixmap: {}x{} at {}x supersampling is too large",
+                pixmap_width, pixmap_height, ssaa
+            ))
+        })?;
+
+    // With a gradient the content is rendered, trimmed and padded on transparency, and only
+    // then composited over the gradient so it spans the final image
+    if let Some(color) = background_color(options)?.filter(|_| gradient.is_none()) {
+        pixmap.fill(color);
+    }
+    let background = pixmap.pixels()[0];
+
+    // Render SVG to pixmap
+    // Without an explicit fit, the whole document is fitted the way its own
+    // preserveAspectRatio asks, as a browser does when it sizes the SVG's viewport
+    let aspect_ratio = match fit {
+        None => parsed
+            .viewport
+            .and_then(|viewport| viewport.aspect_ratio)
+            .filter(|_| node.is_none())
+            .unwrap_or(AspectRatio::NONE),
+        Some(fit) => AspectRatio::centered(fit),
+    };
+    let scale_x = out_width as f32 / svg_size.width();
+    let scale_y = out_height as f32 / svg_size.height();
+    let transform = match aspect_ratio.fit {
+        Fit::Fill => tiny_skia::Transform::from_scale(scale_x, scale_y),
+        Fit::Contain | Fit::Cover => {
+            // Scale uniformly and align; contain leaves background bars, cover crops overflow
+            let scale = if aspect_ratio.fit == Fit::Contain {
+                scale_x.min(scale_y)
+            } else {
+                scale_x.max(scale_y)
+            };
+            let offset_x = (out_width as f32 - svg_size.width() * scale) * aspect_ratio.align_x;
+            let offset_y = (out_height as f32 - svg_size.height() * scale) * aspect_ratio.align_y;
+            tiny_skia::Transform::from_scale(scale, scale).post_translate(offset_x, offset_y)
+        }
+    }
+    .pre_concat(view)
+    .pre_translate(-region.x(), -region.y())
+    .post_concat(flip_transform(
+        options.flip_h,
+        options.flip_v,
+        out_width as f32,
+        out_height as f32,
+    ))
+    .post_concat(rotation.transform(out_width as f32, out_height as f32))
+    .post_scale(ssaa as f32, ssaa as f32);
+
+    match opacity {
+        Some(opacity) => {
+            // Render onto a transparent layer and composite it at reduced opacity so only the
+            // content fades, not the background
+            let mut layer = pool.take(pixmap.width(), pixmap.height()).ok_or_else(|| {
+                ConverterError::RenderFailed("Failed to create layer".to_string())
+            })?;
+            render_content(tree, node, transform, &mut layer)?;
+            pixmap.draw_pixmap(
+                0,
+                0,
+                layer.as_ref(),
+                &tiny_skia::PixmapPaint {
+                    opacity,
+                    ..tiny_skia::PixmapPaint::default()
+                },
+                tiny_skia::Transform::identity(),
+                None,
+            );
+            pool.put(layer);
+        }
+        None => render_content(tree, node, transform, &mut pixmap)?,
+    }
+    if ssaa > 1 {
+        let downsampled = downsample_pixmap(&pixmap, pixmap_width, pixmap_height, resize_filter)?;
+        pool.replace(&mut pixmap, downsampled);
+    }
+
+    // Step 5: Trim background margins, crop, then pad the rendered output
+    if options.trim {
+        if let Some(bounds) = content_bounds(&pixmap, background) {
+            let trimmed = pixmap
+                .clone_rect(bounds)
+                .ok_or_else(|| ConverterError::RenderFailed("Failed to trim image".to_string()))?;
+            pool.replace(&mut pixmap, trimmed);
+        }
+    }
+    if let Some(crop) = options.crop {
+        let cropped = crop_pixmap(&pixmap, crop)?;
+        pool.replace(&mut pixmap, cropped);
+    }
+    if let Some(padding) = options.padding.filter(|&padding| padding > 0) {
+        let padded_size = |size: u32| size as u64 + 2 * padding as u64;
+        check_pixel_limit(
+            padded_size(pixmap.width()),
+            padded_size(pixmap.height()),
+            options,
+        )?;
+        let padded = pad_pixmap(&pixmap, padding, background, pool)?;
+        pool.replace(&mut pixmap, padded);
+    }
+    if let Some((top, bottom)) = gradient {
+        let composited = over_gradient(&pixmap, top, bottom, pool)?;
+        pool.replace(&mut pixmap, composited);
+    }
+
+    Ok(pixmap)
+}
+
+/// Returns the color the output is rendered over: the background color for formats that
+/// don't preserve transparency, or when flattening was requested, and `None` otherwise
+pub fn background_color(
+    options: &ConvertOptions,
+) -> Result<Option<tiny_skia::Color>, ConverterError> {
+    if preserves_transparency(&options.format) && !options.flatten {
+        return Ok(None);
+    }
+    let bg_color = options.background_color.as_deref().unwrap_or("FFFFFF");
+    let (r, g, b, a) = parse_color(bg_color).map_err(ConverterError::InvalidColor)?;
+    // Formats without an alpha channel would drop it after the content was blended over the
+    // translucent background, so they get the color at full opacity to blend over instead
+    let a = if stores_alpha(&options.format) {
+        a
+    } else {
+        u8::MAX
+    };
+    Ok(Some(tiny_skia::Color::from_rgba8(r, g, b, a)))
+}
+
+/// Returns the top and bottom colors of the vertical background gradient, if any
+fn background_gradient(
+    options: &ConvertOptions,
+) -> Result<Option<(tiny_skia::Color, tiny_skia::Color)>, ConverterError> {
+    let Some((top, bottom)) = &options.background_gradient else {
+        return Ok(None);
+    };
+    let color = |color: &str| {
+        let (r, g, b, a) = parse_color(color).map_err(ConverterError::InvalidColor)?;
+        Ok(tiny_skia::Color::from_rgba8(r, g, b, a))
+    };
+    Ok(Some((color(top)?, color(bottom)?)))
+}
+
+/// Turns off anti-aliasing of every shape, including text once it's converted to paths
+const NO_ANTIALIASING_CSS: &str =
+    "* { shape-rendering: crispEdges !important; text-rendering: optimizeSpeed !important; }";
+
+/// Parses the processed SVG, using the default font family and size for text without its own
+/// and the rendering hints for elements whose `shape-rendering`, `text-rendering` or
+/// `image-rendering` is `auto`. Unresolved references and text without an available font
+/// are collected as warnings.
+fn parse_tree(svg: &str, options: &ConvertOptions) -> Result<ParsedSvg, ConverterError> {
+    let (shape_rendering, text_rendering, image_rendering) = rendering_hints(options)?;
+    let resource_dir = options
+        .resource_dir
+        .as_deref()
+        .map(canonical_resource_dir)
+        .transpose()?;
+    let font_warnings = Mutex::new(Vec::new());
+    let image_warnings = Mutex::new(Vec::new());
+    let mut opt = usvg::Options {
+        fontdb: font_database(options.load_system_fonts, &options.font_dirs),
+        font_resolver: warnings::font_resolver(&font_warnings),
+        image_href_resolver: warnings::image_href_resolver(
+            &image_warnings,
+            resource_dir.as_deref(),
+        ),
+        shape_rendering,
+        text_rendering,
+        image_rendering,
+        ..usvg::Options::default()
+    };
+    if let Some(font_family) = &options.font_family {
+        opt.font_family = font_family.clone();
+    }
+    if let Some(font_size) = options.font_size {
+        opt.font_size = font_size;
+    }
+    // Physical units such as mm, cm and pt are resolved to pixels at this DPI
+    if let Some(dpi) = options.dpi {
+        opt.dpi = dpi;
+    }
+    // `switch` elements render their first child whose `systemLanguage` matches one of these
+    if !options.languages.is_empty() {
+        opt.languages = options.languages.clone();
+    }
+    // The injected style sheet is applied before the SVG's own, so its important declarations
+    // win over every attribute, style and rule in the document
+    if !options.antialias {
+        opt.style_sheet = Some(NO_ANTIALIASING_CSS.to_string());
+    }
+    let tree = usvg::Tree::from_str(svg, &opt)
+        .map_err(|e| ConverterError::ParseFailed(format!("Failed to parse SVG: {}", e)))?;
+    drop(opt);
+
+    let mut warnings = warnings::unresolved_references(svg);
+    for resolver_warnings in [font_warnings, image_warnings] {
+        warnings.extend(
+            resolver_warnings
+                .into_inner()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+    }
+    Ok(ParsedSvg {
+        tree: Arc::new(tree),
+        warnings: warnings.into(),
+        viewport: root_viewport(svg),
+    })
+}
+
+// Image hrefs are checked against the canonical directory, so they can't escape it through
+// `..` or a symlink
+fn canonical_resource_dir(dir: &str) -> Result<PathBuf, ConverterError> {
+    Path::new(dir)
+        .canonicalize()
+        .ok()
+        .filter(|path| path.is_dir())
+        .ok_or_else(|| {
+            ConverterError::InvalidOption(format!(
+                "Invalid resource_dir: {:?} is not a directory",
+                dir
+            ))
+        })
+}
+
+/// Returns the bounding box of pixels that differ from `background`, or `None` when the
+/// whole image is background.
+fn content_bounds(
+    pixmap: &tiny_skia::Pixmap,
+    background: tiny_skia::PremultipliedColorU8,
+) -> Option<tiny_skia::IntRect> {
+    let width = pixmap.width() as usize;
+    let (mut left, mut top, mut right, mut bottom) = (usize::MAX, usize::MAX, 0, 0);
+
+    for (y, row) in pixmap.pixels().chunks_exact(width).enumerate() {
+        let Some(first) = row.iter().position(|&pixel| pixel != background) else {
+            continue;
+        };
+        let last = row.iter().rposition(|&pixel| pixel != background)?;
+        left = left.min(first);
+        right = right.max(last + 1);
+        top = top.min(y);
+        bottom = y + 1;
+    }
+
+    if top == usize::MAX {
+        return None;
+    }
+    tiny_skia::IntRect::from_ltrb(left as i32, top as i32, right as i32, bottom as i32)
+}
+
+/// Resizes a supersampled pixmap down to `width` x `height` with `filter`.
+///
+/// Filtering the premultiplied data weights each pixel's color by its coverage, so
+/// transparent pixels don't bleed dark fringes into edges.
+fn downsample_pixmap(
+    pixmap: &tiny_skia::Pixmap,
+    width: u32,
+    height: u32,
+    filter: ResizeFilter,
+) -> Result<tiny_skia::Pixmap, ConverterError> {
+    let image = RgbaImage::from_raw(pixmap.width(), pixmap.height(), pixmap.data().to_vec())
+        .ok_or_else(|| ConverterError::RenderFailed("Failed to downsample image".to_string()))?;
+    let mut data = image::imageops::resize(&image, width, height, filter).into_raw();
+
+    // Lanczos and Catmull-Rom overshoot next to hard edges; clamp the color channels to alpha
+    // so the result is still valid premultiplied data
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3];
+        for channel in &mut pixel[..3] {
+            *channel = (*channel).min(alpha);
+        }
+    }
+
+    tiny_skia::IntSize::from_wh(width, height)
+        .and_then(|size| tiny_skia::Pixmap::from_vec(data, size))
+        .ok_or_else(|| ConverterError::RenderFailed("Failed to downsample image".to_string()))
+}
+
+/// Surrounds `pixmap` with `padding` pixels of `background` on every side.
+fn pad_pixmap(
+    pixmap: &tiny_skia::Pixmap,
+    padding: u32,
+    background: tiny_skia::PremultipliedColorU8,
+    pool: &mut PixmapPool,
+) -> Result<tiny_skia::Pixmap, ConverterError> {
+    let padded_size = |size: u32| size.checked_add(padding.saturating_mul(2));
+    let mut padded = padded_size(pixmap.width())
+        .zip(padded_size(pixmap.height()))
+        .and_then(|(width, height)| pool.take(width, height))
+        .ok_or_else(|| {
+            ConverterError::InvalidDimensions(format!(
+                "Invalid padding: {} pixels is too large",
+                padding
+            ))
+        })?;
+
+    padded.pixels_mut().fill(background);
+    padded.draw_pixmap(
+        padding as i32,
+        padding as i32,
+        pixmap.as_ref(),
+        &tiny_skia::PixmapPaint {
+            blend_mode: tiny_skia::BlendMode::Source,
+            ..tiny_skia::PixmapPaint::default()
+        },
+        tiny_skia::Transform::identity(),
+        None,
+    );
+    Ok(padded)
+}
+
+/// Composites `pixmap` over a vertical gradient from `top` to `bottom`. The gradient runs
+/// between the centers of the first and last rows, so those rows get the exact end colors.
+fn over_gradient(
+    pixmap: &tiny_skia::Pixmap,
+    top: tiny_skia::Color,
+    bottom: tiny_skia::Color,
+    pool: &mut PixmapPool,
+) -> Result<tiny_skia::Pixmap, ConverterError> {
+    let (width, height) = (pixmap.width(), pixmap.height());
+    let mut output = pool
+        .take(width, height)
+        .ok_or_else(|| ConverterError::RenderFailed("Failed to create gradient".to_string()))?;
+
+    // A single row has no height to spread the gradient over, so it's just the top color
+    let gradient = (height > 1).then(|| {
+        tiny_skia::LinearGradient::new(
+            tiny_skia::Point::from_xy(0.0, 0.5),
+            tiny_skia::Point::from_xy(0.0, height as f32 - 0.5),
+            vec![
+                tiny_skia::GradientStop::new(0.0, top),
+                tiny_skia::GradientStop::new(1.0, bottom),
+            ],
+            tiny_skia::SpreadMode::Pad,
+            tiny_skia::Transform::identity(),
+        )
+    });
+    let shader = gradient
+        .flatten()
+        .unwrap_or(tiny_skia::Shader::SolidColor(top));
+    let rect = tiny_skia::Rect::from_xywh(0.0, 0.0, width as f32, height as f32)
+        .ok_or_else(|| ConverterError::RenderFailed("Failed to create gradient".to_string()))?;
+    let paint = tiny_skia::Paint {
+        shader,
+        ..tiny_skia::Paint::default()
+    };
+    output.fill_rect(rect, &paint, tiny_skia::Transform::identity(), None);
+
+    output.draw_pixmap(
+        0,
+        0,
+        pixmap.as_ref(),
+        &tiny_skia::PixmapPaint::default(),
+        tiny_skia::Transform::identity(),
+        None,
+    );
+    Ok(output)
+}
+
+/// Copies the `(x, y, width, height)` rectangle out of `pixmap`, clamped to its bounds.
+fn crop_pixmap(
+    pixmap: &tiny_skia::Pixmap,
+    (x, y, width, height): (u32, u32, u32, u32),
+) -> Result<tiny_skia::Pixmap, ConverterError> {
+    let right = x.saturating_add(width).min(pixmap.width());
+    let bottom = y.saturating_add(height).min(pixmap.height());
+
+    tiny_skia::IntRect::from_ltrb(x as i32, y as i32, right as i32, bottom as i32)
+        .filter(|_| x < right && y < bottom)
+        .and_then(|rect| pixmap.clone_rect(rect))
+        .ok_or_else(|| {
+            ConverterError::InvalidDimensions(format!(
+                "Invalid crop: rectangle {}x{} at ({}, {}) does not overlap the {}x{} image",
+                width,
+                height,
+                x,
+                y,
+                pixmap.width(),
+                pixmap.height()
+            ))
+        })
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Returns the bits per channel to encode with; only PNG accepts 16
+fn validate_bit_depth(bit_depth: Option<u8>, format: &str) -> Result<u8, ConverterError> {
+    match bit_depth {
+        None | Some(8) => Ok(8),
+        Some(16) if format.eq_ignore_ascii_case("png") => Ok(16),
+        Some(16) => Err(ConverterError::InvalidOption(format!(
+            "Invalid bit depth: 16-bit output is only supported for png, got {}",
+            format
+        ))),
+        Some(other) => Err(ConverterError::InvalidOption(format!(
+            "Invalid bit depth: must be 8 or 16, got {}",
+            other
+        ))),
+    }
+}
+
+/// Longest keyword a PNG text chunk can hold
+const MAX_PNG_KEYWORD_LEN: usize = 79;
+
+/// Checks that metadata is only given for PNG output and that every keyword fits a PNG text
+/// chunk: 1 to 79 printable Latin-1 characters
+fn validate_metadata(metadata: &[(String, String)], format: &str) -> Result<(), ConverterError> {
+    if metadata.is_empty() {
+        return Ok(());
+    }
+    if !format.eq_ignore_ascii_case("png") {
+        return Err(ConverterError::InvalidOption(format!(
+            "Invalid metadata: only supported for png, got {}",
+            format
+        )));
+    }
+    let printable = |c: char| matches!(c, ' '..='~' | '\u{a1}'..='\u{ff}');
+    let valid = |keyword: &str| {
+        (1..=MAX_PNG_KEYWORD_LEN).contains(&keyword.chars().count())
+            && keyword.chars().all(printable)
+    };
+    match metadata.iter().find(|(keyword, _)| !valid(keyword)) {
+        Some((keyword, _)) => Err(ConverterError::InvalidOption(format!(
+            "Invalid metadata: keywords must be 1 to {} printable Latin-1 characters, got {:?}",
+            MAX_PNG_KEYWORD_LEN, keyword
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Converts the output DPI, which is only written for PNG, to pixels per meter for its pHYs
+/// chunk
+fn output_pixels_per_meter(
+    dpi_output: Option<f32>,
+    format: &str,
+) -> Result<Option<u32>, ConverterError> {
+    match dpi_output {
+        None => Ok(None),
+        Some(dpi) if !(dpi.is_finite() && dpi > 0.0) => Err(ConverterError::InvalidOption(
+            format!("Invalid output DPI: must be a positive number, got {}", dpi),
+        )),
+        Some(_) if !format.eq_ignore_ascii_case("png") => Err(ConverterError::InvalidOption(
+            format!("Invalid output DPI: only supported for png, got {}", format),
+        )),
+        // An inch is 0.0254 meters; at least 1 so tiny DPIs still record a resolution
+        Some(dpi) => Ok(Some(
+            (dpi as f64 / 0.0254).round().clamp(1.0, u32::MAX as f64) as u32,
+        )),
+    }
+}
+
+/// The color space PNG and JPEG output is tagged with
+#[derive(Clone, Copy)]
+enum ColorProfile<'a> {
+    /// The standard sRGB space the SVG is rendered in: an sRGB chunk in PNG, and a generated
+    /// sRGB ICC profile in JPEG
+    Srgb,
+    /// An ICC profile embedded as given; the pixels aren't converted to it
+    Icc(&'a [u8]),
+}
+
+/// Resolves the `icc_profile` or `srgb` option. Both only apply to PNG and JPEG output and
+/// can't be combined; the ICC profile must parse as an RGB profile, matching the pixels.
+fn output_color_profile(
+    options: &ConvertOptions,
+) -> Result<Option<ColorProfile<'_>>, ConverterError> {
+    let profile = match (&options.icc_profile, options.srgb) {
+        (None, false) => return Ok(None),
+        (Some(_), true) => {
+            return Err(ConverterError::InvalidOption(
+                "Invalid srgb: can't be combined with icc_profile".to_string(),
+            ))
+        }
+        (Some(Bytes(icc_profile)), false) => {
+            let parsed = moxcms::ColorProfile::new_from_slice(icc_profile).map_err(|e| {
+                ConverterError::InvalidOption(format!(
+                    "Invalid icc_profile: the profile could not be parsed ({})",
+                    e
+                ))
+            })?;
+            if parsed.color_space != moxcms::DataColorSpace::Rgb {
+                return Err(ConverterError::InvalidOption(format!(
+                    "Invalid icc_profile: must describe RGB data, got a {:?} profile",
+                    parsed.color_space
+                )));
+            }
+            ColorProfile::Icc(icc_profile)
+        }
+        (None, true) => ColorProfile::Srgb,
+    };
+
+    let format = options.format.to_lowercase();
+    if !matches!(format.as_str(), "png" | "jpg" | "jpeg") {
+        let option = match profile {
+            ColorProfile::Srgb => "srgb",
+            ColorProfile::Icc(_) => "icc_profile",
+        };
+        return Err(ConverterError::InvalidOption(format!(
+            "Invalid {}: only supported for png and jpg, got {}",
+            option, options.format
+        )));
+    }
+    Ok(Some(profile))
+}
+
+fn parse_png_compression(compression: Option<&str>) -> Result<png::Compression, ConverterError> {
+    match compression.map(str::to_lowercase).as_deref() {
+        // Matches what the image crate's `PngEncoder::new`, used before, always wrote
+        None | Some("fast") => Ok(png::Compression::Fast),
+        Some("default") => Ok(png::Compression::Balanced),
+        Some("best") => Ok(png::Compression::High),
+        Some(other) => Err(ConverterError::InvalidOption(format!(
+            "Invalid PNG compression: must be one of fast, default, best, got {}",
+            other
+        ))),
+    }
+}
+
+/// Parses the filter supersampled renders are downsampled with, Lanczos3 by default
+fn parse_resize_filter(filter: Option<&str>) -> Result<ResizeFilter, ConverterError> {
+    match filter.map(str::to_lowercase).as_deref() {
+        None | Some("lanczos3") => Ok(ResizeFilter::Lanczos3),
+        Some("nearest") => Ok(ResizeFilter::Nearest),
+        Some("triangle") => Ok(ResizeFilter::Triangle),
+        Some("catmull") => Ok(ResizeFilter::CatmullRom),
+        Some(other) => Err(ConverterError::InvalidOption(format!(
+            "Invalid resize_filter: must be one of nearest, triangle, catmull, lanczos3, got {}",
+            other
+        ))),
+    }
+}
+
+fn parse_subsampling(subsampling: Option<&str>) -> Result<SamplingFactor, ConverterError> {
+    match subsampling {
+        // Full chroma, as the image crate's JPEG encoder used before jpeg-encoder always wrote
+        None | Some("4:4:4") => Ok(SamplingFactor::R_4_4_4),
+        Some("4:2:2") => Ok(SamplingFactor::R_4_2_2),
+        Some("4:2:0") => Ok(SamplingFactor::R_4_2_0),
+        Some(other) => Err(ConverterError::InvalidOption(format!(
+            "Invalid subsampling: must be one of 4:4:4, 4:2:2, 4:2:0, got {}",
+            other
+        ))),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Fit {
+    Fill,
+    Contain,
+    Cover,
+}
+
+impl Fit {
+    fn parse(fit: Option<&str>) -> Result<Option<Fit>, ConverterError> {
+        match fit.map(str::to_lowercase).as_deref() {
+            None => Ok(None),
+            Some("fill") => Ok(Some(Fit::Fill)),
+            Some("contain") => Ok(Some(Fit::Contain)),
+            Some("cover") => Ok(Some(Fit::Cover)),
+            Some(other) => Err(ConverterError::InvalidOption(format!(
+                "Invalid fit: must be one of fill, contain, cover, got {}",
+                other
+            ))),
+        }
+    }
+
+    /// Resolves the fit mode of `options`, where `preserve_aspect` is shorthand for `contain`,
+    /// or `None` when neither is given
+    fn from_options(options: &ConvertOptions) -> Result<Option<Fit>, ConverterError> {
+        let fit = Fit::parse(options.fit.as_deref())?;
+        if !options.preserve_aspect {
+            return Ok(fit);
+        }
+
+        match options.fit.as_deref() {
+            Some(other) if fit != Some(Fit::Contain) => {
+                Err(ConverterError::InvalidOption(format!(
+                    "Invalid preserve_aspect: can't be combined with fit {}",
+                    other
+                )))
+            }
+            _ => Ok(Some(Fit::Contain)),
+        }
+    }
+}
+
+/// How the SVG is fitted into the output: the fit mode, and where the scaled SVG is placed
+/// along each axis, from 0.0 (left or top) to 1.0 (right or bottom)
+#[derive(Clone, Copy)]
+struct AspectRatio {
+    fit: Fit,
+    align_x: f32,
+    align_y: f32,
+}
+
+impl AspectRatio {
+    /// `preserveAspectRatio="none"`, which stretches the SVG to the output
+    const NONE: AspectRatio = AspectRatio::centered(Fit::Fill);
+
+    /// `preserveAspectRatio="xMidYMid meet"`, the default when the attribute is missing
+    const DEFAULT: AspectRatio = AspectRatio::centered(Fit::Contain);
+
+    const fn centered(fit: Fit) -> AspectRatio {
+        AspectRatio {
+            fit,
+            align_x: 0.5,
+            align_y: 0.5,
+        }
+    }
+
+    /// Parses a `preserveAspectRatio` value such as `xMinYMid slice`; an invalid value falls
+    /// back to the default `xMidYMid meet`, as in browsers
+    fn parse(value: &str) -> AspectRatio {
+        let mut parts = value.split_whitespace().skip_while(|&part| part == "defer");
+        let align = parts.next().unwrap_or("xMidYMid");
+        let fit = match parts.next() {
+            None | Some("meet") => Fit::Contain,
+            Some("slice") => Fit::Cover,
+            Some(_) => return AspectRatio::DEFAULT,
+        };
+        if align == "none" {
+            return AspectRatio::NONE;
+        }
+
+        let position = |value: &str| match value {
+            "Min" => Some(0.0),
+            "Mid" => Some(0.5),
+            "Max" => Some(1.0),
+            _ => None,
+        };
+        let align = align
+            .strip_prefix('x')
+            .and_then(|rest| rest.split_once('Y'))
+            .and_then(|(x, y)| position(x).zip(position(y)));
+        match align {
+            Some((align_x, align_y)) if parts.next().is_none() => AspectRatio {
+                fit,
+                align_x,
+                align_y,
+            },
+            _ => AspectRatio::DEFAULT,
+        }
+    }
+}
+
+/// The root element's `viewBox`, and the `preserveAspectRatio` it declares, if any
+#[derive(Clone, Copy)]
+struct Viewport {
+    view_box: usvg::NonZeroRect,
+    aspect_ratio: Option<AspectRatio>,
+}
+
+/// Returns the root element's viewport, or `None` without a valid `viewBox`, in which case
+/// `preserveAspectRatio` has no effect either
+fn root_viewport(svg: &str) -> Option<Viewport> {
+    // An SVG without a viewBox is cheap to rule out
+    if !svg.contains("viewBox") {
+        return None;
+    }
+    let doc = parse_document(svg)?;
+    let root = doc.root_element();
+    let (x, y, width, height) = parse_view_box(root.attribute("viewBox")?)?;
+    Some(Viewport {
+        view_box: usvg::NonZeroRect::from_xywh(x, y, width, height)?,
+        aspect_ratio: root
+            .attribute("preserveAspectRatio")
+            .map(AspectRatio::parse),
+    })
+}
+
+/// Returns the region of the canvas shown by a `view_box` override, given in the SVG's user
+/// units: the source viewBox maps user units to the canvas as declared, so the override
+/// pans and zooms within the same coordinate space
+fn view_box_region(
+    (x, y, width, height): (f32, f32, f32, f32),
+    parsed: &ParsedSvg,
+) -> Result<usvg::NonZeroRect, ConverterError> {
+    let invalid = || {
+        ConverterError::InvalidOption(format!(
+            "Invalid view_box: must be finite with a positive width and height, got {} {} {} {}",
+            x, y, width, height
+        ))
+    };
+    if ![x, y, width, height].iter().all(|value| value.is_finite()) {
+        return Err(invalid());
+    }
+    let view_box = usvg::NonZeroRect::from_xywh(x, y, width, height).ok_or_else(invalid)?;
+
+    let Some(viewport) = parsed.viewport else {
+        return Ok(view_box);
+    };
+    // The same transform usvg applies to fit the source viewBox to the document size
+    let size = parsed.tree.size();
+    let source = viewport.view_box;
+    let aspect_ratio = viewport.aspect_ratio.unwrap_or(AspectRatio::DEFAULT);
+    let (mut scale_x, mut scale_y) = (
+        size.width() / source.width(),
+        size.height() / source.height(),
+    );
+    match aspect_ratio.fit {
+        Fit::Fill => {}
+        Fit::Contain => (scale_x, scale_y) = (scale_x.min(scale_y), scale_x.min(scale_y)),
+        Fit::Cover => (scale_x, scale_y) = (scale_x.max(scale_y), scale_x.max(scale_y)),
+    }
+    let transform = tiny_skia::Transform::from_row(
+        scale_x,
+        0.0,
+        0.0,
+        scale_y,
+        (size.width() - source.width() * scale_x) * aspect_ratio.align_x - source.x() * scale_x,
+        (size.height() - source.height() * scale_y) * aspect_ratio.align_y - source.y() * scale_y,
+    );
+    view_box.transform(transform).ok_or_else(invalid)
+}
+
+/// Returns the pan and zoom applied before the SVG is fitted to the output: the SVG is moved
+/// so `(offset_x, offset_y)` is at the origin, then magnified by `zoom` about that point.
+fn view_transform(options: &ConvertOptions) -> Result<tiny_skia::Transform, ConverterError> {
+    let zoom = match options.zoom {
+        None => 1.0,
+        Some(zoom) if zoom.is_finite() && zoom > 0.0 => zoom,
+        Some(zoom) => {
+            return Err(ConverterError::InvalidOption(format!(
+                "Invalid zoom: must be a positive number, got {}",
+                zoom
+            )))
+        }
+    };
+    let offset = |name: &str, offset: Option<f32>| match offset {
+        Some(offset) if !offset.is_finite() => Err(ConverterError::InvalidOption(format!(
+            "Invalid {}: must be a finite number, got {}",
+            name, offset
+        ))),
+        offset => Ok(offset.unwrap_or(0.0)),
+    };
+    let offset_x = offset("offset_x", options.offset_x)?;
+    let offset_y = offset("offset_y", options.offset_y)?;
+
+    Ok(tiny_skia::Transform::from_scale(zoom, zoom).pre_translate(-offset_x, -offset_y))
+}
+
+/// Mirrors a `width` x `height` image horizontally and/or vertically in place.
+fn flip_transform(flip_h: bool, flip_v: bool, width: f32, height: f32) -> tiny_skia::Transform {
+    let (scale_x, translate_x) = if flip_h { (-1.0, width) } else { (1.0, 0.0) };
+    let (scale_y, translate_y) = if flip_v { (-1.0, height) } else { (1.0, 0.0) };
+    tiny_skia::Transform::from_row(scale_x, 0.0, 0.0, scale_y, translate_x, translate_y)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Rotation {
+    None,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
 
-    // Step 3: Determine output dimensions
-    let svg_size = tree.size();
-    let (out_width, out_height) = match (width, height) {
-        (Some(w), Some(h)) => (w, h),
-        (Some(w), None) => {
-            let aspect_ratio = svg_size.height() / svg_size.width();
-            (w, (w as f32 * aspect_ratio) as u32)
+impl Rotation {
+    fn parse(degrees: Option<u16>) -> Result<Rotation, ConverterError> {
+        match degrees {
+            None | Some(0) => Ok(Rotation::None),
+            Some(90) => Ok(Rotation::Quarter),
+            Some(180) => Ok(Rotation::Half),
+            Some(270) => Ok(Rotation::ThreeQuarters),
+            Some(other) => Err(ConverterError::InvalidOption(format!(
+                "Invalid rotate: must be one of 0, 90, 180, 270, got {}",
+                other
+            ))),
         }
-        (None, Some(h)) => {
-            let aspect_ratio = svg_size.width() / svg_size.height();
-            ((h as f32 * aspect_ratio) as u32, h)
+    }
+
+    fn swaps_dimensions(self) -> bool {
+        matches!(self, Rotation::Quarter | Rotation::ThreeQuarters)
+    }
+
+    /// Rotates a `width` x `height` image clockwise, keeping it at the pixmap's origin.
+    fn transform(self, width: f32, height: f32) -> tiny_skia::Transform {
+        match self {
+            Rotation::None => tiny_skia::Transform::identity(),
+            Rotation::Quarter => tiny_skia::Transform::from_row(0.0, 1.0, -1.0, 0.0, height, 0.0),
+            Rotation::Half => tiny_skia::Transform::from_row(-1.0, 0.0, 0.0, -1.0, width, height),
+            Rotation::ThreeQuarters => {
+                tiny_skia::Transform::from_row(0.0, -1.0, 1.0, 0.0, 0.0, width)
+            }
+        }
+    }
+}
+
+/// Whether the format's pixels have an alpha channel; the others are written as RGB
+fn stores_alpha(format: &str) -> bool {
+    !matches!(
+        format.to_lowercase().as_str(),
+        "jpg" | "jpeg" | "bmp" | "pnm" | "ppm"
+    )
+}
+
+fn preserves_transparency(format: &str) -> bool {
+    matches!(
+        format.to_lowercase().as_str(),
+        "png" | "tiff" | "tif" | "qoi" | "tga" | "ff" | "farbfeld" | "exr" | "gif"
+    )
+}
+
+// A zero limit would replace nothing for literal replacements but everything for
+// `Regex::replacen`, so it is rejected outright
+fn validate_replacement_limits(replacements: &[Replacement]) -> Result<(), ConverterError> {
+    match replacements.iter().find(|(_, _, max, _)| *max == Some(0)) {
+        Some((search, _, _, _)) => Err(ConverterError::InvalidOption(format!(
+            "Invalid replacement max for {:?}: must be greater than 0",
+            search
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Returns a replacement value as it is substituted into the SVG, XML-escaped when
+/// `escape_replacements` is set so text such as `Fish & Chips` can't break the markup
+fn replacement_value<'a>(value: &'a str, options: &ConvertOptions) -> Cow<'a, str> {
+    if !options.escape_replacements || !value.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut escaped = String::with_capacity(value.len() + 16);
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Replaces occurrences of `search` in `text` regardless of ASCII case, up to `max` of them.
+///
+/// Matches are found in an ASCII-lowercased copy of the text, which has the same byte offsets
+/// as the original, so the replacement is spliced into the original text and everything
+/// outside the matches keeps its case. This costs a copy of the SVG per replacement, but
+/// avoids compiling a case-insensitive regex.
+fn replace_ignore_case(text: &str, search: &str, replace: &str, max: Option<usize>) -> String {
+    let lowercase_text = text.to_ascii_lowercase();
+    let lowercase_search = search.to_ascii_lowercase();
+
+    let mut replaced = String::with_capacity(text.len());
+    let mut last_end = 0;
+    for (start, _) in lowercase_text
+        .match_indices(&lowercase_search)
+        .take(max.unwrap_or(usize::MAX))
+    {
+        replaced.push_str(&text[last_end..start]);
+        replaced.push_str(replace);
+        last_end = start + search.len();
+    }
+    replaced.push_str(&text[last_end..]);
+    replaced
+}
+
+type CompiledReplacement<'a> = (Regex, &'a String, Option<usize>, ReplacementScope);
+
+// Regex replacements are compiled up front so an invalid pattern fails before any work is done
+fn compile_regex_replacements(
+    replacements: &[Replacement],
+) -> Result<Vec<CompiledReplacement<'_>>, ConverterError> {
+    replacements
+        .iter()
+        .map(|(pattern, replace, max, scope)| {
+            let scope = ReplacementScope::parse(pattern, scope)?;
+            Regex::new(pattern)
+                .map(|regex| (regex, replace, *max, scope))
+                .map_err(|e| {
+                    ConverterError::InvalidOption(format!(
+                        "Invalid regex pattern {:?}: {}",
+                        pattern, e
+                    ))
+                })
+        })
+        .collect()
+}
+
+fn replace_regex<'t>(
+    pattern: &Regex,
+    text: &'t str,
+    replace: &str,
+    max: Option<usize>,
+) -> Cow<'t, str> {
+    match max {
+        Some(max) => pattern.replacen(text, max, replace),
+        None => pattern.replace_all(text, replace),
+    }
+}
+
+/// The part of the markup a replacement is allowed to match in
+#[derive(Clone, Copy, PartialEq)]
+enum ReplacementScope {
+    Any,
+    /// Attribute values, between their quotes
+    Attr,
+    /// Character data between tags, including CDATA sections but not comments
+    Text,
+}
+
+impl ReplacementScope {
+    fn parse(search: &str, scope: &str) -> Result<Self, ConverterError> {
+        match scope {
+            "any" => Ok(ReplacementScope::Any),
+            "attr" => Ok(ReplacementScope::Attr),
+            "text" => Ok(ReplacementScope::Text),
+            _ => Err(ConverterError::InvalidOption(format!(
+                "Invalid replacement scope for {:?}: must be one of any, attr, text, got {}",
+                search, scope
+            ))),
         }
-        (None, None) => (svg_size.width() as u32, svg_size.height() as u32),
+    }
+}
+
+/// Applies `replace` to each part of `svg` that `scope` covers and leaves the markup around
+/// them untouched, so a match can't span from one part into the next. `replace` is given a
+/// part and how many matches it may still replace, and returns the replaced part along with
+/// the number of matches it replaced, which is counted against `max` across the parts.
+fn replace_in_scope(
+    svg: &str,
+    scope: ReplacementScope,
+    mut max: Option<usize>,
+    mut replace: impl FnMut(&str, Option<usize>) -> (String, usize),
+) -> String {
+    let mut replaced = String::with_capacity(svg.len());
+    let mut last_end = 0;
+    for range in scoped_ranges(svg, scope) {
+        if max == Some(0) {
+            break;
+        }
+        let (part, count) = replace(&svg[range.clone()], max);
+        max = max.map(|max| max - count);
+        replaced.push_str(&svg[last_end..range.start]);
+        replaced.push_str(&part);
+        last_end = range.end;
+    }
+    replaced.push_str(&svg[last_end..]);
+    replaced
+}
+
+/// Returns the byte ranges of the attribute values or the text in `svg`, in document order,
+/// from a lightweight scan of the markup rather than a full parse, so it also works on
+/// documents that only become valid once every replacement is applied
+fn scoped_ranges(svg: &str, scope: ReplacementScope) -> Vec<Range<usize>> {
+    let bytes = svg.as_bytes();
+    let find = |from: usize, needle: &str| {
+        svg[from..]
+            .find(needle)
+            .map_or(svg.len(), |index| from + index)
     };
 
-    // Step 4: Create pixmap and render SVG
-    let mut pixmap = tiny_skia::Pixmap::new(out_width, out_height)
-        .ok_or_else(|| "Failed to create pixmap".to_string())?;
+    let mut ranges = Vec::new();
+    let mut position = 0;
+    while position < svg.len() {
+        if bytes[position] != b'<' {
+            let end = find(position, "<");
+            if scope == ReplacementScope::Text {
+                ranges.push(position..end);
+            }
+            position = end;
+        } else if svg[position..].starts_with("<!--") {
+            position = (find(position + 4, "-->") + 3).min(svg.len());
+        } else if svg[position..].starts_with("<![CDATA[") {
+            let end = find(position + 9, "]]>");
+            if scope == ReplacementScope::Text {
+                ranges.push(position + 9..end);
+            }
+            position = (end + 3).min(svg.len());
+        } else if svg[position..].starts_with("<?") {
+            position = (find(position + 2, "?>") + 2).min(svg.len());
+        } else {
+            // A tag, or a declaration such as a doctype whose quoted parts aren't attributes
+            let declaration = svg[position..].starts_with("<!");
+            let mut quote = None;
+            position += 1;
+            while position < svg.len() {
+                match (quote, bytes[position]) {
+                    (None, b'>') => break,
+                    (None, b'"' | b'\'') => quote = Some((bytes[position], position + 1)),
+                    (Some((open, start)), byte) if byte == open => {
+                        if scope == ReplacementScope::Attr && !declaration {
+                            ranges.push(start..position);
+                        }
+                        quote = None;
+                    }
+                    _ => {}
+                }
+                position += 1;
+            }
+            position += 1;
+        }
+    }
+    ranges
+}
+
+/// Returns the canvas area covered by a node, including strokes and, for groups, filters.
+fn node_region(node: &usvg::Node) -> Option<tiny_skia::NonZeroRect> {
+    match node {
+        usvg::Node::Group(group) => Some(group.abs_layer_bounding_box()),
+        _ => node.abs_stroke_bounding_box().to_non_zero_rect(),
+    }
+}
 
-    // Apply background color for non-PNG formats
-    let is_png = format.to_lowercase() == "png";
-    if !is_png {
-        let bg_color = background_color.unwrap_or_else(|| "FFFFFF".to_string());
-        let rgb = parse_hex_color(&bg_color)?;
-        pixmap.fill(tiny_skia::Color::from_rgba8(rgb.0, rgb.1, rgb.2, 255));
+/// Renders the whole tree, or just `node` when one was requested
+fn render_content(
+    tree: &usvg::Tree,
+    node: Option<&usvg::Node>,
+    transform: tiny_skia::Transform,
+    pixmap: &mut tiny_skia::Pixmap,
+) -> Result<(), ConverterError> {
+    match node {
+        Some(node) => render_node(node, transform, pixmap),
+        None => {
+            resvg::render(tree, transform, &mut pixmap.as_mut());
+            Ok(())
+        }
     }
+}
 
-    // Render SVG to pixmap
-    let transform = tiny_skia::Transform::from_scale(
-        out_width as f32 / svg_size.width(),
-        out_height as f32 / svg_size.height(),
-    );
+/// Renders a single node with `transform` mapping canvas coordinates to the pixmap.
+///
+/// `resvg::render_node` only applies the node's own transform, not its ancestors', and
+/// shifts the node's layer bounding box to the origin, so both are compensated for here.
+fn render_node(
+    node: &usvg::Node,
+    transform: tiny_skia::Transform,
+    pixmap: &mut tiny_skia::Pixmap,
+) -> Result<(), ConverterError> {
+    let parent_transform = match node {
+        usvg::Node::Group(group) => group
+            .transform()
+            .invert()
+            .map(|own| group.abs_transform().pre_concat(own)),
+        _ => Some(node.abs_transform()),
+    };
+    let layer_bbox = node.abs_layer_bounding_box();
+
+    if let (Some(parent_transform), Some(layer_bbox)) = (parent_transform, layer_bbox) {
+        let transform = transform
+            .pre_concat(parent_transform)
+            .pre_translate(layer_bbox.x(), layer_bbox.y());
+        if resvg::render_node(node, transform, &mut pixmap.as_mut()).is_some() {
+            return Ok(());
+        }
+    }
 
-    resvg::render(&tree, transform, &mut pixmap.as_mut());
+    Err(ConverterError::RenderFailed(format!(
+        "Failed to render node {:?}: it has no visible area",
+        node.id()
+    )))
+}
 
-    // Step 5: Encode to requested format
-    let image_data = pixmap.data();
-    encode_image(image_data, out_width, out_height, &format, is_png)
+/// Parses the SVG's markup for a pass over it before usvg parses it, allowing a DTD as usvg
+/// does so that SVGs with a `<!DOCTYPE>` aren't skipped. Returns `None` for malformed
+/// markup, which is left for usvg to report.
+pub fn parse_document(svg: &str) -> Option<usvg::roxmltree::Document<'_>> {
+    let options = usvg::roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    usvg::roxmltree::Document::parse_with_options(svg, options).ok()
 }
 
-fn parse_hex_color(hex: &str) -> Result<(u8, u8, u8), String> {
-    let hex = hex.trim_start_matches('#');
+/// Makes the root element's viewBox the intrinsic size when its `width` or `height` is
+/// missing or a percentage.
+///
+/// usvg resolves percentages against the viewBox, so `width="50%"` would halve one axis,
+/// and without a viewBox it falls back to the content's bounding box. Dropping the
+/// attributes lets usvg size the tree from the viewBox instead; without a usable viewBox
+/// there is no meaningful size to derive dimensions from, so an error is returned.
+/// Returns `None` when the SVG already has an absolute size and is left unchanged.
+fn normalize_intrinsic_size(svg: &str) -> Result<Option<String>, ConverterError> {
+    let Some(doc) = parse_document(svg) else {
+        return Ok(None);
+    };
+    let root = doc.root_element();
+
+    let is_degenerate = |value: Option<&str>| value.is_none_or(|v| v.trim().ends_with('%'));
+    if !is_degenerate(root.attribute("width")) && !is_degenerate(root.attribute("height")) {
+        return Ok(None);
+    }
 
-    if hex.len() != 6 {
-        return Err(format!(
-            "Invalid hex color: must be 6 characters (RRGGBB), got {}",
-            hex.len()
+    if root.attribute("viewBox").and_then(parse_view_box).is_none() {
+        return Err(ConverterError::InvalidDimensions(
+            "Invalid dimensions: SVG has no usable intrinsic size (width or height \
+                    is missing or a percentage and there is no valid viewBox); supply an \
+                    explicit width and height"
+                .to_string(),
         ));
     }
 
-    let r = u8::from_str_radix(&hex[0..2], 16)
-        .map_err(|_| format!("Invalid hex color: could not parse red component"))?;
-    let g = u8::from_str_radix(&hex[2..4], 16)
-        .map_err(|_| format!("Invalid hex color: could not parse green component"))?;
-    let b = u8::from_str_radix(&hex[4..6], 16)
-        .map_err(|_| format!("Invalid hex color: could not parse blue component"))?;
+    let mut ranges: Vec<_> = root
+        .attributes()
+        .filter(|attr| attr.namespace().is_none() && matches!(attr.name(), "width" | "height"))
+        .map(|attr| attr.range())
+        .collect();
+    if ranges.is_empty() {
+        return Ok(None);
+    }
+
+    // Remove from the end so earlier ranges stay valid
+    ranges.sort_by_key(|range| std::cmp::Reverse(range.start));
+    let mut normalized = svg.to_string();
+    for range in ranges {
+        normalized.replace_range(range, "");
+    }
+    Ok(Some(normalized))
+}
+
+/// Gives the root element a `fill` presentation attribute of `color`, so every element that
+/// doesn't set a fill of its own, text included, inherits it instead of black. A fill the
+/// root already has, as an attribute or in its `style`, is kept, and style sheet rules still
+/// override it like any presentation attribute.
+fn apply_default_fill(svg: &str, color: &str) -> Result<Option<String>, ConverterError> {
+    let color = parse_color(color).map_err(ConverterError::InvalidColor)?;
+    let Some(doc) = parse_document(svg) else {
+        return Ok(None);
+    };
+    let root = doc.root_element();
+
+    let has_fill = root
+        .attributes()
+        .any(|attr| attr.namespace().is_none() && attr.name() == "fill")
+        || root.attribute("style").is_some_and(|style| {
+            style
+                .split(';')
+                .any(|declaration| declaration.split(':').next().unwrap_or("").trim() == "fill")
+        });
+    if has_fill {
+        return Ok(None);
+    }
+
+    let start = root.range().start + 1;
+    let Some(name_length) = svg[start..].find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+    else {
+        return Ok(None);
+    };
+
+    // Colors are written out in a form SVG accepts, whichever form they were given in
+    let mut filled = svg.to_string();
+    filled.insert_str(
+        start + name_length,
+        &format!(" fill=\"{}\"", svg_color(color)),
+    );
+    Ok(Some(filled))
+}
+
+/// Parses a `viewBox` attribute into `(x, y, width, height)`, or `None` unless it is four
+/// finite numbers with a positive width and height
+fn parse_view_box(view_box: &str) -> Option<(f32, f32, f32, f32)> {
+    let values: Vec<f32> = view_box
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .map_while(|part| part.parse().ok())
+        .collect();
+    match values.as_slice() {
+        &[x, y, w, h]
+            if x.is_finite()
+                && y.is_finite()
+                && w.is_finite()
+                && h.is_finite()
+                && w > 0.0
+                && h > 0.0 =>
+        {
+            Some((x, y, w, h))
+        }
+        _ => None,
+    }
+}
+
+fn validate_font_size(font_size: Option<f32>) -> Result<(), ConverterError> {
+    match font_size {
+        Some(size) if !(size.is_finite() && size > 0.0) => Err(ConverterError::InvalidOption(
+            format!("Invalid font size: must be a positive number, got {}", size),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the default shape, text and image rendering, falling back to usvg's defaults
+fn rendering_hints(
+    options: &ConvertOptions,
+) -> Result<(ShapeRendering, TextRendering, ImageRendering), ConverterError> {
+    let invalid = |name: &str, accepted: &str, value: &str| {
+        ConverterError::InvalidOption(format!(
+            "Invalid {} rendering: must be one of {}, got {}",
+            name, accepted, value
+        ))
+    };
+
+    let shape_rendering = match options.shape_rendering.as_deref() {
+        None => ShapeRendering::default(),
+        Some("optimize_speed") => ShapeRendering::OptimizeSpeed,
+        Some("crisp_edges") => ShapeRendering::CrispEdges,
+        Some("geometric_precision") => ShapeRendering::GeometricPrecision,
+        Some(other) => {
+            return Err(invalid(
+                "shape",
+                "optimize_speed, crisp_edges, geometric_precision",
+                other,
+            ))
+        }
+    };
+    let text_rendering = match options.text_rendering.as_deref() {
+        None => TextRendering::default(),
+        Some("optimize_speed") => TextRendering::OptimizeSpeed,
+        Some("optimize_legibility") => TextRendering::OptimizeLegibility,
+        Some("geometric_precision") => TextRendering::GeometricPrecision,
+        Some(other) => {
+            return Err(invalid(
+                "text",
+                "optimize_speed, optimize_legibility, geometric_precision",
+                other,
+            ))
+        }
+    };
+    let image_rendering =
+        match options.image_rendering.as_deref() {
+            None => ImageRendering::default(),
+            Some("optimize_quality") => ImageRendering::OptimizeQuality,
+            Some("optimize_speed") => ImageRendering::OptimizeSpeed,
+            Some("smooth") => ImageRendering::Smooth,
+            Some("high_quality") => ImageRendering::HighQuality,
+            Some("crisp_edges") => ImageRendering::CrispEdges,
+            Some("pixelated") => ImageRendering::Pixelated,
+            Some(other) => return Err(invalid(
+                "image",
+                "optimize_quality, optimize_speed, smooth, high_quality, crisp_edges, pixelated",
+                other,
+            )),
+        };
+
+    Ok((shape_rendering, text_rendering, image_rendering))
+}
+
+/// Checks that every language is a BCP 47 style tag: alphanumeric subtags joined by hyphens
+fn validate_languages(languages: &[String]) -> Result<(), ConverterError> {
+    let valid = |tag: &str| {
+        tag.split('-')
+            .all(|subtag| !subtag.is_empty() && subtag.chars().all(|c| c.is_ascii_alphanumeric()))
+    };
+    match languages.iter().find(|tag| !valid(tag)) {
+        Some(tag) => Err(ConverterError::InvalidOption(format!(
+            "Invalid language: must be a BCP 47 tag such as en or en-US, got {:?}",
+            tag
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn validate_dpi(dpi: Option<f32>) -> Result<(), ConverterError> {
+    match dpi {
+        Some(dpi) if !(dpi.is_finite() && dpi > 0.0) => Err(ConverterError::InvalidOption(
+            format!("Invalid DPI: must be a positive number, got {}", dpi),
+        )),
+        _ => Ok(()),
+    }
+}
+
+const DEFAULT_QUALITY: u8 = 90;
+
+/// rav1e speed preset from 1 (slowest, smallest) to 10; 4 is the `cavif` default
+#[cfg(feature = "avif")]
+const AVIF_SPEED: u8 = 4;
+
+/// Returns the opacity to composite the content at, or `None` when it is fully opaque
+fn validate_opacity(opacity: Option<f32>) -> Result<Option<f32>, ConverterError> {
+    match opacity {
+        None | Some(1.0) => Ok(None),
+        Some(opacity) if (0.0..=1.0).contains(&opacity) => Ok(Some(opacity)),
+        Some(opacity) => Err(ConverterError::InvalidOption(format!(
+            "Invalid opacity: must be between 0 and 1, got {}",
+            opacity
+        ))),
+    }
+}
+
+/// Largest number of pixels a single image may allocate unless `max_pixels` is given: 64
+/// megapixels, or 256 MiB of RGBA data
+const DEFAULT_MAX_PIXELS: u64 = 64 * 1024 * 1024;
+
+/// Checks a `width` x `height` image against the `max_pixels` limit in `options` before it
+/// is allocated, so oversized requests fail without exhausting memory
+pub fn check_pixel_limit(
+    width: u64,
+    height: u64,
+    options: &ConvertOptions,
+) -> Result<(), ConverterError> {
+    let max_pixels = match options.max_pixels {
+        None => DEFAULT_MAX_PIXELS,
+        Some(0) => {
+            return Err(ConverterError::InvalidOption(
+                "Invalid max_pixels: must be greater than 0".to_string(),
+            ))
+        }
+        Some(max_pixels) => max_pixels,
+    };
+
+    let pixels = width.saturating_mul(height);
+    if pixels > max_pixels {
+        return Err(ConverterError::InvalidDimensions(format!(
+            "Invalid dimensions: {}x{} is {} pixels, more than the limit of {}",
+            width, height, pixels, max_pixels
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the factor the intrinsic size is multiplied by, which defaults to 1 and can't be
+/// combined with an explicit width or height
+fn validate_scale(options: &ConvertOptions) -> Result<f32, ConverterError> {
+    match options.scale {
+        None => Ok(1.0),
+        Some(scale) if !(scale.is_finite() && scale > 0.0) => Err(ConverterError::InvalidOption(
+            format!("Invalid scale: must be a positive number, got {}", scale),
+        )),
+        Some(_) if options.width.is_some() || options.height.is_some() => {
+            Err(ConverterError::InvalidOption(
+                "Invalid scale: can't be combined with width or height".to_string(),
+            ))
+        }
+        Some(scale) => Ok(scale),
+    }
+}
+
+/// An output dimension parsed from a `Dimension`
+#[derive(Clone, Copy)]
+enum Length {
+    Pixels(u32),
+    /// Percent of the intrinsic size, multiplied like `scale`
+    Percent(f32),
+}
+
+impl Length {
+    /// Returns the length in pixels, rounding a percentage of `intrinsic` to the nearest one
+    fn resolve(self, intrinsic: f32) -> u32 {
+        match self {
+            Length::Pixels(pixels) => pixels,
+            Length::Percent(percent) => ((intrinsic * percent / 100.0).round() as u32).max(1),
+        }
+    }
+}
+
+impl std::fmt::Display for Length {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Length::Pixels(pixels) => write!(f, "{}", pixels),
+            Length::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+/// Parses the output width and height, each of which scales independently when it is a
+/// percentage
+fn output_lengths(
+    options: &ConvertOptions,
+) -> Result<(Option<Length>, Option<Length>), ConverterError> {
+    let width = options
+        .width
+        .as_ref()
+        .map(|w| parse_length("width", w))
+        .transpose()?;
+    let height = options
+        .height
+        .as_ref()
+        .map(|h| parse_length("height", h))
+        .transpose()?;
+
+    let is_zero = |length| matches!(length, Some(Length::Pixels(0)));
+    if is_zero(width) || is_zero(height) {
+        return Err(ConverterError::InvalidDimensions(format!(
+            "Invalid dimensions: width and height must be greater than 0, got {}x{}",
+            width.map_or("auto".to_string(), |w| w.to_string()),
+            height.map_or("auto".to_string(), |h| h.to_string())
+        )));
+    }
+    Ok((width, height))
+}
+
+fn parse_length(name: &str, dimension: &Dimension) -> Result<Length, ConverterError> {
+    let text = match dimension {
+        Dimension::Pixels(pixels) => return Ok(Length::Pixels(*pixels)),
+        Dimension::Text(text) => text.trim(),
+    };
+    let length = match text.strip_suffix('%') {
+        Some(percent) => percent
+            .trim_end()
+            .parse::<f32>()
+            .ok()
+            .filter(|percent| percent.is_finite() && *percent > 0.0)
+            .map(Length::Percent),
+        None => text.parse().ok().map(Length::Pixels),
+    };
+    length.ok_or_else(|| {
+        ConverterError::InvalidDimensions(format!(
+            "Invalid dimensions: {} must be a number of pixels or a positive percentage such \
+             as \"150%\", got {:?}",
+            name, text
+        ))
+    })
+}
+
+/// Returns the device pixel ratio the intrinsic size is rendered at, which defaults to 1.
+/// Unlike `scale` it combines with a width or height: pixel dimensions are already physical
+/// and ignore it, while percentages of the intrinsic size are multiplied by it.
+fn validate_dpr(options: &ConvertOptions) -> Result<f32, ConverterError> {
+    match options.dpr {
+        None => Ok(1.0),
+        Some(dpr) if !(dpr.is_finite() && dpr > 0.0) => Err(ConverterError::InvalidOption(
+            format!("Invalid dpr: must be a positive number, got {}", dpr),
+        )),
+        Some(_) if options.scale.is_some() => Err(ConverterError::InvalidOption(
+            "Invalid dpr: can't be combined with scale".to_string(),
+        )),
+        Some(dpr) => Ok(dpr),
+    }
+}
+
+/// Largest supersampling factor; memory use grows with its square
+const MAX_SSAA: u8 = 8;
+
+fn validate_ssaa(ssaa: Option<u8>) -> Result<u32, ConverterError> {
+    match ssaa {
+        None => Ok(1),
+        Some(factor @ 1..=MAX_SSAA) => Ok(factor as u32),
+        Some(factor) => Err(ConverterError::InvalidOption(format!(
+            "Invalid ssaa: must be between 1 and {}, got {}",
+            MAX_SSAA, factor
+        ))),
+    }
+}
+
+fn validate_quality(quality: Option<u8>) -> Result<u8, ConverterError> {
+    match quality {
+        None => Ok(DEFAULT_QUALITY),
+        Some(q @ 1..=100) => Ok(q),
+        Some(q) => Err(ConverterError::InvalidOption(format!(
+            "Invalid quality: must be between 1 and 100, got {}",
+            q
+        ))),
+    }
+}
 
-    Ok((r, g, b))
+/// Returns the `alpha_threshold` option, which must be at least 1 since a threshold of 0
+/// would make every pixel opaque, fully transparent ones included
+pub fn validate_alpha_threshold(threshold: Option<u8>) -> Result<Option<u8>, ConverterError> {
+    match threshold {
+        Some(0) => Err(ConverterError::InvalidOption(
+            "Invalid alpha_threshold: must be between 1 and 255, got 0".to_string(),
+        )),
+        threshold => Ok(threshold),
+    }
 }
 
+/// Largest width and height libwebp's lossy encoder accepts
+const MAX_LOSSY_WEBP_SIZE: u32 = 16383;
+
+/// Encodes RGBA data to `format`, other than PNG (see `encode_png`). When `grayscale` is set
+/// the color channels are all equal and JPEG output is written as a single luminance channel.
+/// `lossless` only applies to WebP, `dither` and `alpha_threshold` only to GIF, and
+/// `progressive`, `subsampling` and `color_profile` only to JPEG.
+#[allow(clippy::too_many_arguments)]
 fn encode_image(
     data: &[u8],
     width: u32,
     height: u32,
     format: &str,
     has_alpha: bool,
-) -> Result<Vec<u8>, String> {
+    quality: u8,
+    lossless: bool,
+    grayscale: bool,
+    dither: bool,
+    alpha_threshold: u8,
+    progressive: bool,
+    subsampling: SamplingFactor,
+    color_profile: Option<ColorProfile>,
+) -> Result<Vec<u8>, ConverterError> {
     let mut output = Vec::new();
     let cursor = Cursor::new(&mut output);
 
     match format.to_lowercase().as_str() {
-        "png" => {
-            let encoder = PngEncoder::new(cursor);
+        "jpg" | "jpeg" => {
+            output = encode_jpeg(
+                data,
+                width,
+                height,
+                quality,
+                grayscale,
+                progressive,
+                subsampling,
+                color_profile,
+            )?
+        }
+        "gif" => output = encode_gif(data, width, height, has_alpha, alpha_threshold, dither)?,
+        // The image crate only ships a lossless WebP encoder, so lossy WebP goes through libwebp
+        "webp" if lossless => {
+            WebPEncoder::new_lossless(cursor)
+                .write_image(data, width, height, ExtendedColorType::Rgba8)
+                .map_err(|e| {
+                    ConverterError::EncodeFailed(format!("Failed to encode WebP: {}", e))
+                })?;
+        }
+        "webp" => {
+            if width > MAX_LOSSY_WEBP_SIZE || height > MAX_LOSSY_WEBP_SIZE {
+                return Err(ConverterError::EncodeFailed(format!(
+                    "Failed to encode WebP: {}x{} is larger than the {}x{} maximum of lossy WebP",
+                    width, height, MAX_LOSSY_WEBP_SIZE, MAX_LOSSY_WEBP_SIZE
+                )));
+            }
+            let encoded = webp::Encoder::from_rgba(data, width, height)
+                .encode_simple(false, quality as f32)
+                .map_err(|e| {
+                    ConverterError::EncodeFailed(format!("Failed to encode WebP: {:?}", e))
+                })?;
+            output = encoded.to_vec();
+        }
+        "tiff" | "tif" => {
+            // TIFF supports alpha, so keep the RGBA data as-is
+            TiffEncoder::new(cursor)
+                .write_image(data, width, height, ExtendedColorType::Rgba8)
+                .map_err(|e| {
+                    ConverterError::EncodeFailed(format!("Failed to encode TIFF: {}", e))
+                })?;
+        }
+        #[cfg(feature = "avif")]
+        "avif" => {
+            let encoder = AvifEncoder::new_with_speed_quality(cursor, AVIF_SPEED, quality);
             encoder
-                .write_image(data, width, height, image::ExtendedColorType::Rgba8)
-                .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+                .write_image(data, width, height, ExtendedColorType::Rgba8)
+                .map_err(|e| {
+                    ConverterError::EncodeFailed(format!("Failed to encode AVIF: {}", e))
+                })?;
         }
-        "jpg" | "jpeg" => {
-            // Convert RGBA to RGB for JPEG
+        "qoi" => {
+            // QOI supports alpha, so keep the RGBA data as-is
+            QoiEncoder::new(cursor)
+                .write_image(data, width, height, ExtendedColorType::Rgba8)
+                .map_err(|e| {
+                    ConverterError::EncodeFailed(format!("Failed to encode QOI: {}", e))
+                })?;
+        }
+        "tga" => {
+            // TGA supports alpha, so keep the RGBA data as-is
+            TgaEncoder::new(cursor)
+                .write_image(data, width, height, ExtendedColorType::Rgba8)
+                .map_err(|e| {
+                    ConverterError::EncodeFailed(format!("Failed to encode TGA: {}", e))
+                })?;
+        }
+        "pnm" | "ppm" => {
+            // PPM is RGB-only, so write the flattened RGB data like JPEG. The encoder would
+            // otherwise pick PAM, which fewer tools read
             let rgb_data = rgba_to_rgb(data);
-            let encoder = JpegEncoder::new_with_quality(cursor, 90);
+            let encoder =
+                PnmEncoder::new(cursor).with_subtype(PnmSubtype::Pixmap(SampleEncoding::Binary));
             encoder
-                .write_image(&rgb_data, width, height, image::ExtendedColorType::Rgb8)
-                .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
-        }
-        "gif" => {
-            // For GIF, we need to use the image crate's DynamicImage
-            let img = if has_alpha {
-                ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(width, height, data.to_vec())
-                    .ok_or_else(|| "Failed to create image buffer".to_string())?
+                .write_image(&rgb_data, width, height, ExtendedColorType::Rgb8)
+                .map_err(|e| {
+                    ConverterError::EncodeFailed(format!("Failed to encode PPM: {}", e))
+                })?;
+        }
+        "bmp" => {
+            // Most BMP readers ignore alpha, so write the flattened RGB data like JPEG
+            let rgb_data = rgba_to_rgb(data);
+            BmpEncoder::new(&mut Cursor::new(&mut output))
+                .write_image(&rgb_data, width, height, ExtendedColorType::Rgb8)
+                .map_err(|e| {
+                    ConverterError::EncodeFailed(format!("Failed to encode BMP: {}", e))
+                })?;
+        }
+        "ff" | "farbfeld" => {
+            // Farbfeld always stores 16 bits per channel, so widen each 8-bit sample
+            let rgba16: Vec<u8> = data
+                .iter()
+                .flat_map(|&channel| (channel as u16 * 257).to_ne_bytes())
+                .collect();
+            FarbfeldEncoder::new(cursor)
+                .write_image(&rgba16, width, height, ExtendedColorType::Rgba16)
+                .map_err(|e| {
+                    ConverterError::EncodeFailed(format!("Failed to encode farbfeld: {}", e))
+                })?;
+        }
+        #[cfg(feature = "exr")]
+        "exr" => {
+            // OpenEXR stores float channels, so map each 8-bit sample onto 0.0 to 1.0
+            let rgba32f: Vec<u8> = data
+                .iter()
+                .flat_map(|&channel| (channel as f32 / 255.0).to_ne_bytes())
+                .collect();
+            OpenExrEncoder::new(cursor)
+                .write_image(&rgba32f, width, height, ExtendedColorType::Rgba32F)
+                .map_err(|e| {
+                    ConverterError::EncodeFailed(format!("Failed to encode EXR: {}", e))
+                })?;
+        }
+        _ => return Err(unsupported_format(format)),
+    }
+
+    Ok(output)
+}
+
+/// The error for a format `encode_image` can't write, which includes avif and exr when the
+/// native library was built without their features
+fn unsupported_format(format: &str) -> ConverterError {
+    let missing_feature = match format.to_lowercase().as_str() {
+        "avif" if cfg!(not(feature = "avif")) => Some("avif"),
+        "exr" if cfg!(not(feature = "exr")) => Some("exr"),
+        _ => None,
+    };
+    if let Some(feature) = missing_feature {
+        return ConverterError::UnsupportedFormat(format!(
+            "Unsupported format: {} (the native library was built without the {} feature)",
+            feature, feature
+        ));
+    }
+
+    ConverterError::UnsupportedFormat(format!("Unsupported format: {}", format))
+}
+
+/// Converts tiny-skia's premultiplied RGBA8 to the straight alpha every output format
+/// stores, so a 50% transparent red pixel is written as full red with half alpha rather than
+/// darkened. Opaque data is the same either way and is borrowed as-is.
+pub fn straight_alpha(data: &[u8]) -> Cow<'_, [u8]> {
+    if data.chunks_exact(4).all(|pixel| pixel[3] == u8::MAX) {
+        return Cow::Borrowed(data);
+    }
+
+    let mut straight = data.to_vec();
+    for pixel in straight.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        if alpha == 0 || alpha == 255 {
+            continue;
+        }
+        for channel in &mut pixel[..3] {
+            *channel = ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+        }
+    }
+    Cow::Owned(straight)
+}
+
+/// Replaces the color channels of every pixel with its Rec. 709 luminance, keeping alpha
+pub fn rgba_to_grayscale(rgba_data: &[u8]) -> Vec<u8> {
+    let mut gray_data = rgba_data.to_vec();
+
+    for pixel in gray_data.chunks_exact_mut(4) {
+        let luma = luminance(pixel[0], pixel[1], pixel[2]);
+        pixel[..3].fill(luma);
+    }
+
+    gray_data
+}
+
+fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    ((2126 * r as u32 + 7152 * g as u32 + 722 * b as u32 + 5000) / 10000) as u8
+}
+
+/// Keeps one channel of RGBA data whose color channels are already equal
+fn rgba_to_luma(rgba_data: &[u8]) -> Vec<u8> {
+    rgba_data.chunks(4).map(|chunk| chunk[0]).collect()
+}
+
+/// The PNG encoding settings of a conversion, validated against its format
+struct PngSettings<'a> {
+    bit_depth: u8,
+    compression: png::Compression,
+    metadata: &'a [(String, String)],
+    pixels_per_meter: Option<u32>,
+    /// Also embedded in JPEG output, so it's validated here for every format
+    color_profile: Option<ColorProfile<'a>>,
+}
+
+impl<'a> PngSettings<'a> {
+    /// Validates the PNG-only options, which are errors for any other format when set
+    fn from_options(options: &'a ConvertOptions) -> Result<Self, ConverterError> {
+        let format = options.format.as_str();
+        validate_metadata(&options.metadata, format)?;
+        Ok(PngSettings {
+            bit_depth: validate_bit_depth(options.bit_depth, format)?,
+            compression: parse_png_compression(options.png_compression.as_deref())?,
+            metadata: &options.metadata,
+            pixels_per_meter: output_pixels_per_meter(options.dpi_output, format)?,
+            color_profile: output_color_profile(options)?,
+        })
+    }
+
+    /// Returns an RGBA encoder writing to `output` with a text chunk for each `(keyword,
+    /// text)` pair of the metadata: tEXt when the text is Latin-1 and iTXt (UTF-8) otherwise.
+    /// The pixels per meter are written as a pHYs chunk and the color profile as an sRGB or
+    /// iCCP chunk when given.
+    ///
+    /// The output is reproducible: no tIME chunk or other per-run data is written, and the
+    /// adaptive filter picks each row's filter from the pixels alone, so the same image always
+    /// encodes to the same bytes.
+    fn encoder<W: Write>(
+        &self,
+        output: W,
+        width: u32,
+        height: u32,
+    ) -> Result<png::Encoder<'a, W>, ConverterError> {
+        let mut info = png::Info::with_size(width, height);
+        if let Some(ColorProfile::Icc(icc_profile)) = self.color_profile {
+            info.icc_profile = Some(Cow::Borrowed(icc_profile));
+        }
+        let mut encoder = png::Encoder::with_info(output, info).map_err(png_encode_error)?;
+        if let Some(ColorProfile::Srgb) = self.color_profile {
+            encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        }
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(if self.bit_depth == 16 {
+            png::BitDepth::Sixteen
+        } else {
+            png::BitDepth::Eight
+        });
+        encoder.set_compression(self.compression);
+        encoder.set_filter(png::Filter::Adaptive);
+        encoder.set_pixel_dims(self.pixels_per_meter.map(|pixels| png::PixelDimensions {
+            xppu: pixels,
+            yppu: pixels,
+            unit: png::Unit::Meter,
+        }));
+        for (keyword, text) in self.metadata {
+            if text.chars().all(|c| c <= '\u{ff}') {
+                encoder.add_text_chunk(keyword.clone(), text.clone())
             } else {
-                ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(width, height, data.to_vec())
-                    .ok_or_else(|| "Failed to create image buffer".to_string())?
-            };
+                encoder.add_itxt_chunk(keyword.clone(), text.clone())
+            }
+            .map_err(png_encode_error)?;
+        }
+        Ok(encoder)
+    }
 
-            image::DynamicImage::ImageRgba8(img)
-                .write_to(&mut Cursor::new(&mut output), ImageFormat::Gif)
-                .map_err(|e| format!("Failed to encode GIF: {}", e))?;
+    /// Returns RGBA8 samples at the PNG's bit depth. At 16 bits each channel is widened
+    /// exactly (`v * 257` maps 255 to 65535), so the pixels match the 8-bit output at higher
+    /// precision; PNG stores them big-endian.
+    fn samples<'d>(&self, data: &'d [u8]) -> Cow<'d, [u8]> {
+        if self.bit_depth == 16 {
+            Cow::Owned(
+                data.iter()
+                    .flat_map(|&channel| (channel as u16 * 257).to_be_bytes())
+                    .collect(),
+            )
+        } else {
+            Cow::Borrowed(data)
         }
-        "webp" => {
-            // For WebP, we need to convert to DynamicImage
-            let img = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(width, height, data.to_vec())
-                .ok_or_else(|| "Failed to create image buffer".to_string())?;
+    }
+}
+
+fn png_encode_error(e: png::EncodingError) -> ConverterError {
+    ConverterError::EncodeFailed(format!("Failed to encode PNG: {}", e))
+}
+
+/// Encodes RGBA8 data as a PNG with the bit depth, compression and chunks of `settings`
+fn encode_png(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    settings: &PngSettings,
+) -> Result<Vec<u8>, ConverterError> {
+    let mut output = Vec::new();
+    settings
+        .encoder(&mut output, width, height)?
+        .write_header()
+        .and_then(|mut writer| {
+            writer.write_image_data(&settings.samples(data))?;
+            writer.finish()
+        })
+        .map_err(png_encode_error)?;
 
-            image::DynamicImage::ImageRgba8(img)
-                .write_to(&mut Cursor::new(&mut output), ImageFormat::WebP)
-                .map_err(|e| format!("Failed to encode WebP: {}", e))?;
+    Ok(output)
+}
+
+/// Encodes RGBA8 data as a JPEG, dropping alpha, or as a single luminance channel when
+/// `grayscale` is set. `progressive` writes a progressive JPEG (SOF2) that browsers can show
+/// at low detail before it has fully loaded, and a color profile is embedded in APP2
+/// segments.
+#[allow(clippy::too_many_arguments)]
+fn encode_jpeg(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    quality: u8,
+    grayscale: bool,
+    progressive: bool,
+    subsampling: SamplingFactor,
+    color_profile: Option<ColorProfile>,
+) -> Result<Vec<u8>, ConverterError> {
+    let (Ok(jpeg_width), Ok(jpeg_height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(ConverterError::EncodeFailed(format!(
+            "Failed to encode JPEG: {}x{} is larger than the 65535x65535 maximum",
+            width, height
+        )));
+    };
+
+    let icc_profile = match color_profile {
+        // The profiles describe RGB data, which a luminance-only JPEG doesn't have
+        Some(_) if grayscale => {
+            return Err(ConverterError::InvalidOption(
+                "Invalid color profile: grayscale JPEG output can't embed an RGB profile"
+                    .to_string(),
+            ))
         }
-        _ => return Err(format!("Unsupported format: {}", format)),
+        Some(ColorProfile::Icc(icc_profile)) => Some(Cow::Borrowed(icc_profile)),
+        Some(ColorProfile::Srgb) => Some(Cow::Owned(
+            moxcms::ColorProfile::new_srgb().encode().map_err(|e| {
+                ConverterError::EncodeFailed(format!("Failed to encode sRGB profile: {}", e))
+            })?,
+        )),
+        None => None,
+    };
+
+    let mut output = Vec::new();
+    let mut encoder = jpeg_encoder::Encoder::new(&mut output, quality);
+    encoder.set_sampling_factor(subsampling);
+    encoder.set_progressive(progressive);
+    if let Some(icc_profile) = icc_profile {
+        encoder
+            .add_icc_profile(&icc_profile)
+            .map_err(|e| ConverterError::EncodeFailed(format!("Failed to encode JPEG: {}", e)))?;
+    }
+    let result = if grayscale {
+        encoder.encode(
+            &rgba_to_luma(data),
+            jpeg_width,
+            jpeg_height,
+            jpeg_encoder::ColorType::Luma,
+        )
+    } else {
+        encoder.encode(data, jpeg_width, jpeg_height, jpeg_encoder::ColorType::Rgba)
+    };
+    result.map_err(|e| ConverterError::EncodeFailed(format!("Failed to encode JPEG: {}", e)))?;
+
+    Ok(output)
+}
+
+/// Pixels with less alpha than this become the transparent palette entry of a GIF, and the
+/// rest are made fully opaque, since GIF has no partial transparency. `alpha_threshold`
+/// overrides it.
+pub const DEFAULT_ALPHA_THRESHOLD: u8 = 128;
+
+/// NeuQuant sampling factor from 1 (slowest, best) to 30; 10 is the recommended default
+const GIF_QUANTIZER_SPEED: i32 = 10;
+
+/// Encodes straight RGBA8 data as a single-frame indexed GIF (see `gif_frame`)
+fn encode_gif(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    has_alpha: bool,
+    alpha_threshold: u8,
+    dither: bool,
+) -> Result<Vec<u8>, ConverterError> {
+    let frame = gif_frame(data, width, height, has_alpha, alpha_threshold, dither)?;
+    let mut output = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut output, frame.width, frame.height, &[])
+            .map_err(|e| ConverterError::EncodeFailed(format!("Failed to encode GIF: {}", e)))?;
+        encoder
+            .write_frame(&frame)
+            .map_err(|e| ConverterError::EncodeFailed(format!("Failed to encode GIF: {}", e)))?;
     }
 
     Ok(output)
 }
 
-fn rgba_to_rgb(rgba_data: &[u8]) -> Vec<u8> {
+/// Quantizes straight RGBA8 data into an indexed GIF frame. With `has_alpha`, pixels with
+/// less alpha than `alpha_threshold` map to a dedicated transparent palette entry. Images
+/// with more opaque colors than fit in the palette are quantized with NeuQuant, and with
+/// `dither` the quantization error is diffused with Floyd-Steinberg dithering to avoid
+/// banding.
+pub fn gif_frame(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    has_alpha: bool,
+    alpha_threshold: u8,
+    dither: bool,
+) -> Result<gif::Frame<'static>, ConverterError> {
+    let (Ok(gif_width), Ok(gif_height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(ConverterError::EncodeFailed(format!(
+            "Failed to encode GIF: {}x{} is larger than the 65535x65535 maximum",
+            width, height
+        )));
+    };
+
+    // Straight RGBA with full alpha for opaque pixels, or `None` for transparent ones
+    let pixels: Vec<Option<[u8; 4]>> = data
+        .chunks_exact(4)
+        .map(|pixel| match pixel[3] {
+            alpha if has_alpha && alpha < alpha_threshold => None,
+            _ => Some([pixel[0], pixel[1], pixel[2], 255]),
+        })
+        .collect();
+    let has_transparency = pixels.iter().any(Option::is_none);
+    let max_colors = if has_transparency { 255 } else { 256 };
+
+    let mut colors: Vec<[u8; 4]> = pixels.iter().flatten().copied().collect();
+    colors.sort_unstable();
+    colors.dedup();
+    let quantizer = (colors.len() > max_colors).then(|| {
+        let opaque: Vec<u8> = pixels.iter().flatten().flatten().copied().collect();
+        color_quant::NeuQuant::new(GIF_QUANTIZER_SPEED, max_colors, &opaque)
+    });
+    let mut palette: Vec<u8> = match &quantizer {
+        Some(quantizer) => quantizer.color_map_rgb(),
+        None => colors
+            .iter()
+            .flat_map(|color| &color[..3])
+            .copied()
+            .collect(),
+    };
+
+    // The transparent entry comes after the opaque colors, which leave room for it
+    let transparent_index = (palette.len() / 3) as u8;
+    let indices: Vec<u8> = match &quantizer {
+        Some(quantizer) if dither => {
+            dither_indices(&pixels, width as usize, quantizer, transparent_index)
+        }
+        _ => pixels
+            .iter()
+            .map(|pixel| match (pixel, &quantizer) {
+                (None, _) => transparent_index,
+                (Some(color), Some(quantizer)) => quantizer.index_of(color) as u8,
+                (Some(color), None) => colors.binary_search(color).unwrap_or(0) as u8,
+            })
+            .collect(),
+    };
+    let transparent = if has_transparency {
+        palette.extend_from_slice(&[0, 0, 0]);
+        Some(transparent_index)
+    } else {
+        None
+    };
+
+    Ok(gif::Frame::from_palette_pixels(
+        gif_width,
+        gif_height,
+        indices,
+        palette,
+        transparent,
+    ))
+}
+
+/// Maps opaque pixels to the nearest palette entry of `quantizer`, spreading each pixel's
+/// error over its unvisited neighbors with the Floyd-Steinberg weights. Transparent pixels
+/// map to `transparent_index` and neither take nor pass on any error.
+fn dither_indices(
+    pixels: &[Option<[u8; 4]>],
+    width: usize,
+    quantizer: &color_quant::NeuQuant,
+    transparent_index: u8,
+) -> Vec<u8> {
+    // Accumulated error per pixel and channel, in sixteenths
+    let mut errors = vec![[0i32; 3]; pixels.len()];
+    let mut indices = Vec::with_capacity(pixels.len());
+
+    for (i, pixel) in pixels.iter().enumerate() {
+        let Some(color) = pixel else {
+            indices.push(transparent_index);
+            continue;
+        };
+
+        let mut wanted = [0u8, 0, 0, 255];
+        for channel in 0..3 {
+            wanted[channel] = (color[channel] as i32 + errors[i][channel] / 16).clamp(0, 255) as u8;
+        }
+        let index = quantizer.index_of(&wanted);
+        indices.push(index as u8);
+
+        let actual = quantizer.lookup(index).unwrap_or(wanted);
+        let (x, last_column) = (i % width, i % width == width - 1);
+        let neighbors = [
+            (!last_column).then(|| (i + 1, 7)),
+            (x > 0).then(|| (i + width - 1, 3)),
+            Some((i + width, 5)),
+            (!last_column).then(|| (i + width + 1, 1)),
+        ];
+        for (neighbor, weight) in neighbors.into_iter().flatten() {
+            if neighbor < pixels.len() && pixels[neighbor].is_some() {
+                for channel in 0..3 {
+                    errors[neighbor][channel] +=
+                        (wanted[channel] as i32 - actual[channel] as i32) * weight;
+                }
+            }
+        }
+    }
+
+    indices
+}
+
+/// Drops the alpha channel, which only keeps the colors intact for opaque pixels
+pub fn rgba_to_rgb(rgba_data: &[u8]) -> Vec<u8> {
     let mut rgb_data = Vec::with_capacity(rgba_data.len() * 3 / 4);
 
     for chunk in rgba_data.chunks(4) {
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use crate::error::ConverterError;
+
+/// How many custom properties may reference each other in a chain before the reference is
+/// left unresolved, which also ends cycles such as `--a: var(--b)` with `--b: var(--a)`
+const MAX_DEPTH: usize = 16;
+
+/// Resolves the `var()` references in the SVG, which usvg doesn't support, from the custom
+/// properties declared in its style sheets and style attributes, with `overrides` taking
+/// precedence. Declarations apply to the whole document, the last one winning, rather than
+/// following the cascade. A reference to an undefined property is replaced by its fallback,
+/// or left as it is without one. Returns `None` when the SVG has no references.
+pub fn resolve_css_vars(
+    svg: &str,
+    overrides: &[(String, String)],
+) -> Result<Option<String>, ConverterError> {
+    let overrides = overrides
+        .iter()
+        .map(|(name, value)| {
+            let property = name.strip_prefix("--").unwrap_or(name);
+            if property.is_empty() || !property.chars().all(is_name_char) {
+                return Err(ConverterError::InvalidOption(format!(
+                    "Invalid css_vars name {:?}: must be a custom property name such as --brand",
+                    name
+                )));
+            }
+            Ok((property, value.trim()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if !svg.contains("var(") {
+        return Ok(None);
+    }
+
+    let mut properties = declarations(svg);
+    properties.extend(overrides);
+    Ok(Some(substitute(svg, &properties, MAX_DEPTH)))
+}
+
+/// Returns the custom property declarations in the SVG by name, without the leading `--`
+fn declarations(svg: &str) -> HashMap<&str, &str> {
+    let mut properties = HashMap::new();
+    for (index, _) in svg.match_indices("--") {
+        // A declaration starts a block, a style attribute or a declaration list, which also
+        // rules out comment delimiters
+        let starts_declaration = svg[..index]
+            .ends_with(|c: char| c.is_whitespace() || matches!(c, '{' | ';' | '"' | '\''));
+        if !starts_declaration {
+            continue;
+        }
+        let rest = &svg[index + 2..];
+        let (name, rest) = rest.split_at(rest.find(|c| !is_name_char(c)).unwrap_or(rest.len()));
+        let Some(value) = rest.trim_start().strip_prefix(':') else {
+            continue;
+        };
+        if !name.is_empty() {
+            let end = value
+                .find([';', '}', '"', '\'', '<'])
+                .unwrap_or(value.len());
+            properties.insert(name, value[..end].trim());
+        }
+    }
+    properties
+}
+
+/// Replaces each `var()` reference in `text` with its value, resolving references within
+/// values up to `depth` levels deep
+fn substitute(text: &str, properties: &HashMap<&str, &str>, depth: usize) -> String {
+    let mut resolved = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("var(") {
+        resolved.push_str(&rest[..start]);
+        let reference = &rest[start..];
+        // `var(` can also end a longer function name, which isn't a reference
+        let parsed = Some(reference)
+            .filter(|_| !resolved.ends_with(is_name_char))
+            .and_then(parse_reference);
+        let Some((name, fallback, length)) = parsed else {
+            resolved.push_str("var(");
+            rest = &reference[4..];
+            continue;
+        };
+
+        match properties.get(name).copied().or(fallback) {
+            Some(value) if depth > 0 => {
+                resolved.push_str(&substitute(value, properties, depth - 1))
+            }
+            _ => resolved.push_str(&reference[..length]),
+        }
+        rest = &reference[length..];
+    }
+    resolved.push_str(rest);
+    resolved
+}
+
+/// Parses the `var(--name)` or `var(--name, fallback)` reference that `reference` starts
+/// with into the property name, the fallback and the length of the reference
+fn parse_reference(reference: &str) -> Option<(&str, Option<&str>, usize)> {
+    let name = reference[4..].trim_start().strip_prefix("--")?;
+    let (name, rest) = name.split_at(name.find(|c| !is_name_char(c)).unwrap_or(name.len()));
+    let rest = rest.trim_start();
+    if name.is_empty() || !rest.starts_with([')', ',']) {
+        return None;
+    }
+
+    // The fallback runs to the closing parenthesis, and may contain functions of its own
+    let mut nesting = 0;
+    let close = rest.char_indices().find_map(|(index, c)| match c {
+        '(' => {
+            nesting += 1;
+            None
+        }
+        ')' if nesting == 0 => Some(index),
+        ')' => {
+            nesting -= 1;
+            None
+        }
+        _ => None,
+    })?;
+    let fallback = rest[..close].strip_prefix(',').map(str::trim);
+    let length = reference.len() - rest.len() + close + 1;
+    Some((name, fallback, length))
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
@@ -0,0 +1,87 @@
+use rustler::{Encoder, Env, Term};
+use std::fmt;
+
+mod atoms {
+    rustler::atoms! {
+        parse_failed,
+        unsupported_format,
+        invalid_color,
+        invalid_dimensions,
+        invalid_option,
+        node_not_found,
+        render_failed,
+        encode_failed,
+        write_failed,
+        timeout,
+    }
+}
+
+/// A conversion failure, returned to Elixir as a `{reason, message}` tuple so callers can
+/// match on the reason atom and still show the human-readable message.
+#[derive(Debug)]
+pub enum ConverterError {
+    /// The input could not be decompressed, decoded or parsed as SVG
+    ParseFailed(String),
+    /// The requested output format isn't supported
+    UnsupportedFormat(String),
+    /// The background color couldn't be parsed
+    InvalidColor(String),
+    /// The output dimensions are invalid or can't be derived from the SVG
+    InvalidDimensions(String),
+    /// Any other option has an invalid value
+    InvalidOption(String),
+    /// No element matches the requested `node_id`
+    NodeNotFound(String),
+    /// Rendering the SVG failed
+    RenderFailed(String),
+    /// Encoding the rendered image failed
+    EncodeFailed(String),
+    /// Writing the encoded image to a file failed
+    WriteFailed(String),
+    /// The conversion didn't finish within its `timeout_ms`
+    Timeout(String),
+}
+
+impl ConverterError {
+    pub fn message(&self) -> &str {
+        match self {
+            ConverterError::ParseFailed(message)
+            | ConverterError::UnsupportedFormat(message)
+            | ConverterError::InvalidColor(message)
+            | ConverterError::InvalidDimensions(message)
+            | ConverterError::InvalidOption(message)
+            | ConverterError::NodeNotFound(message)
+            | ConverterError::RenderFailed(message)
+            | ConverterError::EncodeFailed(message)
+            | ConverterError::WriteFailed(message)
+            | ConverterError::Timeout(message) => message,
+        }
+    }
+
+    fn reason(&self) -> rustler::Atom {
+        match self {
+            ConverterError::ParseFailed(_) => atoms::parse_failed(),
+            ConverterError::UnsupportedFormat(_) => atoms::unsupported_format(),
+            ConverterError::InvalidColor(_) => atoms::invalid_color(),
+            ConverterError::InvalidDimensions(_) => atoms::invalid_dimensions(),
+            ConverterError::InvalidOption(_) => atoms::invalid_option(),
+            ConverterError::NodeNotFound(_) => atoms::node_not_found(),
+            ConverterError::RenderFailed(_) => atoms::render_failed(),
+            ConverterError::EncodeFailed(_) => atoms::encode_failed(),
+            ConverterError::WriteFailed(_) => atoms::write_failed(),
+            ConverterError::Timeout(_) => atoms::timeout(),
+        }
+    }
+}
+
+impl fmt::Display for ConverterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl Encoder for ConverterError {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        (self.reason(), self.message()).encode(env)
+    }
+}
@@ -0,0 +1,42 @@
+use crate::converter::{catch_panics, parse_svg, render_image, ConvertOptions, Dimension};
+use crate::error::ConverterError;
+use crate::ico::encode_ico;
+
+/// Sizes packed into `favicon.ico`
+const ICO_SIZES: [u32; 3] = [16, 32, 48];
+
+/// The PNG icons of the bundle and their sizes: the apple-touch icon and the PWA manifest icons
+const PNG_ICONS: [(&str, u32); 3] = [
+    ("apple_touch_icon", 180),
+    ("icon_192", 192),
+    ("icon_512", 512),
+];
+
+/// Converts SVG data to the standard favicon set, returned as `(name, data)` pairs in order: a
+/// multi-size `favicon` ICO followed by the square PNG icons. The SVG is parsed once and every
+/// image is rendered from the same tree; the format and size in `options` are ignored.
+pub fn convert_svg_to_favicon(
+    svg_data: &[u8],
+    options: &ConvertOptions,
+) -> Result<Vec<(&'static str, Vec<u8>)>, ConverterError> {
+    catch_panics(|| {
+        let mut options = ConvertOptions {
+            format: "png".to_string(),
+            width: Some(Dimension::Pixels(ICO_SIZES[0])),
+            height: Some(Dimension::Pixels(ICO_SIZES[0])),
+            scale: None,
+            ..options.clone()
+        };
+        let parsed = parse_svg(svg_data, &options)?;
+
+        let mut bundle = Vec::with_capacity(1 + PNG_ICONS.len());
+        bundle.push(("favicon", encode_ico(&parsed, &options, &ICO_SIZES)?));
+        for (name, size) in PNG_ICONS {
+            options.width = Some(Dimension::Pixels(size));
+            options.height = Some(Dimension::Pixels(size));
+            bundle.push((name, render_image(&parsed, &options)?.data));
+        }
+
+        Ok(bundle)
+    })
+}
@@ -0,0 +1,36 @@
+use std::sync::{Arc, OnceLock};
+use usvg::fontdb::Database;
+
+static SYSTEM_FONTS: OnceLock<Arc<Database>> = OnceLock::new();
+
+/// Returns the font database used for text rendering.
+///
+/// Scanning system fonts is expensive, so the system database is built on first use and
+/// shared for the rest of the process. When `load_system_fonts` is false an empty database
+/// is used instead, which keeps text rendering independent of the host's fonts.
+///
+/// Fonts in `font_dirs` are loaded on top of that for each call, so the shared system
+/// database is left untouched. Directories that can't be read are skipped.
+pub fn font_database(load_system_fonts: bool, font_dirs: &[String]) -> Arc<Database> {
+    let base = if load_system_fonts {
+        SYSTEM_FONTS
+            .get_or_init(|| {
+                let mut db = Database::new();
+                db.load_system_fonts();
+                Arc::new(db)
+            })
+            .clone()
+    } else {
+        Arc::new(Database::new())
+    };
+
+    if font_dirs.is_empty() {
+        return base;
+    }
+
+    let mut db = Arc::unwrap_or_clone(base);
+    for dir in font_dirs {
+        db.load_fonts_dir(dir);
+    }
+    Arc::new(db)
+}
@@ -0,0 +1,104 @@
+use image::codecs::ico::{IcoEncoder, IcoFrame};
+use image::ExtendedColorType;
+
+use crate::converter::{
+    catch_panics, encode_pixmap, parse_svg, render_pixmap, validate_alpha_threshold,
+    ConvertOptions, Dimension, ParsedSvg,
+};
+use crate::error::ConverterError;
+
+/// Largest width and height an ICO entry can describe
+const MAX_ICO_SIZE: u32 = 256;
+
+/// Converts SVG data to an ICO with one square PNG entry per size. The SVG is parsed once
+/// and rendered at each size; the format in `options` is ignored.
+pub fn convert_svg_to_ico(
+    svg_data: &[u8],
+    options: &ConvertOptions,
+    sizes: &[u32],
+) -> Result<Vec<u8>, ConverterError> {
+    catch_panics(|| {
+        validate_sizes(sizes)?;
+        let options = ConvertOptions {
+            format: "png".to_string(),
+            width: Some(Dimension::Pixels(sizes[0])),
+            height: Some(Dimension::Pixels(sizes[0])),
+            scale: None,
+            ..options.clone()
+        };
+        let parsed = parse_svg(svg_data, &options)?;
+
+        encode_ico(&parsed, &options, sizes)
+    })
+}
+
+/// Renders `parsed` as a square PNG at each of `sizes` and packs the results into an ICO.
+/// The PNG entries keep their full alpha unless `options` has an `alpha_threshold`, which
+/// makes every pixel either fully transparent or fully opaque as in a classic icon's mask.
+pub fn encode_ico(
+    parsed: &ParsedSvg,
+    options: &ConvertOptions,
+    sizes: &[u32],
+) -> Result<Vec<u8>, ConverterError> {
+    let alpha_threshold = validate_alpha_threshold(options.alpha_threshold)?;
+    let mut options = options.clone();
+    let mut entries = Vec::with_capacity(sizes.len());
+    for &size in sizes {
+        options.width = Some(Dimension::Pixels(size));
+        options.height = Some(Dimension::Pixels(size));
+        let mut pixmap = render_pixmap(parsed, &options)?;
+        if let Some(threshold) = alpha_threshold {
+            threshold_alpha(&mut pixmap, threshold);
+        }
+        entries.push(encode_pixmap(&pixmap, &options)?);
+    }
+
+    let frames = entries
+        .iter()
+        .map(|entry| {
+            IcoFrame::with_encoded(
+                entry.data.as_slice(),
+                entry.width,
+                entry.height,
+                ExtendedColorType::Rgba8,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ConverterError::EncodeFailed(format!("Failed to encode ICO: {}", e)))?;
+
+    let mut output = Vec::new();
+    IcoEncoder::new(&mut output)
+        .encode_images(&frames)
+        .map_err(|e| ConverterError::EncodeFailed(format!("Failed to encode ICO: {}", e)))?;
+
+    Ok(output)
+}
+
+/// Makes the pixels with less alpha than `threshold` fully transparent and the rest fully
+/// opaque, like GIF output
+fn threshold_alpha(pixmap: &mut tiny_skia::Pixmap, threshold: u8) {
+    for pixel in pixmap.pixels_mut() {
+        *pixel = if pixel.alpha() < threshold {
+            tiny_skia::PremultipliedColorU8::TRANSPARENT
+        } else {
+            let color = pixel.demultiply();
+            tiny_skia::ColorU8::from_rgba(color.red(), color.green(), color.blue(), 255)
+                .premultiply()
+        };
+    }
+}
+
+fn validate_sizes(sizes: &[u32]) -> Result<(), ConverterError> {
+    if sizes.is_empty() {
+        return Err(ConverterError::InvalidDimensions(
+            "Invalid ICO sizes: at least one size is required".to_string(),
+        ));
+    }
+    if let Some(size) = sizes.iter().find(|&&size| size == 0 || size > MAX_ICO_SIZE) {
+        return Err(ConverterError::InvalidDimensions(format!(
+            "Invalid ICO sizes: {} is outside 1..={}",
+            size, MAX_ICO_SIZE
+        )));
+    }
+    Ok(())
+}
@@ -1,30 +1,299 @@
-use rustler::{Binary, Env, OwnedBinary};
+use std::path::Path;
 
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use rustler::types::atom;
+use rustler::{Atom, Binary, Encoder, Env, OwnedBinary, Term};
+
+mod animation;
+mod cache;
+mod color;
+mod color_remap;
 mod converter;
-use converter::convert_svg_to_image;
+mod css_vars;
+mod error;
+mod favicon;
+mod fonts;
+mod ico;
+mod multi;
+mod palette;
+mod pixmap_pool;
+mod raw;
+mod sprite_sheet;
+mod tiles;
+mod warnings;
+use converter::{convert_svg_to_image, convert_svg_to_image_pooled, ConvertOptions};
+use error::ConverterError;
+use pixmap_pool::PixmapPool;
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 fn convert_svg<'a>(
     env: Env<'a>,
-    svg_data: String,
-    format: String,
-    width: Option<u32>,
-    height: Option<u32>,
-    background_color: Option<String>,
-    replacements: Vec<(String, String)>,
-) -> Result<Binary<'a>, String> {
-    let data = convert_svg_to_image(
-        svg_data,
-        format,
-        width,
-        height,
-        background_color,
-        replacements,
-    )?;
-
-    let mut binary =
-        OwnedBinary::new(data.len()).ok_or_else(|| "Failed to allocate binary".to_string())?;
-    binary.as_mut_slice().copy_from_slice(&data);
+    svg_data: Binary,
+    options: ConvertOptions,
+) -> Result<Binary<'a>, ConverterError> {
+    let image = convert_svg_to_image(svg_data.as_slice(), &options)?;
+
+    to_binary(env, &image.data)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn convert_svg_with_info<'a>(
+    env: Env<'a>,
+    svg_data: Binary,
+    options: ConvertOptions,
+) -> Result<(Binary<'a>, u32, u32, Vec<String>), ConverterError> {
+    let image = convert_svg_to_image(svg_data.as_slice(), &options)?;
+
+    Ok((
+        to_binary(env, &image.data)?,
+        image.width,
+        image.height,
+        image.warnings,
+    ))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn convert_svg_to_rgba<'a>(
+    env: Env<'a>,
+    svg_data: Binary,
+    options: ConvertOptions,
+    channels: u8,
+    premultiplied: bool,
+) -> Result<(Binary<'a>, u32, u32, u8), ConverterError> {
+    let (data, width, height) =
+        raw::convert_svg_to_rgba(svg_data.as_slice(), &options, channels, premultiplied)?;
+
+    Ok((to_binary(env, &data)?, width, height, channels))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn svg_intrinsic_size(
+    svg_data: Binary,
+    options: ConvertOptions,
+) -> Result<(f32, f32), ConverterError> {
+    converter::svg_intrinsic_size(svg_data.as_slice(), &options)
+}
+
+#[rustler::nif]
+fn mime_for_format(format: String) -> Result<&'static str, ConverterError> {
+    converter::mime_type(&format)
+}
+
+#[rustler::nif]
+fn extension_for_format(format: String) -> Result<&'static str, ConverterError> {
+    converter::extension(&format)
+}
+
+/// The versions of the rendering crates the NIF was compiled against, as resolved in
+/// Cargo.lock by the build script
+#[derive(rustler::NifMap)]
+struct Versions {
+    usvg: String,
+    resvg: String,
+    tiny_skia: String,
+    image: String,
+}
+
+#[rustler::nif]
+fn versions() -> Versions {
+    Versions {
+        usvg: env!("SVGAGER_USVG_VERSION").to_string(),
+        resvg: env!("SVGAGER_RESVG_VERSION").to_string(),
+        tiny_skia: env!("SVGAGER_TINY_SKIA_VERSION").to_string(),
+        image: env!("SVGAGER_IMAGE_VERSION").to_string(),
+    }
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn convert_svg_to_data_uri(
+    svg_data: Binary,
+    options: ConvertOptions,
+) -> Result<String, ConverterError> {
+    converter::convert_svg_to_data_uri(svg_data.as_slice(), &options)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn convert_svg_to_file<'a>(
+    env: Env<'a>,
+    svg_data: Binary,
+    options: ConvertOptions,
+    path: String,
+) -> Term<'a> {
+    let result = converter::convert_svg_to_file(svg_data.as_slice(), &options, Path::new(&path));
+
+    ok_or_error(env, result)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn validate_svg<'a>(env: Env<'a>, svg_data: Binary, options: ConvertOptions) -> Term<'a> {
+    ok_or_error(env, converter::validate_svg(svg_data.as_slice(), &options))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn svg_element_ids(
+    svg_data: Binary,
+    options: ConvertOptions,
+) -> Result<Vec<String>, ConverterError> {
+    converter::svg_element_ids(svg_data.as_slice(), &options)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn svg_complexity(
+    svg_data: Binary,
+    options: ConvertOptions,
+) -> Result<converter::Complexity, ConverterError> {
+    converter::svg_complexity(svg_data.as_slice(), &options)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn convert_svg_to_ico<'a>(
+    env: Env<'a>,
+    svg_data: Binary,
+    options: ConvertOptions,
+    sizes: Vec<u32>,
+) -> Result<Binary<'a>, ConverterError> {
+    let data = ico::convert_svg_to_ico(svg_data.as_slice(), &options, &sizes)?;
+
+    to_binary(env, &data)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn convert_svg_to_favicon<'a>(
+    env: Env<'a>,
+    svg_data: Binary,
+    options: ConvertOptions,
+) -> Result<Vec<(Atom, Binary<'a>)>, ConverterError> {
+    favicon::convert_svg_to_favicon(svg_data.as_slice(), &options)?
+        .into_iter()
+        .map(|(name, data)| {
+            let name = Atom::from_str(env, name).map_err(|_| {
+                ConverterError::EncodeFailed(format!("Failed to create atom {}", name))
+            })?;
+            Ok((name, to_binary(env, &data)?))
+        })
+        .collect()
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn convert_svgs_to_animated_gif<'a>(
+    env: Env<'a>,
+    frames: Vec<Binary>,
+    delays: Vec<u32>,
+    options: ConvertOptions,
+    loop_count: Option<u16>,
+) -> Result<Binary<'a>, ConverterError> {
+    let frames: Vec<&[u8]> = frames.iter().map(|frame| frame.as_slice()).collect();
+    let data = animation::convert_svgs_to_animated_gif(&frames, &delays, &options, loop_count)?;
+
+    to_binary(env, &data)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn convert_svgs_to_sprite_sheet<'a>(
+    env: Env<'a>,
+    svgs: Vec<Binary>,
+    options: ConvertOptions,
+    columns: u32,
+    cell_size: (u32, u32),
+    gutter: u32,
+) -> Result<(Binary<'a>, u32, u32, Vec<sprite_sheet::SpriteRect>), ConverterError> {
+    let svgs: Vec<&[u8]> = svgs.iter().map(|svg| svg.as_slice()).collect();
+    let sheet =
+        sprite_sheet::convert_svgs_to_sprite_sheet(&svgs, &options, columns, cell_size, gutter)?;
+
+    Ok((
+        to_binary(env, &sheet.data)?,
+        sheet.width,
+        sheet.height,
+        sheet.sprites,
+    ))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn convert_svg_to_tiles<'a>(
+    env: Env<'a>,
+    svg_data: Binary,
+    options: ConvertOptions,
+    tile_size: (u32, u32),
+) -> Result<Vec<(tiles::TileKey, Binary<'a>)>, ConverterError> {
+    tiles::convert_svg_to_tiles(svg_data.as_slice(), &options, tile_size)?
+        .into_iter()
+        .map(|(key, data)| Ok((key, to_binary(env, &data)?)))
+        .collect()
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn convert_svg_multi<'a>(
+    env: Env<'a>,
+    svg_data: Binary,
+    options: ConvertOptions,
+    formats: Vec<String>,
+) -> Result<Vec<(String, Binary<'a>)>, ConverterError> {
+    multi::convert_svg_to_formats(svg_data.as_slice(), &options, &formats)?
+        .into_iter()
+        .map(|(format, data)| Ok((format, to_binary(env, &data)?)))
+        .collect()
+}
+
+/// Converts every `(svg_data, options)` job in parallel, returning one result per job in the
+/// same order so a failing job doesn't fail the rest of the batch. `max_concurrency` bounds
+/// how many jobs render at once; `None` uses the global pool with one thread per CPU.
+#[rustler::nif(schedule = "DirtyCpu")]
+fn convert_svg_batch<'a>(
+    env: Env<'a>,
+    jobs: Vec<(Binary<'a>, ConvertOptions)>,
+    max_concurrency: Option<usize>,
+) -> Result<Vec<Result<Binary<'a>, ConverterError>>, ConverterError> {
+    let jobs: Vec<(&[u8], &ConvertOptions)> = jobs
+        .iter()
+        .map(|(svg_data, options)| (svg_data.as_slice(), options))
+        .collect();
+
+    // Each worker reuses the pixmaps of its previous jobs, which saves reallocating the
+    // buffers when the batch renders many images of the same size
+    let render = || -> Vec<_> {
+        jobs.par_iter()
+            .map_init(PixmapPool::default, |pool, (svg_data, options)| {
+                convert_svg_to_image_pooled(svg_data, options, pool)
+            })
+            .collect()
+    };
+
+    let images = match max_concurrency {
+        None => render(),
+        Some(0) => {
+            return Err(ConverterError::InvalidOption(
+                "max_concurrency must be a positive integer".to_string(),
+            ))
+        }
+        Some(threads) => ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|e| {
+                ConverterError::RenderFailed(format!("Failed to create thread pool: {}", e))
+            })?
+            .install(render),
+    };
+
+    Ok(images
+        .into_iter()
+        .map(|image| image.and_then(|image| to_binary(env, &image.data)))
+        .collect())
+}
+
+/// Encodes `Ok(())` as `:ok` rather than `{:ok, {}}`
+fn ok_or_error(env: Env<'_>, result: Result<(), ConverterError>) -> Term<'_> {
+    match result {
+        Ok(()) => atom::ok().encode(env),
+        Err(error) => (atom::error(), error).encode(env),
+    }
+}
+
+fn to_binary<'a>(env: Env<'a>, data: &[u8]) -> Result<Binary<'a>, ConverterError> {
+    let mut binary = OwnedBinary::new(data.len())
+        .ok_or_else(|| ConverterError::EncodeFailed("Failed to allocate binary".to_string()))?;
+    binary.as_mut_slice().copy_from_slice(data);
 
     Ok(binary.release(env))
 }
@@ -0,0 +1,80 @@
+use crate::converter::{
+    background_color, catch_panics, encode_pixmap, mime_type, parse_svg, render_pixmap,
+    ConvertOptions,
+};
+use crate::error::ConverterError;
+
+/// Renders the SVG once with `options` and encodes the image to each of `formats`, in
+/// order; the format in `options` is ignored.
+///
+/// The image is rendered on transparency, so formats that keep it get the same pixels as
+/// their own `convert_svg_to_image`, and formats that don't are encoded from one copy
+/// flattened onto the background color. With `trim`, the margins trimmed are the
+/// transparent ones for every format, rather than margins of the background color.
+pub fn convert_svg_to_formats(
+    svg_data: &[u8],
+    options: &ConvertOptions,
+    formats: &[String],
+) -> Result<Vec<(String, Vec<u8>)>, ConverterError> {
+    catch_panics(|| {
+        if formats.is_empty() {
+            return Err(ConverterError::InvalidOption(
+                "Invalid formats: at least one format is required".to_string(),
+            ));
+        }
+        // Reject an unsupported format before spending time on the render
+        for format in formats {
+            mime_type(format)?;
+        }
+
+        let render_options = ConvertOptions {
+            format: "png".to_string(),
+            ..options.clone()
+        };
+        let parsed = parse_svg(svg_data, &render_options)?;
+        let image = render_pixmap(&parsed, &render_options)?;
+        // Flattening can only be needed when the render itself stayed transparent, and a
+        // background gradient is already composited under the image for every format
+        let rendered_background = background_color(&render_options)?;
+
+        let mut flattened = None;
+        let mut outputs = Vec::with_capacity(formats.len());
+        for format in formats {
+            let format_options = ConvertOptions {
+                format: format.clone(),
+                ..options.clone()
+            };
+            let background = background_color(&format_options)?
+                .filter(|_| rendered_background.is_none() && options.background_gradient.is_none());
+            let pixmap = match background {
+                Some(color) => match &mut flattened {
+                    Some(pixmap) => &*pixmap,
+                    None => &*flattened.insert(flatten(&image, color)?),
+                },
+                None => &image,
+            };
+            outputs.push((format.clone(), encode_pixmap(pixmap, &format_options)?.data));
+        }
+
+        Ok(outputs)
+    })
+}
+
+/// Composites `pixmap` over an opaque fill of `color`
+fn flatten(
+    pixmap: &tiny_skia::Pixmap,
+    color: tiny_skia::Color,
+) -> Result<tiny_skia::Pixmap, ConverterError> {
+    let mut flattened = tiny_skia::Pixmap::new(pixmap.width(), pixmap.height())
+        .ok_or_else(|| ConverterError::RenderFailed("Failed to flatten image".to_string()))?;
+    flattened.fill(color);
+    flattened.draw_pixmap(
+        0,
+        0,
+        pixmap.as_ref(),
+        &tiny_skia::PixmapPaint::default(),
+        tiny_skia::Transform::identity(),
+        None,
+    );
+    Ok(flattened)
+}
@@ -0,0 +1,80 @@
+use crate::color::parse_color;
+use crate::error::ConverterError;
+
+/// 4x4 Bayer matrix of the ordered dither, in sixteenths
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Parses the palette colors into RGB, or `None` for an empty palette. Their alpha is
+/// ignored, since only the color channels are quantized.
+pub fn parse_palette(colors: &[String]) -> Result<Option<Vec<[u8; 3]>>, ConverterError> {
+    if colors.is_empty() {
+        return Ok(None);
+    }
+    colors
+        .iter()
+        .map(|color| {
+            let (r, g, b, _) = parse_color(color).map_err(ConverterError::InvalidColor)?;
+            Ok([r, g, b])
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+/// Replaces the color of every pixel of straight RGBA8 data `width` pixels wide with the
+/// nearest palette color by Euclidean distance in RGB, keeping alpha. With `dither`, a 4x4
+/// ordered dither offsets each pixel before the lookup so areas between two palette colors
+/// become a pattern of both; unlike error diffusion it's stable, so a small change to the
+/// SVG only changes the pixels it touches. `data` starts at row `first_row` of the image,
+/// which keeps the dither pattern aligned when an image is quantized a few rows at a time.
+pub fn quantize(
+    data: &mut [u8],
+    width: usize,
+    first_row: usize,
+    palette: &[[u8; 3]],
+    dither: bool,
+) {
+    // The offsets span the typical gap between neighboring palette colors, per channel
+    let spread = if dither { dither_spread(palette) } else { 0.0 };
+
+    for (index, pixel) in data.chunks_exact_mut(4).enumerate() {
+        let (x, y) = (index % width, first_row + index / width);
+        let offset = (BAYER[y % 4][x % 4] as f32 + 0.5) / 16.0 - 0.5;
+        let target = [0, 1, 2].map(|channel| pixel[channel] as f32 + offset * spread);
+        let nearest = palette
+            .iter()
+            .min_by(|a, b| distance(a, &target).total_cmp(&distance(b, &target)))
+            .expect("palettes are never empty");
+        pixel[..3].copy_from_slice(nearest);
+    }
+}
+
+/// Returns the mean distance from each palette color to its nearest neighbor, per channel
+fn dither_spread(palette: &[[u8; 3]]) -> f32 {
+    if palette.len() < 2 {
+        return 0.0;
+    }
+    let total: f32 = palette
+        .iter()
+        .enumerate()
+        .map(|(index, color)| {
+            let color = color.map(f32::from);
+            palette
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(_, other)| distance(other, &color))
+                .fold(f32::INFINITY, f32::min)
+                .sqrt()
+        })
+        .sum();
+    total / palette.len() as f32 / 3f32.sqrt()
+}
+
+/// Squared Euclidean distance between a palette color and a target color
+fn distance(color: &[u8; 3], target: &[f32; 3]) -> f32 {
+    color
+        .iter()
+        .zip(target)
+        .map(|(&channel, target)| (channel as f32 - target).powi(2))
+        .sum()
+}
@@ -0,0 +1,43 @@
+/// Number of pixmaps kept for reuse before the oldest one is dropped. A render can discard
+/// a few sizes at once (the supersampled canvas, an opacity layer and the final image), so
+/// this keeps each of them around for the next job.
+const CAPACITY: usize = 4;
+
+/// Pixmaps left over from earlier renders, handed out again to renders of the same size so
+/// a batch of same-sized images doesn't allocate fresh buffers for every job
+#[derive(Default)]
+pub struct PixmapPool {
+    pixmaps: Vec<tiny_skia::Pixmap>,
+}
+
+impl PixmapPool {
+    /// Returns a transparent `width` x `height` pixmap, reusing a pooled one when its size
+    /// matches, or `None` when the size is empty or too large
+    pub fn take(&mut self, width: u32, height: u32) -> Option<tiny_skia::Pixmap> {
+        let index = self
+            .pixmaps
+            .iter()
+            .position(|pixmap| pixmap.width() == width && pixmap.height() == height);
+        match index {
+            Some(index) => {
+                let mut pixmap = self.pixmaps.remove(index);
+                pixmap.fill(tiny_skia::Color::TRANSPARENT);
+                Some(pixmap)
+            }
+            None => tiny_skia::Pixmap::new(width, height),
+        }
+    }
+
+    /// Returns a pixmap that is no longer needed to the pool
+    pub fn put(&mut self, pixmap: tiny_skia::Pixmap) {
+        if self.pixmaps.len() == CAPACITY {
+            self.pixmaps.remove(0);
+        }
+        self.pixmaps.push(pixmap);
+    }
+
+    /// Replaces `pixmap` with `next`, returning the old pixmap to the pool
+    pub fn replace(&mut self, pixmap: &mut tiny_skia::Pixmap, next: tiny_skia::Pixmap) {
+        self.put(std::mem::replace(pixmap, next));
+    }
+}
@@ -0,0 +1,95 @@
+use std::borrow::Cow;
+
+use crate::color::{parse_color, svg_color};
+use crate::converter::{
+    catch_panics, parse_svg, render_pixmap, rgba_to_grayscale, rgba_to_rgb, straight_alpha,
+    ConvertOptions,
+};
+use crate::error::ConverterError;
+use crate::palette::{parse_palette, quantize};
+
+/// Renders the SVG with `options` and returns the pixels unencoded with the image's width and
+/// height, row by row from the top-left: RGBA8 with 4 `channels`, or RGB8 with 3. RGBA is
+/// straight alpha unless `premultiplied` is set, which keeps tiny-skia's premultiplied data
+/// as rendered. RGB output is flattened onto the background color first, like other formats
+/// without alpha. The format in `options` is ignored.
+pub fn convert_svg_to_rgba(
+    svg_data: &[u8],
+    options: &ConvertOptions,
+    channels: u8,
+    premultiplied: bool,
+) -> Result<(Vec<u8>, u32, u32), ConverterError> {
+    catch_panics(|| {
+        if channels != 3 && channels != 4 {
+            return Err(ConverterError::InvalidOption(format!(
+                "Invalid channels: must be 3 or 4, got {}",
+                channels
+            )));
+        }
+
+        // RGB is flattened during the render onto the background color at full opacity, as
+        // for other formats without alpha, so every pixel is opaque by the time the alpha
+        // channel is dropped and semi-transparent ones are composited over the background
+        let background_color = if channels == 3 {
+            let color = options.background_color.as_deref().unwrap_or("FFFFFF");
+            let (r, g, b, _) = parse_color(color).map_err(ConverterError::InvalidColor)?;
+            Some(svg_color((r, g, b, u8::MAX)))
+        } else {
+            options.background_color.clone()
+        };
+        let options = ConvertOptions {
+            format: "png".to_string(),
+            flatten: options.flatten || channels == 3,
+            background_color,
+            ..options.clone()
+        };
+        let parsed = parse_svg(svg_data, &options)?;
+        let pixmap = render_pixmap(&parsed, &options)?;
+        let palette = parse_palette(&options.palette)?;
+
+        // Luminance is a weighted sum of the channels, so it's the same computed on either
+        // alpha representation. Palette colors are matched against straight colors.
+        let data = if premultiplied && channels == 4 && palette.is_none() {
+            Cow::Borrowed(pixmap.data())
+        } else {
+            straight_alpha(pixmap.data())
+        };
+        let data = if options.grayscale {
+            Cow::Owned(rgba_to_grayscale(&data))
+        } else {
+            data
+        };
+        let data = match palette {
+            Some(palette) => {
+                let mut quantized = data.into_owned();
+                quantize(
+                    &mut quantized,
+                    pixmap.width() as usize,
+                    0,
+                    &palette,
+                    options.palette_dither,
+                );
+                if premultiplied && channels == 4 {
+                    premultiply(&mut quantized);
+                }
+                Cow::Owned(quantized)
+            }
+            None => data,
+        };
+        let data = if channels == 3 {
+            rgba_to_rgb(&data)
+        } else {
+            data.into_owned()
+        };
+
+        Ok((data, pixmap.width(), pixmap.height()))
+    })
+}
+
+fn premultiply(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        let color = tiny_skia::ColorU8::from_rgba(pixel[0], pixel[1], pixel[2], pixel[3]);
+        let color = color.premultiply();
+        pixel.copy_from_slice(&[color.red(), color.green(), color.blue(), color.alpha()]);
+    }
+}
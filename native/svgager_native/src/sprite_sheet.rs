@@ -0,0 +1,115 @@
+use crate::converter::{
+    background_color, catch_panics, check_pixel_limit, encode_pixmap, parse_svg, render_pixmap,
+    ConvertOptions, Dimension,
+};
+use crate::error::ConverterError;
+
+/// The `(x, y, width, height)` of a sprite on the sheet, in pixels
+pub type SpriteRect = (u32, u32, u32, u32);
+
+/// A rendered sprite sheet and the position of each sprite, in input order
+pub struct SpriteSheet {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub sprites: Vec<SpriteRect>,
+}
+
+/// Renders every SVG into its own `cell_width` x `cell_height` cell of a grid with `columns`
+/// columns and `gutter` pixels between cells, filled left to right and top to bottom, and
+/// encodes the sheet as a PNG. The gutters are transparent unless `options` flattens the
+/// output onto its background color; the format and size in `options` are ignored.
+pub fn convert_svgs_to_sprite_sheet(
+    svgs: &[&[u8]],
+    options: &ConvertOptions,
+    columns: u32,
+    (cell_width, cell_height): (u32, u32),
+    gutter: u32,
+) -> Result<SpriteSheet, ConverterError> {
+    catch_panics(|| {
+        if svgs.is_empty() {
+            return Err(ConverterError::InvalidOption(
+                "Invalid sprites: at least one SVG is required".to_string(),
+            ));
+        }
+        if columns == 0 || cell_width == 0 || cell_height == 0 {
+            return Err(ConverterError::InvalidDimensions(format!(
+                "Invalid sprite sheet: columns and cell size must be greater than 0, got {} \
+                 columns of {}x{}",
+                columns, cell_width, cell_height
+            )));
+        }
+
+        let options = ConvertOptions {
+            format: "png".to_string(),
+            width: Some(Dimension::Pixels(cell_width)),
+            height: Some(Dimension::Pixels(cell_height)),
+            scale: None,
+            ..options.clone()
+        };
+        let count = svgs.len() as u32;
+        let columns = columns.min(count);
+        let rows = count.div_ceil(columns);
+        let sheet_size = |cells: u32, cell: u32| {
+            cells
+                .checked_mul(cell)?
+                .checked_add((cells - 1).checked_mul(gutter)?)
+        };
+        let size = sheet_size(columns, cell_width).zip(sheet_size(rows, cell_height));
+        if let Some((width, height)) = size {
+            check_pixel_limit(width as u64, height as u64, &options)?;
+        }
+        let mut sheet = size
+            .and_then(|(width, height)| tiny_skia::Pixmap::new(width, height))
+            .ok_or_else(|| {
+                ConverterError::InvalidDimensions(format!(
+                    "Invalid sprite sheet: {}x{} cells of {}x{} with a {} pixel gutter is too \
+                     large",
+                    columns, rows, cell_width, cell_height, gutter
+                ))
+            })?;
+        if let Some(color) = background_color(&options)? {
+            sheet.fill(color);
+        }
+
+        let mut sprites = Vec::with_capacity(svgs.len());
+        for (index, svg_data) in svgs.iter().enumerate() {
+            let parsed = parse_svg(svg_data, &options)?;
+            let sprite = render_pixmap(&parsed, &options)?;
+            if sprite.width() > cell_width || sprite.height() > cell_height {
+                return Err(ConverterError::InvalidDimensions(format!(
+                    "Invalid sprite sheet: sprite {} is {}x{}, which doesn't fit a {}x{} cell",
+                    index,
+                    sprite.width(),
+                    sprite.height(),
+                    cell_width,
+                    cell_height
+                )));
+            }
+
+            let (column, row) = (index as u32 % columns, index as u32 / columns);
+            let x = column * (cell_width + gutter);
+            let y = row * (cell_height + gutter);
+            sheet.draw_pixmap(
+                x as i32,
+                y as i32,
+                sprite.as_ref(),
+                &tiny_skia::PixmapPaint {
+                    blend_mode: tiny_skia::BlendMode::Source,
+                    ..tiny_skia::PixmapPaint::default()
+                },
+                tiny_skia::Transform::identity(),
+                None,
+            );
+            sprites.push((x, y, sprite.width(), sprite.height()));
+        }
+
+        let image = encode_pixmap(&sheet, &options)?;
+        Ok(SpriteSheet {
+            data: image.data,
+            width: image.width,
+            height: image.height,
+            sprites,
+        })
+    })
+}
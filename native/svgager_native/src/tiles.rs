@@ -0,0 +1,68 @@
+use crate::converter::{
+    background_color, catch_panics, check_pixel_limit, encode_pixmap, parse_svg, render_pixmap,
+    ConvertOptions,
+};
+use crate::error::ConverterError;
+
+/// A tile's `(column, row)` in the grid, counted from the top-left tile
+pub type TileKey = (u32, u32);
+
+/// Renders the SVG once with `options` and slices the image into `tile_width` x
+/// `tile_height` PNG tiles, returned row by row from the top-left. Tiles along the right and
+/// bottom edges that stick out of the image are padded with the background color, which is
+/// transparent unless `options` flattens the output; the format in `options` is ignored.
+pub fn convert_svg_to_tiles(
+    svg_data: &[u8],
+    options: &ConvertOptions,
+    (tile_width, tile_height): (u32, u32),
+) -> Result<Vec<(TileKey, Vec<u8>)>, ConverterError> {
+    catch_panics(|| {
+        if tile_width == 0 || tile_height == 0 {
+            return Err(ConverterError::InvalidDimensions(format!(
+                "Invalid tile size: must be greater than 0, got {}x{}",
+                tile_width, tile_height
+            )));
+        }
+
+        let options = ConvertOptions {
+            format: "png".to_string(),
+            ..options.clone()
+        };
+        let parsed = parse_svg(svg_data, &options)?;
+        let image = render_pixmap(&parsed, &options)?;
+        let background = background_color(&options)?.unwrap_or(tiny_skia::Color::TRANSPARENT);
+
+        check_pixel_limit(tile_width as u64, tile_height as u64, &options)?;
+        let columns = image.width().div_ceil(tile_width);
+        let rows = image.height().div_ceil(tile_height);
+        let mut tile = tiny_skia::Pixmap::new(tile_width, tile_height).ok_or_else(|| {
+            ConverterError::InvalidDimensions(format!(
+                "Invalid tile size: {}x{} is too large",
+                tile_width, tile_height
+            ))
+        })?;
+
+        let mut tiles = Vec::with_capacity((columns * rows) as usize);
+        for row in 0..rows {
+            for column in 0..columns {
+                // Only edge tiles extend past the image, but every tile is cleared so nothing
+                // of the previous one is left behind
+                tile.fill(background);
+                tile.draw_pixmap(
+                    -((column * tile_width) as i32),
+                    -((row * tile_height) as i32),
+                    image.as_ref(),
+                    &tiny_skia::PixmapPaint {
+                        blend_mode: tiny_skia::BlendMode::Source,
+                        ..tiny_skia::PixmapPaint::default()
+                    },
+                    tiny_skia::Transform::identity(),
+                    None,
+                );
+                tiles.push(((column, row), encode_pixmap(&tile, &options)?.data));
+            }
+        }
+
+        Ok(tiles)
+    })
+}
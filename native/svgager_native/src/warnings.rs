@@ -0,0 +1,255 @@
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use usvg::fontdb;
+
+use crate::converter::parse_document;
+
+/// Returns a warning for each `url(#id)` or `#id` link in the SVG whose target doesn't exist,
+/// in document order. usvg silently drops such references, leaving for example a shape
+/// without its gradient or filter.
+pub fn unresolved_references(svg: &str) -> Vec<String> {
+    // Most SVGs don't link to other elements at all, which is cheap to rule out
+    if !svg.contains("url(") && !svg.contains("href") {
+        return Vec::new();
+    }
+    let Some(doc) = parse_document(svg) else {
+        return Vec::new();
+    };
+
+    let ids: std::collections::HashSet<&str> = doc
+        .descendants()
+        .filter_map(|node| node.attribute("id"))
+        .collect();
+
+    let mut references = Vec::new();
+    for node in doc.descendants() {
+        if node.is_text()
+            && node
+                .parent_element()
+                .is_some_and(|p| p.has_tag_name("style"))
+        {
+            references.extend(url_references(node.text().unwrap_or_default()));
+        }
+        for attribute in node.attributes() {
+            if attribute.name() == "href" {
+                references.extend(attribute.value().strip_prefix('#'));
+            } else {
+                references.extend(url_references(attribute.value()));
+            }
+        }
+    }
+
+    let mut warnings = Vec::new();
+    for id in references.into_iter().filter(|id| !ids.contains(id)) {
+        push_unique(
+            &mut warnings,
+            format!(
+                "Unresolved reference to \"#{}\": no element has that id",
+                id
+            ),
+        );
+    }
+    warnings
+}
+
+/// Returns the ids referenced by `url(#id)` functions in a presentation attribute, style
+/// attribute or style sheet, with or without quotes around the reference
+fn url_references(value: &str) -> impl Iterator<Item = &str> {
+    value.split("url(").skip(1).filter_map(|rest| {
+        let reference = rest.split(')').next()?.trim();
+        let reference = reference.trim_matches(|c| c == '"' || c == '\'');
+        reference.strip_prefix('#').filter(|id| !id.is_empty())
+    })
+}
+
+/// Wraps usvg's default font selection to record a warning whenever none of a text span's
+/// font families is available, whether usvg then falls back to another font or has no font
+/// left and drops the text
+pub fn font_resolver(warnings: &Mutex<Vec<String>>) -> usvg::FontResolver<'_> {
+    let select_font = usvg::FontResolver::default_font_selector();
+    usvg::FontResolver {
+        select_font: Box::new(move |font, fontdb| {
+            let id = select_font(font, fontdb);
+
+            let available = font.families().iter().any(|family| {
+                let families = [fontdb_family(family)];
+                fontdb
+                    .query(&fontdb::Query {
+                        families: &families,
+                        ..fontdb::Query::default()
+                    })
+                    .is_some()
+            });
+            if !available {
+                let families: Vec<String> =
+                    font.families().iter().map(ToString::to_string).collect();
+                let outcome = match id {
+                    Some(_) => "a fallback font was used",
+                    None => "the text was not rendered",
+                };
+                push_unique(
+                    &mut warnings.lock().unwrap_or_else(PoisonError::into_inner),
+                    format!(
+                        "No font found for font-family {}; {}",
+                        families.join(", "),
+                        outcome
+                    ),
+                );
+            }
+
+            id
+        }),
+        ..usvg::FontResolver::default()
+    }
+}
+
+/// Wraps usvg's `<image>` href handling so raster images are recognized by their content
+/// rather than a data URL's declared MIME type, which is often generic or wrong, and records
+/// a warning for each image that is dropped because it can't be loaded or decoded. Raster
+/// images are decoded once here to check them, since the renderer would otherwise skip a
+/// broken one without saying so.
+///
+/// With a `resource_dir`, which must be canonical, other hrefs are paths relative to it, and
+/// any that resolve outside of it are refused. Without one, usvg's default file loading
+/// applies.
+pub fn image_href_resolver<'a>(
+    warnings: &'a Mutex<Vec<String>>,
+    resource_dir: Option<&'a Path>,
+) -> usvg::ImageHrefResolver<'a> {
+    let resolve_data = usvg::ImageHrefResolver::default_data_resolver();
+    let resolve_string: usvg::ImageHrefStringResolverFn = match resource_dir {
+        Some(dir) => {
+            let resolve_data = usvg::ImageHrefResolver::default_data_resolver();
+            Box::new(move |href, opts| {
+                let image = format!("Image {:?}", href);
+                let data = resource_path(dir, href).and_then(|path| {
+                    std::fs::read(&path)
+                        .map(|data| (path, data))
+                        .map_err(|_| "could not be read")
+                });
+                let (path, data) = match data {
+                    Ok(loaded) => loaded,
+                    Err(problem) => {
+                        push_image_warning(warnings, format!("{} {}", image, problem));
+                        return None;
+                    }
+                };
+                let is_svg = path.extension().is_some_and(|ext| {
+                    ext.eq_ignore_ascii_case("svg") || ext.eq_ignore_ascii_case("svgz")
+                });
+                // Without an SVG extension, usvg looks for raster magic bytes and then tries SVG
+                let mime = if is_svg {
+                    "image/svg+xml"
+                } else {
+                    "text/plain"
+                };
+                checked_image(&image, mime, Arc::new(data), opts, &resolve_data, warnings)
+            })
+        }
+        None => usvg::ImageHrefResolver::default_string_resolver(),
+    };
+
+    usvg::ImageHrefResolver {
+        resolve_data: Box::new(move |mime, data, opts| {
+            checked_image("Embedded image", mime, data, opts, &resolve_data, warnings)
+        }),
+        resolve_string,
+    }
+}
+
+/// Returns the image data as the kind its content is, or `None` with a warning about `image`
+/// when it is neither a raster image that decodes nor an SVG that loads
+fn checked_image(
+    image: &str,
+    mime: &str,
+    data: Arc<Vec<u8>>,
+    opts: &usvg::Options,
+    resolve_data: &usvg::ImageHrefDataResolverFn,
+    warnings: &Mutex<Vec<String>>,
+) -> Option<usvg::ImageKind> {
+    let kind = match image::guess_format(&data) {
+        Ok(image::ImageFormat::Png) => Some(usvg::ImageKind::PNG(data)),
+        Ok(image::ImageFormat::Jpeg) => Some(usvg::ImageKind::JPEG(data)),
+        Ok(image::ImageFormat::Gif) => Some(usvg::ImageKind::GIF(data)),
+        Ok(image::ImageFormat::WebP) => Some(usvg::ImageKind::WEBP(data)),
+        // Anything else may still be an SVG image, which usvg parses itself
+        _ => resolve_data(mime, data, opts),
+    };
+    if kind.as_ref().is_some_and(decodes) {
+        return kind;
+    }
+
+    let format = match &kind {
+        Some(usvg::ImageKind::PNG(_)) => "PNG",
+        Some(usvg::ImageKind::JPEG(_)) => "JPEG",
+        Some(usvg::ImageKind::GIF(_)) => "GIF",
+        Some(usvg::ImageKind::WEBP(_)) => "WebP",
+        _ => mime,
+    };
+    push_image_warning(
+        warnings,
+        format!("{} ({}) could not be decoded", image, format),
+    );
+    None
+}
+
+/// Resolves an image href against the canonical `dir`, refusing anything that ends up
+/// outside of it, whether through an absolute path, `..` components or a symlink
+fn resource_path(dir: &Path, href: &str) -> Result<PathBuf, &'static str> {
+    let relative = Path::new(href);
+    if relative.is_absolute() {
+        return Err("is an absolute path, which resource_dir doesn't allow");
+    }
+    let path = dir
+        .join(relative)
+        .canonicalize()
+        .map_err(|_| "was not found in resource_dir")?;
+    if !path.starts_with(dir) {
+        return Err("is outside of resource_dir");
+    }
+    Ok(path)
+}
+
+fn push_image_warning(warnings: &Mutex<Vec<String>>, problem: String) {
+    push_unique(
+        &mut warnings.lock().unwrap_or_else(PoisonError::into_inner),
+        format!("{}; it was not rendered", problem),
+    );
+}
+
+// The renderer decodes JPEG with zune-jpeg and the other formats with the same decoders
+// `image` uses, so an image that decodes here also renders
+fn decodes(kind: &usvg::ImageKind) -> bool {
+    let (data, format) = match kind {
+        usvg::ImageKind::JPEG(data) => {
+            return zune_jpeg::JpegDecoder::new(Cursor::new(data.as_slice()))
+                .decode()
+                .is_ok();
+        }
+        usvg::ImageKind::PNG(data) => (data, image::ImageFormat::Png),
+        usvg::ImageKind::GIF(data) => (data, image::ImageFormat::Gif),
+        usvg::ImageKind::WEBP(data) => (data, image::ImageFormat::WebP),
+        usvg::ImageKind::SVG(_) => return true,
+    };
+    image::load_from_memory_with_format(data, format).is_ok()
+}
+
+fn fontdb_family(family: &usvg::FontFamily) -> fontdb::Family<'_> {
+    match family {
+        usvg::FontFamily::Serif => fontdb::Family::Serif,
+        usvg::FontFamily::SansSerif => fontdb::Family::SansSerif,
+        usvg::FontFamily::Cursive => fontdb::Family::Cursive,
+        usvg::FontFamily::Fantasy => fontdb::Family::Fantasy,
+        usvg::FontFamily::Monospace => fontdb::Family::Monospace,
+        usvg::FontFamily::Named(name) => fontdb::Family::Name(name),
+    }
+}
+
+// Each text span resolves its font separately, so the same problem is reported only once
+fn push_unique(warnings: &mut Vec<String>, warning: String) {
+    if !warnings.contains(&warning) {
+        warnings.push(warning);
+    }
+}
@@ -42,6 +42,50 @@ defmodule Svgager.TestHelper do
     """
   end
 
+  @doc """
+  Returns an SVG with unstyled text, which renders with the default font family and size.
+  """
+  def text_svg do
+    """
+    <svg xmlns="http://www.w3.org/2000/svg" width="200" height="50" viewBox="0 0 200 50">
+      <text x="10" y="35">Svgager</text>
+    </svg>
+    """
+  end
+
+  @doc """
+  Returns the directory of fonts bundled for tests, which contains DejaVu Sans Mono.
+  """
+  def fonts_dir do
+    Path.expand("../fixtures/fonts", __DIR__)
+  end
+
+  @doc """
+  Returns a sprite sheet SVG with two identifiable icons over a full-size background.
+  """
+  def sprite_svg do
+    """
+    <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100" viewBox="0 0 100 100">
+      <rect id="background" width="100" height="100" fill="#0000FF" />
+      <rect id="red-icon" x="10" y="10" width="20" height="10" fill="#FF0000" />
+      <g transform="translate(50 50)">
+        <circle id="green-icon" cx="15" cy="15" r="15" fill="#00FF00" />
+      </g>
+    </svg>
+    """
+  end
+
+  @doc """
+  Returns a square SVG of the given size filled with a single color.
+  """
+  def solid_svg(color, size \\ 10) do
+    """
+    <svg xmlns="http://www.w3.org/2000/svg" width="#{size}" height="#{size}">
+      <rect width="#{size}" height="#{size}" fill="#{color}"/>
+    </svg>
+    """
+  end
+
   @doc """
   Returns an invalid SVG string for error testing.
   """
@@ -163,6 +207,65 @@ defmodule Svgager.TestHelper do
 
   def valid_webp?(_), do: false
 
+  @doc """
+  Checks if binary data appears to be a valid BMP by checking magic bytes and declared file size.
+  """
+  def valid_bmp?(<<"BM", file_size::32-little, _rest::binary>> = data) do
+    file_size == byte_size(data)
+  end
+
+  def valid_bmp?(_), do: false
+
+  @doc """
+  Checks if binary data appears to be a valid little-endian TIFF by checking the header.
+  """
+  def valid_tiff?(<<"II", 42::16-little, ifd_offset::32-little, _rest::binary>> = data) do
+    ifd_offset < byte_size(data)
+  end
+
+  def valid_tiff?(_), do: false
+
+  @doc """
+  Checks if binary data appears to be a valid QOI image by checking the header.
+  """
+  def valid_qoi?(<<"qoif", width::32, height::32, channels, colorspace, _rest::binary>>) do
+    width > 0 and height > 0 and channels in [3, 4] and colorspace in [0, 1]
+  end
+
+  def valid_qoi?(_), do: false
+
+  @doc """
+  Checks if binary data appears to be a valid true-color TGA by checking the header.
+  """
+  def valid_tga?(<<_id_length, 0, image_type, _color_map::binary-size(5), _rest::binary>>),
+    do: image_type in [2, 10]
+
+  def valid_tga?(_), do: false
+
+  @doc """
+  Checks if binary data appears to be a valid binary PPM by checking the header.
+  """
+  def valid_ppm?(data), do: get_ppm_dimensions(data) != nil
+
+  @doc """
+  Checks if binary data appears to be a valid AVIF by checking the `ftyp` box brand.
+  """
+  def valid_avif?(<<_size::32, "ftyp", brand::binary-size(4), _rest::binary>>) do
+    brand in ["avif", "avis"]
+  end
+
+  def valid_avif?(_), do: false
+
+  @doc """
+  Checks if binary data appears to be a valid farbfeld image by checking the magic bytes.
+  """
+  def valid_farbfeld?(data), do: get_farbfeld_dimensions(data) != nil
+
+  @doc """
+  Checks if binary data appears to be a valid OpenEXR image by checking the header.
+  """
+  def valid_exr?(data), do: exr_header(data) != nil
+
   @doc """
   Validates that binary data matches the expected format.
   """
@@ -171,6 +274,17 @@ defmodule Svgager.TestHelper do
   def valid_format?(data, :jpeg), do: valid_jpeg?(data)
   def valid_format?(data, :gif), do: valid_gif?(data)
   def valid_format?(data, :webp), do: valid_webp?(data)
+  def valid_format?(data, :bmp), do: valid_bmp?(data)
+  def valid_format?(data, :tiff), do: valid_tiff?(data)
+  def valid_format?(data, :tif), do: valid_tiff?(data)
+  def valid_format?(data, :qoi), do: valid_qoi?(data)
+  def valid_format?(data, :avif), do: valid_avif?(data)
+  def valid_format?(data, :tga), do: valid_tga?(data)
+  def valid_format?(data, :pnm), do: valid_ppm?(data)
+  def valid_format?(data, :ppm), do: valid_ppm?(data)
+  def valid_format?(data, :ff), do: valid_farbfeld?(data)
+  def valid_format?(data, :farbfeld), do: valid_farbfeld?(data)
+  def valid_format?(data, :exr), do: valid_exr?(data)
 
   @doc """
   Extracts dimensions from PNG image data.
@@ -185,24 +299,303 @@ defmodule Svgager.TestHelper do
 
   def get_png_dimensions(_), do: nil
 
+  @doc """
+  Returns the bit depth from the IHDR chunk of PNG image data, or nil if parsing fails.
+  """
+  def png_bit_depth(
+        <<0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, _length::32, "IHDR", _width::32,
+          _height::32, bit_depth, _rest::binary>>
+      ),
+      do: bit_depth
+
+  def png_bit_depth(_), do: nil
+
+  @doc """
+  Returns the `{x, y, unit}` pixel density from the pHYs chunk of PNG image data, where `unit`
+  is 1 for pixels per meter and 0 when only the aspect ratio is known, or nil without one.
+  """
+  def png_physical_dimensions(
+        <<0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, chunks::binary>>
+      ),
+      do: find_png_phys(chunks)
+
+  defp find_png_phys(<<9::32, "pHYs", x::32, y::32, unit, _crc::32, _rest::binary>>),
+    do: {x, y, unit}
+
+  defp find_png_phys(<<_length::32, "IEND", _rest::binary>>), do: nil
+
+  defp find_png_phys(
+         <<length::32, _type::binary-size(4), _data::binary-size(length), _crc::32, rest::binary>>
+       ),
+       do: find_png_phys(rest)
+
+  @doc """
+  Returns the decompressed ICC profile from the iCCP chunk of PNG image data, or nil
+  without one.
+  """
+  def png_icc_profile(<<0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, chunks::binary>>) do
+    with data when is_binary(data) <- find_png_chunk(chunks, "iCCP") do
+      [_name, <<0, compressed::binary>>] = :binary.split(data, <<0>>)
+      :zlib.uncompress(compressed)
+    end
+  end
+
+  @doc """
+  Returns true when PNG image data has an sRGB chunk.
+  """
+  def png_srgb?(<<0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, chunks::binary>>),
+    do: find_png_chunk(chunks, "sRGB") != nil
+
+  defp find_png_chunk(
+         <<length::32, type::binary-size(4), data::binary-size(length), _crc::32, rest::binary>>,
+         wanted
+       ) do
+    cond do
+      type == wanted -> data
+      type == "IEND" -> nil
+      true -> find_png_chunk(rest, wanted)
+    end
+  end
+
+  @doc """
+  Returns the text chunks of PNG image data in order, as `{type, keyword, text}` tuples
+  where `type` is `"tEXt"` or `"iTXt"` and the text is decoded to UTF-8. Compressed iTXt
+  text isn't supported.
+  """
+  def png_text_chunks(<<0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, chunks::binary>>),
+    do: read_png_text_chunks(chunks, [])
+
+  defp read_png_text_chunks(
+         <<length::32, type::binary-size(4), data::binary-size(length), _crc::32, rest::binary>>,
+         acc
+       ) do
+    case type do
+      "tEXt" ->
+        [keyword, text] = :binary.split(data, <<0>>)
+        text = :unicode.characters_to_binary(text, :latin1)
+        read_png_text_chunks(rest, [{type, keyword, text} | acc])
+
+      "iTXt" ->
+        [keyword, <<0, _method, rest_of_chunk::binary>>] = :binary.split(data, <<0>>)
+        [_language, _translated_keyword, text] = :binary.split(rest_of_chunk, <<0>>, [:global])
+        read_png_text_chunks(rest, [{type, keyword, text} | acc])
+
+      "IEND" ->
+        Enum.reverse(acc)
+
+      _ ->
+        read_png_text_chunks(rest, acc)
+    end
+  end
+
+  @doc """
+  Decodes an 8-bit, non-interlaced RGB or RGBA PNG.
+
+  Returns `{width, height, pixels}` where `pixels` is RGBA8 data (RGB images get an opaque
+  alpha channel). This covers everything the NIF's PNG encoder produces.
+  """
+  def decode_png(<<0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, chunks::binary>>) do
+    {{width, height, color_type}, idat} = read_png_chunks(chunks, nil, [])
+
+    channels =
+      case color_type do
+        2 -> 3
+        6 -> 4
+      end
+
+    stride = width * channels
+
+    pixels =
+      idat
+      |> IO.iodata_to_binary()
+      |> :zlib.uncompress()
+      |> unfilter_png(stride, channels, :binary.copy(<<0>>, stride), [])
+
+    pixels =
+      if channels == 3,
+        do: for(<<r, g, b <- pixels>>, into: <<>>, do: <<r, g, b, 255>>),
+        else: pixels
+
+    {width, height, pixels}
+  end
+
+  @doc """
+  Returns true if every pixel of an image decoded by `decode_png/1` has zero alpha.
+  """
+  def fully_transparent?({_width, _height, pixels}) do
+    for(<<_r, _g, _b, a <- pixels>>, uniq: true, do: a) == [0]
+  end
+
+  @doc """
+  Returns the sorted distinct values of one channel (`:red`, `:green`, `:blue` or `:alpha`)
+  across every pixel of PNG image data.
+  """
+  def channel_values(png_data, channel) do
+    {_width, _height, pixels} = decode_png(png_data)
+    offset = Enum.find_index([:red, :green, :blue, :alpha], &(&1 == channel))
+
+    for(<<pixel::binary-size(4) <- pixels>>, uniq: true, do: :binary.at(pixel, offset))
+    |> Enum.sort()
+  end
+
+  @doc """
+  Returns the `{r, g, b, a}` pixel at `{x, y}` of an image decoded by `decode_png/1`.
+  """
+  def pixel_at({width, _height, pixels}, x, y) do
+    offset = (y * width + x) * 4
+    <<_::binary-size(offset), r, g, b, a, _rest::binary>> = pixels
+    {r, g, b, a}
+  end
+
+  defp read_png_chunks(
+         <<length::32, type::binary-size(4), data::binary-size(length), _crc::32, rest::binary>>,
+         header,
+         idat
+       ) do
+    case type do
+      "IHDR" ->
+        <<width::32, height::32, 8, color_type, _compression, _filter, 0>> = data
+        read_png_chunks(rest, {width, height, color_type}, idat)
+
+      "IDAT" ->
+        read_png_chunks(rest, header, [idat, data])
+
+      "IEND" ->
+        {header, idat}
+
+      _ ->
+        read_png_chunks(rest, header, idat)
+    end
+  end
+
+  defp unfilter_png(<<>>, _stride, _bpp, _previous, rows),
+    do: rows |> Enum.reverse() |> IO.iodata_to_binary()
+
+  defp unfilter_png(data, stride, bpp, previous, rows) do
+    <<filter, line::binary-size(stride), rest::binary>> = data
+    row = unfilter_row(filter, line, previous, bpp)
+    unfilter_png(rest, stride, bpp, row, [row | rows])
+  end
+
+  # Reverses a PNG scanline filter; `a` is the byte to the left, `b` the byte above and
+  # `c` the byte above-left of the current byte
+  defp unfilter_row(filter, line, previous, bpp) do
+    above = :binary.bin_to_list(previous)
+    above_left = List.duplicate(0, bpp) ++ above
+
+    line
+    |> :binary.bin_to_list()
+    |> Enum.zip(Enum.zip(above, above_left))
+    |> Enum.reduce([], fn {x, {b, c}}, acc ->
+      a = Enum.at(acc, bpp - 1, 0)
+      [band(x + png_predict(filter, a, b, c), 0xFF) | acc]
+    end)
+    |> Enum.reverse()
+    |> :binary.list_to_bin()
+  end
+
+  defp png_predict(0, _a, _b, _c), do: 0
+  defp png_predict(1, a, _b, _c), do: a
+  defp png_predict(2, _a, b, _c), do: b
+  defp png_predict(3, a, b, _c), do: div(a + b, 2)
+
+  defp png_predict(4, a, b, c) do
+    p = a + b - c
+    pa = abs(p - a)
+    pb = abs(p - b)
+    pc = abs(p - c)
+
+    cond do
+      pa <= pb and pa <= pc -> a
+      pb <= pc -> b
+      true -> c
+    end
+  end
+
   @doc """
   Extracts dimensions from JPEG image data.
   Returns {width, height} or nil if parsing fails.
   """
   def get_jpeg_dimensions(data) do
-    case parse_jpeg_dimensions(data) do
-      {width, height} when is_integer(width) and is_integer(height) -> {width, height}
+    case parse_jpeg_sof(data) do
+      {_marker, width, height, _components, _sampling}
+      when is_integer(width) and is_integer(height) ->
+        {width, height}
+
+      _ ->
+        nil
+    end
+  end
+
+  @doc """
+  Returns the number of color components in JPEG image data: 1 for grayscale, 3 for color.
+  Returns nil if parsing fails.
+  """
+  def jpeg_components(data) do
+    case parse_jpeg_sof(data) do
+      {_marker, _width, _height, components, _sampling} -> components
       _ -> nil
     end
   end
 
-  defp parse_jpeg_dimensions(<<0xFF, 0xD8, rest::binary>>), do: find_jpeg_sof(rest)
-  defp parse_jpeg_dimensions(_), do: nil
+  @doc """
+  Returns true when JPEG image data is progressive, i.e. its frame header is SOF2 rather
+  than the baseline SOF0.
+  """
+  def jpeg_progressive?(data) do
+    match?({0xC2, _width, _height, _components, _sampling}, parse_jpeg_sof(data))
+  end
+
+  @doc """
+  Returns the chroma subsampling of color JPEG image data as a string such as "4:2:0",
+  derived from the luma component's sampling factors. Returns nil if parsing fails.
+  """
+  def jpeg_subsampling(data) do
+    case parse_jpeg_sof(data) do
+      {_marker, _width, _height, 3, {1, 1}} -> "4:4:4"
+      {_marker, _width, _height, 3, {2, 1}} -> "4:2:2"
+      {_marker, _width, _height, 3, {2, 2}} -> "4:2:0"
+      _ -> nil
+    end
+  end
+
+  @doc """
+  Returns the ICC profile embedded in the APP2 segments of JPEG image data, reassembled from
+  its chunks, or nil without one.
+  """
+  def jpeg_icc_profile(<<0xFF, 0xD8, rest::binary>>) do
+    case read_jpeg_icc_chunks(rest, []) do
+      [] -> nil
+      chunks -> chunks |> Enum.sort() |> Enum.map_join(&elem(&1, 1))
+    end
+  end
+
+  defp read_jpeg_icc_chunks(<<0xFF, 0xDA, _rest::binary>>, acc), do: acc
+
+  defp read_jpeg_icc_chunks(<<0xFF, marker, length::16, rest::binary>>, acc) do
+    payload_length = length - 2
+    <<payload::binary-size(payload_length), rest::binary>> = rest
+
+    case {marker, payload} do
+      {0xE2, <<"ICC_PROFILE", 0, sequence, _count, data::binary>>} ->
+        read_jpeg_icc_chunks(rest, [{sequence, data} | acc])
+
+      _ ->
+        read_jpeg_icc_chunks(rest, acc)
+    end
+  end
+
+  defp read_jpeg_icc_chunks(_data, acc), do: acc
+
+  defp parse_jpeg_sof(<<0xFF, 0xD8, rest::binary>>), do: find_jpeg_sof(rest)
+  defp parse_jpeg_sof(_), do: nil
 
   defp find_jpeg_sof(<<0xFF, marker, rest::binary>>) when marker in [0xC0, 0xC2] do
     # SOF0 or SOF2 marker
-    <<_length::16, _precision::8, height::16, width::16, _rest::binary>> = rest
-    {width, height}
+    <<_length::16, _precision::8, height::16, width::16, components::8, _id::8, h::4, v::4,
+      _rest::binary>> = rest
+
+    {marker, width, height, components, {h, v}}
   end
 
   defp find_jpeg_sof(<<0xFF, _marker, length::16, rest::binary>>) do
@@ -265,6 +658,285 @@ defmodule Svgager.TestHelper do
 
   defp parse_webp_chunk(_), do: nil
 
+  @doc """
+  Returns the delay of every frame of GIF data in milliseconds, read from the graphic
+  control extensions.
+  """
+  def gif_frame_delays(data) do
+    for {offset, _length} <- :binary.matches(data, <<0x21, 0xF9, 0x04>>),
+        <<_::binary-size(offset + 4), delay::16-little, _transparent, 0, _::binary>> <- [data] do
+      delay * 10
+    end
+  end
+
+  @doc """
+  Returns the transparent color index from the first graphic control extension of GIF data,
+  or nil when the GIF has no transparent color.
+  """
+  def gif_transparent_index(data) do
+    with {offset, _length} <- :binary.match(data, <<0x21, 0xF9, 0x04>>),
+         <<_::binary-size(offset + 3), _::7, 1::1, _delay::16, index, _::binary>> <- data do
+      index
+    else
+      _ -> nil
+    end
+  end
+
+  @doc """
+  Returns the loop count stored in GIF data's NETSCAPE2.0 extension, where 0 loops forever,
+  or nil when the GIF has no loop extension.
+  """
+  def gif_loop_count(data) do
+    case :binary.match(data, "NETSCAPE2.0") do
+      {offset, length} ->
+        <<_::binary-size(offset + length), 3, 1, count::16-little, _::binary>> = data
+        count
+
+      :nomatch ->
+        nil
+    end
+  end
+
+  @doc """
+  Extracts dimensions from BMP image data.
+  Returns {width, height} or nil if parsing fails.
+  """
+  def get_bmp_dimensions(
+        <<"BM", _::binary-size(12), _dib_size::32-little, width::32-little-signed,
+          height::32-little-signed, _rest::binary>>
+      ) do
+    # Height is negative for top-down bitmaps
+    {width, abs(height)}
+  end
+
+  def get_bmp_dimensions(_), do: nil
+
+  @doc """
+  Parses the directory of ICO data.
+  Returns a list of {width, height, image_data} entries, or nil if parsing fails.
+  """
+  def ico_entries(<<0::16-little, 1::16-little, count::16-little, rest::binary>> = data) do
+    {directory, _images} = :erlang.split_binary(rest, count * 16)
+
+    for <<width, height, _colors, _reserved, _planes::16-little, _bpp::16-little,
+          size::32-little, offset::32-little <- directory>> do
+      # A stored size of 0 means 256
+      {if(width == 0, do: 256, else: width), if(height == 0, do: 256, else: height),
+       binary_part(data, offset, size)}
+    end
+  end
+
+  def ico_entries(_), do: nil
+
+  @doc """
+  Extracts dimensions from little-endian TIFF image data.
+  Returns {width, height} or nil if parsing fails.
+  """
+  def get_tiff_dimensions(<<"II", 42::16-little, ifd_offset::32-little, _rest::binary>> = data) do
+    tags = read_tiff_tags(data, ifd_offset)
+    {Map.get(tags, 256), Map.get(tags, 257)}
+  end
+
+  def get_tiff_dimensions(_), do: nil
+
+  @doc """
+  Returns the first RGBA pixel of an uncompressed, single-strip TIFF as {r, g, b, a}.
+  """
+  def tiff_first_pixel(<<"II", 42::16-little, ifd_offset::32-little, _rest::binary>> = data) do
+    # StripOffsets (tag 273) points at the first byte of pixel data
+    strip_offset = data |> read_tiff_tags(ifd_offset) |> Map.fetch!(273)
+    <<_::binary-size(strip_offset), r, g, b, a, _::binary>> = data
+    {r, g, b, a}
+  end
+
+  defp read_tiff_tags(data, ifd_offset) do
+    <<_::binary-size(ifd_offset), count::16-little, entries::binary-size(count * 12),
+      _rest::binary>> = data
+
+    # Every value we read fits in the 4-byte value field, so offsets to arrays are never followed
+    for <<tag::16-little, _type::16-little, _count::32-little, value::32-little <- entries>>,
+        into: %{},
+        do: {tag, value}
+  end
+
+  @doc """
+  Extracts dimensions from QOI image data.
+  Returns {width, height} or nil if parsing fails.
+  """
+  def get_qoi_dimensions(<<"qoif", width::32, height::32, _rest::binary>>), do: {width, height}
+  def get_qoi_dimensions(_), do: nil
+
+  @doc """
+  Extracts dimensions and bits per pixel from TGA image data.
+  Returns {width, height, bits_per_pixel} or nil if parsing fails.
+  """
+  def tga_header(<<_id_length, 0, _image_type, _color_map::binary-size(5), _origin::32,
+                   width::16-little, height::16-little, bits_per_pixel, _rest::binary>>),
+      do: {width, height, bits_per_pixel}
+
+  def tga_header(_), do: nil
+
+  @doc """
+  Extracts dimensions from TGA image data.
+  Returns {width, height} or nil if parsing fails.
+  """
+  def get_tga_dimensions(data) do
+    case tga_header(data) do
+      {width, height, _bits_per_pixel} -> {width, height}
+      nil -> nil
+    end
+  end
+
+  @doc """
+  Extracts dimensions from binary PPM (P6) image data.
+  Returns {width, height} or nil if parsing fails.
+  """
+  def get_ppm_dimensions(<<"P6\n", rest::binary>>) do
+    with [width, rest] <- :binary.split(rest, " "),
+         [height, <<"255\n", _pixels::binary>>] <- :binary.split(rest, "\n") do
+      {String.to_integer(width), String.to_integer(height)}
+    else
+      _ -> nil
+    end
+  end
+
+  def get_ppm_dimensions(_), do: nil
+
+  @doc """
+  Extracts dimensions from farbfeld image data.
+  Returns {width, height} or nil if parsing fails.
+  """
+  def get_farbfeld_dimensions(<<"farbfeld", width::32, height::32, _pixels::binary>>),
+    do: {width, height}
+
+  def get_farbfeld_dimensions(_), do: nil
+
+  @doc """
+  Decodes a farbfeld image, keeping the high byte of each 16-bit channel.
+
+  Returns `{width, height, pixels}` with RGBA8 pixel data, like `decode_png/1`.
+  """
+  def decode_farbfeld(<<"farbfeld", width::32, height::32, pixels::binary>>) do
+    {width, height, for(<<high, _low <- pixels>>, into: <<>>, do: <<high>>)}
+  end
+
+  @doc """
+  Reads the header of an OpenEXR image.
+
+  Returns `%{width: width, height: height, channels: [{name, pixel_type}]}`, where pixel
+  type 2 is a 32-bit float, or nil if the data isn't OpenEXR.
+  """
+  def exr_header(<<0x76, 0x2F, 0x31, 0x01, _version::little-32, attributes::binary>>) do
+    attributes = read_exr_attributes(attributes, %{})
+
+    <<x_min::little-signed-32, y_min::little-signed-32, x_max::little-signed-32,
+      y_max::little-signed-32>> = attributes["dataWindow"]
+
+    %{
+      width: x_max - x_min + 1,
+      height: y_max - y_min + 1,
+      channels: read_exr_channels(attributes["channels"], [])
+    }
+  end
+
+  def exr_header(_), do: nil
+
+  defp read_exr_attributes(<<0, _rest::binary>>, acc), do: acc
+
+  defp read_exr_attributes(data, acc) do
+    [name, rest] = :binary.split(data, <<0>>)
+    [_type, <<size::little-32, rest::binary>>] = :binary.split(rest, <<0>>)
+    <<value::binary-size(size), rest::binary>> = rest
+    read_exr_attributes(rest, Map.put(acc, name, value))
+  end
+
+  defp read_exr_channels(<<0>>, acc), do: Enum.reverse(acc)
+
+  defp read_exr_channels(data, acc) do
+    [name, <<pixel_type::little-32, _linear_and_reserved::32, _sampling::64, rest::binary>>] =
+      :binary.split(data, <<0>>)
+
+    read_exr_channels(rest, [{name, pixel_type} | acc])
+  end
+
+  @doc """
+  Decodes a QOI image.
+
+  Returns `{width, height, pixels}` with RGBA8 pixel data, like `decode_png/1`.
+  """
+  def decode_qoi(<<"qoif", width::32, height::32, _channels, _colorspace, chunks::binary>>) do
+    index = :erlang.make_tuple(64, {0, 0, 0, 0})
+    pixels = decode_qoi_chunks(chunks, width * height, {0, 0, 0, 255}, index, [])
+    {width, height, pixels}
+  end
+
+  defp decode_qoi_chunks(_chunks, 0, _previous, _index, acc) do
+    acc |> Enum.reverse() |> IO.iodata_to_binary()
+  end
+
+  defp decode_qoi_chunks(chunks, remaining, {r, g, b, a} = previous, index, acc) do
+    {pixel, run, rest} =
+      case chunks do
+        <<0xFE, r, g, b, rest::binary>> ->
+          {{r, g, b, a}, 1, rest}
+
+        <<0xFF, r, g, b, a, rest::binary>> ->
+          {{r, g, b, a}, 1, rest}
+
+        <<0::2, position::6, rest::binary>> ->
+          {elem(index, position), 1, rest}
+
+        <<1::2, dr::2, dg::2, db::2, rest::binary>> ->
+          {{wrap_byte(r + dr - 2), wrap_byte(g + dg - 2), wrap_byte(b + db - 2), a}, 1, rest}
+
+        <<2::2, dg::6, dr_dg::4, db_dg::4, rest::binary>> ->
+          dg = dg - 32
+
+          {{wrap_byte(r + dg + dr_dg - 8), wrap_byte(g + dg), wrap_byte(b + dg + db_dg - 8), a},
+           1, rest}
+
+        <<3::2, run::6, rest::binary>> ->
+          {previous, run + 1, rest}
+      end
+
+    {pr, pg, pb, pa} = pixel
+    index = put_elem(index, rem(pr * 3 + pg * 5 + pb * 7 + pa * 11, 64), pixel)
+    pixels = List.duplicate(<<pr, pg, pb, pa>>, run)
+    decode_qoi_chunks(rest, remaining - run, pixel, index, [pixels | acc])
+  end
+
+  defp wrap_byte(value), do: band(value, 0xFF)
+
+  @doc """
+  Decodes a run-length encoded, top-to-bottom 32-bit TGA image.
+
+  Returns `{width, height, pixels}` with RGBA8 pixel data, like `decode_png/1`.
+  """
+  def decode_tga(
+        <<id_length, 0, 10, _color_map::binary-size(5), _x::16, _y::16, width::little-16,
+          height::little-16, 32, descriptor, rest::binary>>
+      )
+      when band(descriptor, 0x20) != 0 do
+    <<_id::binary-size(id_length), packets::binary>> = rest
+    pixels = decode_tga_packets(packets, width * height, [])
+    {width, height, pixels}
+  end
+
+  defp decode_tga_packets(_packets, 0, acc) do
+    acc |> Enum.reverse() |> IO.iodata_to_binary()
+  end
+
+  defp decode_tga_packets(<<1::1, count::7, b, g, r, a, rest::binary>>, remaining, acc) do
+    pixels = List.duplicate(<<r, g, b, a>>, count + 1)
+    decode_tga_packets(rest, remaining - count - 1, [pixels | acc])
+  end
+
+  defp decode_tga_packets(<<0::1, count::7, rest::binary>>, remaining, acc) do
+    <<raw::binary-size((count + 1) * 4), rest::binary>> = rest
+    pixels = for <<b, g, r, a <- raw>>, into: <<>>, do: <<r, g, b, a>>
+    decode_tga_packets(rest, remaining - count - 1, [pixels | acc])
+  end
+
   @doc """
   Gets dimensions for any supported format.
   Returns {width, height} or nil if parsing fails.
@@ -274,6 +946,22 @@ defmodule Svgager.TestHelper do
   def get_dimensions(data, :jpeg), do: get_jpeg_dimensions(data)
   def get_dimensions(data, :gif), do: get_gif_dimensions(data)
   def get_dimensions(data, :webp), do: get_webp_dimensions(data)
+  def get_dimensions(data, :bmp), do: get_bmp_dimensions(data)
+  def get_dimensions(data, :tiff), do: get_tiff_dimensions(data)
+  def get_dimensions(data, :tif), do: get_tiff_dimensions(data)
+  def get_dimensions(data, :qoi), do: get_qoi_dimensions(data)
+  def get_dimensions(data, :tga), do: get_tga_dimensions(data)
+  def get_dimensions(data, :pnm), do: get_ppm_dimensions(data)
+  def get_dimensions(data, :ppm), do: get_ppm_dimensions(data)
+  def get_dimensions(data, :ff), do: get_farbfeld_dimensions(data)
+  def get_dimensions(data, :farbfeld), do: get_farbfeld_dimensions(data)
+
+  def get_dimensions(data, :exr) do
+    case exr_header(data) do
+      %{width: width, height: height} -> {width, height}
+      nil -> nil
+    end
+  end
 
   @doc """
   Verifies that image dimensions match expected values.
@@ -12,6 +12,15 @@ defmodule Svgager.ConverterTest do
       assert TestHelper.valid_format?(data, :png)
     end
 
+    test "converts SVG to byte-identical PNGs on repeated calls" do
+      svg = TestHelper.complex_svg()
+      opts = [format: :png, width: 200, png_compression: :best, metadata: %{"Title" => "Logo"}]
+
+      assert {:ok, first} = Converter.convert(svg, opts)
+      assert {:ok, ^first} = Converter.convert(svg, opts)
+      refute first =~ "tIME"
+    end
+
     test "converts SVG to JPG format" do
       svg = TestHelper.simple_svg()
 
@@ -44,6 +53,153 @@ defmodule Svgager.ConverterTest do
       assert TestHelper.valid_format?(data, :webp)
     end
 
+    test "converts SVG to BMP format" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :bmp, width: 100)
+      assert is_binary(data)
+      assert TestHelper.valid_format?(data, :bmp)
+    end
+
+    test "converts SVG to TIFF format" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :tiff, width: 100)
+      assert is_binary(data)
+      assert TestHelper.valid_format?(data, :tiff)
+    end
+
+    test "converts SVG to QOI format" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :qoi, width: 100)
+      assert TestHelper.valid_format?(data, :qoi)
+      assert TestHelper.get_qoi_dimensions(data) == {100, 100}
+    end
+
+    test "converts SVG to AVIF format" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, <<_size::32, "ftypavif", _rest::binary>> = data} =
+               Converter.convert(svg, format: :avif, width: 32)
+
+      assert TestHelper.valid_format?(data, :avif)
+    end
+
+    test "AVIF honors quality" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, low} = Converter.convert(svg, format: :avif, width: 32, quality: 10)
+      assert {:ok, high} = Converter.convert(svg, format: :avif, width: 32, quality: 95)
+      assert byte_size(low) < byte_size(high)
+    end
+
+    test "converts SVG to TGA format with alpha" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :tga, width: 120, height: 80)
+      assert TestHelper.valid_format?(data, :tga)
+      assert TestHelper.tga_header(data) == {120, 80, 32}
+    end
+
+    test "farbfeld round-trips the rendered pixels at 16 bits per channel" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+        <circle cx="20" cy="20" r="15" fill="#FF0000"/>
+      </svg>
+      """
+
+      assert {:ok, png} = Converter.convert(svg, format: :png)
+
+      for format <- [:ff, :farbfeld] do
+        assert {:ok, data} = Converter.convert(svg, format: format)
+        assert TestHelper.valid_format?(data, format)
+        assert byte_size(data) == byte_size("farbfeld") + 8 + 40 * 40 * 8
+        assert TestHelper.decode_farbfeld(data) == TestHelper.decode_png(png)
+      end
+    end
+
+    test "converts SVG to OpenEXR with float RGBA channels" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, <<0x76, 0x2F, 0x31, 0x01, _rest::binary>> = data} =
+               Converter.convert(svg, format: :exr, width: 120, height: 80)
+
+      assert TestHelper.valid_format?(data, :exr)
+
+      assert TestHelper.exr_header(data) == %{
+               width: 120,
+               height: 80,
+               channels: [{"A", 2}, {"B", 2}, {"G", 2}, {"R", 2}]
+             }
+    end
+
+    test "converts SVG to PPM format" do
+      svg = TestHelper.simple_svg()
+
+      for format <- [:pnm, :ppm] do
+        assert {:ok, data} = Converter.convert(svg, format: format, width: 120, height: 80)
+        assert TestHelper.valid_format?(data, format)
+        assert TestHelper.get_ppm_dimensions(data) == {120, 80}
+        # Three bytes per pixel after the header, with no alpha channel
+        assert byte_size(data) == byte_size("P6\n120 80\n255\n") + 120 * 80 * 3
+      end
+    end
+
+    test "QOI round-trips the rendered pixels, including transparency" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+        <circle cx="20" cy="20" r="15" fill="#FF0000"/>
+        <rect x="5" y="5" width="10" height="10" fill="#0000FF" fill-opacity="0.5"/>
+      </svg>
+      """
+
+      assert {:ok, qoi} = Converter.convert(svg, format: :qoi)
+      assert {:ok, png} = Converter.convert(svg, format: :png)
+
+      image = TestHelper.decode_qoi(qoi)
+      assert image == TestHelper.decode_png(png)
+      assert {_r, _g, _b, 0} = TestHelper.pixel_at(image, 0, 0)
+      assert TestHelper.pixel_at(image, 20, 20) == {255, 0, 0, 255}
+    end
+
+    test "semi-transparent pixels are encoded with straight alpha, not darkened" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+        <rect width="10" height="10" fill="#FF0000" opacity="0.5"/>
+      </svg>
+      """
+
+      assert {:ok, png} = Converter.convert(svg, format: :png)
+      assert {255, 0, 0, alpha} = png |> TestHelper.decode_png() |> TestHelper.pixel_at(5, 5)
+      assert alpha in 127..128
+
+      assert {:ok, qoi} = Converter.convert(svg, format: :qoi)
+      assert {:ok, tga} = Converter.convert(svg, format: :tga)
+      assert TestHelper.decode_qoi(qoi) == TestHelper.decode_png(png)
+      assert TestHelper.decode_tga(tga) == TestHelper.decode_png(png)
+    end
+
+    test "TGA round-trips the rendered pixels, and lossless formats are deterministic" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="300" height="200">
+        <circle cx="150" cy="100" r="90" fill="#FF8800" fill-opacity="0.7"/>
+        <rect x="10" y="10" width="280" height="40" fill="#0000FF" fill-opacity="0.5"/>
+      </svg>
+      """
+
+      assert {:ok, png} = Converter.convert(svg, format: :png)
+      assert {:ok, tga} = Converter.convert(svg, format: :tga)
+      assert TestHelper.decode_tga(tga) == TestHelper.decode_png(png)
+
+      for format <- [:webp, :tiff, :qoi, :tga] do
+        assert {:ok, data} = Converter.convert(svg, format: format)
+        assert {:ok, ^data} = Converter.convert(svg, format: format)
+        assert TestHelper.valid_format?(data, format)
+        assert TestHelper.get_dimensions(data, format) == {300, 200}
+      end
+    end
+
     test "converts complex SVG with multiple elements" do
       svg = TestHelper.complex_svg()
 
@@ -109,6 +265,62 @@ defmodule Svgager.ConverterTest do
       assert is_binary(data)
       assert TestHelper.valid_format?(data, :png)
     end
+
+    test "scales by percentages of the intrinsic size" do
+      svg = ~s(<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50"/>)
+
+      assert {:ok, %{width: 150, height: 75}} =
+               Converter.convert_with_info(svg, format: :png, width: "150%")
+
+      assert {:ok, %{width: 50, height: 25}} =
+               Converter.convert_with_info(svg, format: :png, height: "50%")
+
+      assert {:ok, %{width: 25, height: 100}} =
+               Converter.convert_with_info(svg, format: :png, width: "25%", height: "200%")
+
+      # 33.6 pixels rounds to 34, like every other derived size
+      assert {:ok, %{width: 34, height: 17}} =
+               Converter.convert_with_info(svg, format: :png, width: "33.6%")
+    end
+
+    test "accepts pixel strings and mixes them with percentages" do
+      svg = ~s(<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50"/>)
+
+      assert Converter.convert(svg, format: :png, width: "240") ==
+               Converter.convert(svg, format: :png, width: 240)
+
+      assert {:ok, %{width: 150, height: 40}} =
+               Converter.convert_with_info(svg, format: :png, width: "150%", height: 40)
+    end
+  end
+
+  describe "convert/2 - SVGZ input" do
+    test "gzip-compressed SVG produces identical output to plain SVG" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, plain} = Converter.convert(svg, format: :png, width: 150)
+      assert {:ok, compressed} = Converter.convert(:zlib.gzip(svg), format: :png, width: 150)
+      assert plain == compressed
+    end
+
+    test "replacements are applied after decompression" do
+      svg = TestHelper.replaceable_svg()
+      opts = [format: :png, width: 100, replacements: %{"blue" => "red"}]
+
+      assert {:ok, plain} = Converter.convert(svg, opts)
+      assert {:ok, compressed} = Converter.convert(:zlib.gzip(svg), opts)
+      assert plain == compressed
+    end
+
+    test "returns error for truncated SVGZ data" do
+      compressed = :zlib.gzip(TestHelper.complex_svg())
+      truncated = binary_part(compressed, 0, div(byte_size(compressed), 2))
+
+      assert {:error, {:parse_failed, message}} =
+               Converter.convert(truncated, format: :png, width: 100)
+
+      assert message =~ "Failed to decompress SVGZ"
+    end
   end
 
   describe "convert/2 - background colors" do
@@ -122,6 +334,51 @@ defmodule Svgager.ConverterTest do
       assert TestHelper.valid_format?(data, :png)
     end
 
+    test "TIFF preserves transparency when no background is supplied" do
+      # simple_svg leaves a 10px transparent margin around the rect
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :tiff, width: 100)
+      assert {_r, _g, _b, 0} = TestHelper.tiff_first_pixel(data)
+    end
+
+    test "PNG is flattened onto the background color when flatten is set" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 background_color: "000000",
+                 flatten: true
+               )
+
+      {_width, _height, pixels} = image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 0, 0) == {0, 0, 0, 255}
+      assert for(<<_r, _g, _b, a <- pixels>>, uniq: true, do: a) == [255]
+    end
+
+    test "PNG stays transparent when flatten is false" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 background_color: "000000",
+                 flatten: false
+               )
+
+      assert {_r, _g, _b, 0} = data |> TestHelper.decode_png() |> TestHelper.pixel_at(0, 0)
+    end
+
+    test "returns error for non-boolean flatten" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, flatten: "yes")
+
+      assert message =~ "flatten must be a boolean"
+    end
+
     test "JPG uses background color" do
       svg = TestHelper.simple_svg()
 
@@ -152,189 +409,3270 @@ defmodule Svgager.ConverterTest do
       assert TestHelper.valid_format?(data, :jpg)
     end
 
-    test "WebP uses background color" do
+    test "accepts 3-character shorthand background color" do
       svg = TestHelper.simple_svg()
 
-      assert {:ok, data} =
-               Converter.convert(svg, format: :webp, width: 100, background_color: "FF00FF")
+      assert {:ok, shorthand} =
+               Converter.convert(svg, format: :jpg, width: 100, background_color: "#0F0")
 
-      assert is_binary(data)
-      assert TestHelper.valid_format?(data, :webp)
+      assert {:ok, full} =
+               Converter.convert(svg, format: :jpg, width: 100, background_color: "#00FF00")
+
+      assert shorthand == full
     end
 
-    test "GIF uses background color" do
+    test "accepts 8-character background color with alpha" do
       svg = TestHelper.simple_svg()
 
       assert {:ok, data} =
-               Converter.convert(svg, format: :gif, width: 100, background_color: "FFFF00")
+               Converter.convert(svg, format: :webp, width: 100, background_color: "FF000080")
 
-      assert is_binary(data)
-      assert TestHelper.valid_format?(data, :gif)
+      assert TestHelper.valid_format?(data, :webp)
     end
 
-    test "uses default white background when not specified for JPG" do
-      svg = TestHelper.simple_svg()
+    test "fills transparent areas of JPEG output with the background color" do
+      svg = ~s(<svg xmlns="http://www.w3.org/2000/svg" width="40" height="40"/>)
 
-      assert {:ok, data} = Converter.convert(svg, format: :jpg, width: 100)
-      assert is_binary(data)
-      assert TestHelper.valid_format?(data, :jpg)
+      assert {:ok, jpg} = Converter.convert(svg, format: :jpg, background_color: "112233")
+
+      # Render the JPEG back to PNG to read its pixels
+      embedded = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+        <image width="40" height="40" href="data:image/jpeg;base64,#{Base.encode64(jpg)}"/>
+      </svg>
+      """
+
+      assert {:ok, png} = Converter.convert(embedded, format: :png)
+      {r, g, b, 255} = png |> TestHelper.decode_png() |> TestHelper.pixel_at(0, 0)
+      assert abs(r - 0x11) <= 3 and abs(g - 0x22) <= 3 and abs(b - 0x33) <= 3
     end
-  end
 
-  describe "convert/2 - SVG preprocessing with replacements" do
-    test "replaces hex color codes in SVG" do
-      svg = TestHelper.replaceable_svg()
+    test "uses an 8-character background color at full opacity for JPEG output" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect x="10" y="10" width="80" height="80" fill="#FF0000" fill-opacity="0.5"/>
+      </svg>
+      """
 
-      assert {:ok, data} =
-               Converter.convert(svg,
-                 format: :png,
-                 width: 100,
-                 replacements: %{"#000000" => "#FF0000"}
-               )
+      assert {:ok, translucent} =
+               Converter.convert(svg, format: :jpg, width: 100, background_color: "11223380")
 
-      assert is_binary(data)
-      assert TestHelper.valid_format?(data, :png)
+      assert {:ok, opaque} =
+               Converter.convert(svg, format: :jpg, width: 100, background_color: "112233")
+
+      assert translucent == opaque
     end
 
-    test "replaces named colors in SVG" do
-      svg = TestHelper.replaceable_svg()
+    test "accepts named background colors" do
+      svg = TestHelper.simple_svg()
 
-      assert {:ok, data} =
-               Converter.convert(svg,
-                 format: :png,
-                 width: 100,
-                 replacements: %{"blue" => "red"}
-               )
+      assert {:ok, named} =
+               Converter.convert(svg, format: :jpg, width: 100, background_color: "white")
 
-      assert is_binary(data)
-      assert TestHelper.valid_format?(data, :png)
+      assert {:ok, hex} =
+               Converter.convert(svg, format: :jpg, width: 100, background_color: "FFFFFF")
+
+      assert named == hex
     end
 
-    test "applies multiple replacements" do
-      svg = TestHelper.replaceable_svg()
+    test "matches named background colors case-insensitively" do
+      svg = TestHelper.simple_svg()
 
-      assert {:ok, data} =
-               Converter.convert(svg,
-                 format: :png,
-                 width: 100,
-                 replacements: %{
-                   "#000000" => "#FF0000",
-                   "blue" => "green"
-                 }
-               )
+      assert {:ok, upper} =
+               Converter.convert(svg, format: :jpg, width: 100, background_color: "RoyalBlue")
 
-      assert is_binary(data)
-      assert TestHelper.valid_format?(data, :png)
+      assert {:ok, lower} =
+               Converter.convert(svg, format: :jpg, width: 100, background_color: "royalblue")
+
+      assert upper == lower
     end
 
-    test "handles empty replacements map" do
+    test "accepts transparent as a background color" do
       svg = TestHelper.simple_svg()
 
-      assert {:ok, data} = Converter.convert(svg, format: :png, width: 100, replacements: %{})
-      assert is_binary(data)
-      assert TestHelper.valid_format?(data, :png)
+      assert {:ok, data} =
+               Converter.convert(svg, format: :webp, width: 100, background_color: "transparent")
+
+      assert TestHelper.valid_format?(data, :webp)
     end
 
-    test "handles replacements with no matches in SVG" do
+    test "accepts rgb() background colors" do
       svg = TestHelper.simple_svg()
 
-      assert {:ok, data} =
+      assert {:ok, rgb} =
                Converter.convert(svg,
-                 format: :png,
+                 format: :jpg,
                  width: 100,
-                 replacements: %{"nonexistent" => "value"}
+                 background_color: "rgb( 255 , 128,0 )"
                )
 
-      assert is_binary(data)
-      assert TestHelper.valid_format?(data, :png)
-    end
-  end
-
-  describe "convert/2 - error handling" do
-    test "returns error when format is missing" do
-      svg = TestHelper.simple_svg()
+      assert {:ok, hex} =
+               Converter.convert(svg, format: :jpg, width: 100, background_color: "FF8000")
 
-      assert {:error, message} = Converter.convert(svg, width: 100)
-      assert message == "format option is required"
+      assert rgb == hex
     end
 
-    test "returns error for unsupported format" do
+    test "accepts rgba() background colors with float alpha" do
       svg = TestHelper.simple_svg()
 
-      assert {:error, message} = Converter.convert(svg, format: :bmp, width: 100)
-      assert message =~ "unsupported format"
-      assert message =~ ":bmp"
-    end
+      assert {:ok, rgba} =
+               Converter.convert(svg,
+                 format: :webp,
+                 width: 100,
+                 background_color: "rgba(255, 128, 0, 0.5)"
+               )
 
-    test "returns error for invalid SVG" do
-      svg = TestHelper.invalid_svg()
+      assert {:ok, hex} =
+               Converter.convert(svg, format: :webp, width: 100, background_color: "FF800080")
 
-      assert {:error, message} = Converter.convert(svg, format: :png, width: 100)
-      assert message =~ "Failed to parse SVG"
+      assert rgba == hex
     end
 
-    test "returns error for negative width" do
+    test "returns error for wrong rgb() component count" do
       svg = TestHelper.simple_svg()
 
-      assert {:error, message} = Converter.convert(svg, format: :png, width: -100)
-      assert message =~ "width must be a positive integer"
-    end
-
-    test "returns error for zero width" do
-      svg = TestHelper.simple_svg()
+      assert {:error, {:invalid_color, message}} =
+               Converter.convert(svg, format: :jpg, width: 100, background_color: "rgb(255, 0)")
 
-      assert {:error, message} = Converter.convert(svg, format: :png, width: 0)
-      assert message =~ "width must be a positive integer"
+      assert message =~ "expected 3 components, got 2"
     end
 
-    test "returns error for negative height" do
+    test "returns error for unknown color name" do
       svg = TestHelper.simple_svg()
 
-      assert {:error, message} = Converter.convert(svg, format: :png, height: -100)
-      assert message =~ "height must be a positive integer"
-    end
-
-    test "returns error for zero height" do
-      svg = TestHelper.simple_svg()
+      assert {:error, {:invalid_color, message}} =
+               Converter.convert(svg, format: :jpg, width: 100, background_color: "notacolor")
 
-      assert {:error, message} = Converter.convert(svg, format: :png, height: 0)
-      assert message =~ "height must be a positive integer"
+      assert message =~ "not a known color name"
     end
 
-    test "returns error for non-integer width" do
+    test "returns error listing accepted lengths for invalid background color" do
       svg = TestHelper.simple_svg()
 
-      assert {:error, message} = Converter.convert(svg, format: :png, width: "100")
-      assert message =~ "width must be a positive integer"
+      assert {:error, {:invalid_color, message}} =
+               Converter.convert(svg, format: :jpg, width: 100, background_color: "FFFF")
+
+      assert message =~ "must be 3, 6 or 8 characters"
     end
 
-    test "returns error for non-integer height" do
+    test "WebP uses background color" do
       svg = TestHelper.simple_svg()
 
-      assert {:error, message} = Converter.convert(svg, format: :png, height: 99.5)
-      assert message =~ "height must be a positive integer"
+      assert {:ok, data} =
+               Converter.convert(svg, format: :webp, width: 100, background_color: "FF00FF")
+
+      assert is_binary(data)
+      assert TestHelper.valid_format?(data, :webp)
     end
 
-    test "returns error for invalid replacements (not a map)" do
+    test "GIF uses background color when flattened" do
       svg = TestHelper.simple_svg()
 
-      assert {:error, message} =
-               Converter.convert(svg, format: :png, width: 100, replacements: "invalid")
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :gif,
+                 width: 100,
+                 background_color: "FFFF00",
+                 flatten: true
+               )
 
-      assert message =~ "replacements must be a map"
+      assert TestHelper.valid_format?(data, :gif)
+      assert TestHelper.gif_transparent_index(data) == nil
     end
 
-    test "returns error for empty SVG string" do
-      assert {:error, message} = Converter.convert("", format: :png, width: 100)
-      assert message =~ "Failed to parse SVG"
+    test "GIF keeps a transparent background" do
+      # simple_svg's rect leaves a transparent margin around it
+      assert {:ok, data} = Converter.convert(TestHelper.simple_svg(), format: :gif, width: 100)
+
+      assert TestHelper.valid_format?(data, :gif)
+      assert TestHelper.gif_transparent_index(data) != nil
     end
 
-    test "returns error for non-string SVG input" do
-      assert_raise FunctionClauseError, fn ->
-        Converter.convert(123, format: :png, width: 100)
-      end
+    test "GIF without transparent pixels has no transparent index" do
+      assert {:ok, data} = Converter.convert(TestHelper.solid_svg("#00FF00"), format: :gif)
+      assert TestHelper.gif_transparent_index(data) == nil
     end
-  end
+
+    test "uses default white background when not specified for JPG" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :jpg, width: 100)
+      assert is_binary(data)
+      assert TestHelper.valid_format?(data, :jpg)
+    end
+  end
+
+  describe "convert/2 - background gradient" do
+    @gradient {"#FFFFFF", "#0000FF"}
+
+    test "the top and bottom rows match the two colors" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :png,
+                 background_gradient: @gradient
+               )
+
+      image = TestHelper.decode_png(data)
+
+      for x <- [0, 50, 99] do
+        assert TestHelper.pixel_at(image, x, 0) == {255, 255, 255, 255}
+        assert TestHelper.pixel_at(image, x, 99) == {0, 0, 255, 255}
+      end
+
+      # The rect is drawn over the gradient, which is halfway between the colors beside it
+      assert TestHelper.pixel_at(image, 50, 50) == {255, 0, 0, 255}
+      {r, g, b, 255} = TestHelper.pixel_at(image, 0, 50)
+      assert r == g and r in 120..135 and b == 255
+    end
+
+    test "overrides a flattened background_color" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :qoi,
+                 flatten: true,
+                 background_color: "#00FF00",
+                 background_gradient: @gradient
+               )
+
+      assert {:ok, png} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :png,
+                 background_gradient: @gradient
+               )
+
+      assert TestHelper.decode_qoi(data) == TestHelper.decode_png(png)
+    end
+
+    test "covers the padding" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :png,
+                 padding: 5,
+                 background_gradient: @gradient
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 0, 0) == {255, 255, 255, 255}
+      assert TestHelper.pixel_at(image, 0, 109) == {0, 0, 255, 255}
+    end
+
+    test "is drawn after trimming transparent margins" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :png,
+                 trim: true,
+                 background_gradient: @gradient
+               )
+
+      assert TestHelper.get_png_dimensions(data) == {80, 80}
+    end
+
+    test "returns error for invalid colors" do
+      assert {:error, {:invalid_color, _message}} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :png,
+                 background_gradient: {"#FFFFFF", "not-a-color"}
+               )
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :png,
+                 background_gradient: "#FFFFFF"
+               )
+
+      assert message =~ "background_gradient must be"
+    end
+  end
+
+  describe "convert/2 - default_fill" do
+    @font_opts [font_dirs: [TestHelper.fonts_dir()], load_system_fonts: false]
+
+    test "unstyled shapes and text render in the default fill" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="100" height="40">
+        <rect width="20" height="40"/>
+        <text x="30" y="30" font-size="30" font-family="DejaVu Sans Mono">█</text>
+      </svg>
+      """
+
+      assert {:ok, data} = Converter.convert(svg, [format: :png] ++ @font_opts)
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 10, 20) == {0, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 45, 20) == {0, 0, 0, 255}
+
+      assert {:ok, data} =
+               Converter.convert(svg, [format: :png, default_fill: "white"] ++ @font_opts)
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 10, 20) == {255, 255, 255, 255}
+      assert TestHelper.pixel_at(image, 45, 20) == {255, 255, 255, 255}
+    end
+
+    test "keeps explicit fills, including one on the root" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+        <rect width="10" height="10" fill="#0000FF"/>
+        <rect x="10" width="10" height="10"/>
+      </svg>
+      """
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, default_fill: "#FFFFFF")
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 5, 5) == {0, 0, 255, 255}
+      assert TestHelper.pixel_at(image, 15, 5) == {255, 255, 255, 255}
+
+      root_filled = String.replace(svg, "<svg ", ~S(<svg fill="#00FF00" ))
+      assert {:ok, data} = Converter.convert(root_filled, format: :png, default_fill: "#FFFFFF")
+      assert data |> TestHelper.decode_png() |> TestHelper.pixel_at(15, 5) == {0, 255, 0, 255}
+    end
+
+    test "fills SVGs with a DOCTYPE" do
+      svg =
+        ~s(<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" ) <>
+          ~s("http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">) <>
+          ~s(<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">) <>
+          ~s(<rect width="10" height="10"/></svg>)
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, default_fill: "#FFFFFF")
+      assert data |> TestHelper.decode_png() |> TestHelper.pixel_at(5, 5) == {255, 255, 255, 255}
+    end
+
+    test "returns error for an invalid default_fill" do
+      assert {:error, {:invalid_color, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, default_fill: "nope")
+
+      assert message =~ ~S(Invalid color: "nope")
+    end
+  end
+
+  describe "convert/2 - color_remap" do
+    test "recolors fills and strokes near the from color but not unrelated colors" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="40" height="10">
+        <style>.off { fill: rgb(250, 4, 4) }</style>
+        <rect width="10" height="10" fill="#FE0101"/>
+        <rect x="10" width="10" height="10" style="fill: red"/>
+        <rect x="20" width="10" height="10" class="off"/>
+        <rect x="30" width="10" height="10" fill="#E00000"/>
+      </svg>
+      """
+
+      assert {:ok, data} =
+               Converter.convert(svg, format: :png, color_remap: [{"#FF0000", "#123456", 8}])
+
+      image = TestHelper.decode_png(data)
+
+      for x <- [5, 15, 25] do
+        assert TestHelper.pixel_at(image, x, 5) == {0x12, 0x34, 0x56, 255}
+      end
+
+      assert TestHelper.pixel_at(image, 35, 5) == {0xE0, 0, 0, 255}
+    end
+
+    test "recolors gradient stops" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+        <style>.end { stop-color: #FF0101 }</style>
+        <linearGradient id="g">
+          <stop offset="0.5" stop-color="#FE0000"/>
+          <stop offset="0.5" class="end"/>
+        </linearGradient>
+        <rect width="20" height="10" fill="url(#g)"/>
+      </svg>
+      """
+
+      assert {:ok, data} =
+               Converter.convert(svg, format: :png, color_remap: [{"#FF0000", "#123456", 8}])
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 2, 5) == {0x12, 0x34, 0x56, 255}
+      assert TestHelper.pixel_at(image, 17, 5) == {0x12, 0x34, 0x56, 255}
+    end
+
+    test "returns error for invalid entries and colors" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, color_remap: [{"#FF0000", "#123456", 300}])
+
+      assert message =~ "color_remap entries must be {from, to, tolerance} tuples"
+
+      assert {:error, {:invalid_color, message}} =
+               Converter.convert(svg, format: :png, color_remap: [{"nope", "#123456", 8}])
+
+      assert message =~ ~S(Invalid color: "nope")
+    end
+  end
+
+  describe "convert/2 - SVG preprocessing with replacements" do
+    test "replaces hex color codes in SVG" do
+      svg = TestHelper.replaceable_svg()
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 replacements: %{"#000000" => "#FF0000"}
+               )
+
+      assert is_binary(data)
+      assert TestHelper.valid_format?(data, :png)
+    end
+
+    test "replaces named colors in SVG" do
+      svg = TestHelper.replaceable_svg()
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 replacements: %{"blue" => "red"}
+               )
+
+      assert is_binary(data)
+      assert TestHelper.valid_format?(data, :png)
+    end
+
+    test "applies multiple replacements" do
+      svg = TestHelper.replaceable_svg()
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 replacements: %{
+                   "#000000" => "#FF0000",
+                   "blue" => "green"
+                 }
+               )
+
+      assert is_binary(data)
+      assert TestHelper.valid_format?(data, :png)
+    end
+
+    test "handles empty replacements map" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, width: 100, replacements: %{})
+      assert is_binary(data)
+      assert TestHelper.valid_format?(data, :png)
+    end
+
+    test "handles replacements with no matches in SVG" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 replacements: %{"nonexistent" => "value"}
+               )
+
+      assert is_binary(data)
+      assert TestHelper.valid_format?(data, :png)
+    end
+
+    test "applies regex replacements to every match" do
+      svg = TestHelper.replaceable_svg()
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 regex_replacements: %{~S(fill="[^"]*") => ~S(fill="#00FF00")}
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 20, 20) == {0, 255, 0, 255}
+      assert TestHelper.pixel_at(image, 70, 70) == {0, 255, 0, 255}
+    end
+
+    test "regex replacements can reference capture groups" do
+      svg = TestHelper.replaceable_svg()
+
+      assert {:ok, regex} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 regex_replacements: %{~S{(width|height)="40"} => ~S(${1}="20")}
+               )
+
+      assert {:ok, literal} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 replacements: %{
+                   ~S(width="40") => ~S(width="20"),
+                   ~S(height="40") => ~S(height="20")
+                 }
+               )
+
+      assert regex == literal
+    end
+
+    test "regex replacements run after literal replacements" do
+      svg = TestHelper.replaceable_svg()
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 replacements: %{"blue" => "#000000"},
+                 regex_replacements: %{"#0{6}" => "#FFFFFF"}
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 20, 20) == {255, 255, 255, 255}
+      assert TestHelper.pixel_at(image, 70, 70) == {255, 255, 255, 255}
+    end
+
+    test "list replacements are applied in order" do
+      svg = TestHelper.replaceable_svg()
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 replacements: [{"blue", "#000000"}, {"#000000", "#00FF00"}]
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 20, 20) == {0, 255, 0, 255}
+      assert TestHelper.pixel_at(image, 70, 70) == {0, 255, 0, 255}
+    end
+
+    test "max limits replacements to the first occurrences" do
+      svg = TestHelper.replaceable_svg()
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 replacements: [{"blue", "#000000"}, {"#000000", "#00FF00", 1}]
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 20, 20) == {0, 255, 0, 255}
+      assert TestHelper.pixel_at(image, 70, 70) == {0, 0, 0, 255}
+    end
+
+    test "max limits regex replacements to the first matches" do
+      svg = TestHelper.replaceable_svg()
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 regex_replacements: [{~S(fill="[^"]*"), ~S(fill="#00FF00"), 1}]
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 20, 20) == {0, 255, 0, 255}
+      assert TestHelper.pixel_at(image, 70, 70) == {0, 0, 255, 255}
+    end
+
+    test "a text scope leaves matching attribute values untouched" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <style>.swatch { fill: red }</style>
+        <rect id="red" class="swatch" width="100" height="100"/>
+      </svg>
+      """
+
+      opts = [replacements: [{"red", "blue", :text}]]
+      assert {:ok, ["red"]} = Converter.element_ids(svg, opts)
+      assert {:ok, data} = Converter.convert(svg, [format: :png] ++ opts)
+      assert data |> TestHelper.decode_png() |> TestHelper.pixel_at(50, 50) == {0, 0, 255, 255}
+
+      assert {:ok, ["blue"]} = Converter.element_ids(svg, replacements: [{"red", "blue"}])
+    end
+
+    test "an attr scope leaves matching element text untouched" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <style>.keep { fill: red }</style>
+        <rect class="keep" width="50" height="50"/>
+        <rect x="50" width="50" height="50" fill="red"/>
+        <rect y="50" width="100" height="50" fill="red"/>
+      </svg>
+      """
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 replacements: [{"red", "#00FF00", 1, :attr}],
+                 # Anchors match at the start and end of each attribute value
+                 regex_replacements: [{"^red$", "#0000FF", :attr}]
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 25, 25) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 75, 25) == {0, 255, 0, 255}
+      assert TestHelper.pixel_at(image, 50, 75) == {0, 0, 255, 255}
+    end
+
+    test "returns error for an unknown replacement scope" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :png,
+                 replacements: [{"#FF0000", "#0000FF", :attribute}]
+               )
+
+      assert message =~ "a scope of :any, :attr or :text"
+    end
+
+    test "css_vars overrides the custom properties var() references resolve to" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <style>:root { --brand: #FF0000 } .logo { fill: var(--brand) }</style>
+        <rect class="logo" width="50" height="100"/>
+        <rect x="50" width="50" height="100" fill="var(--accent, #00FF00)"/>
+      </svg>
+      """
+
+      assert {:ok, data} = Converter.convert(svg, format: :png)
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 25, 50) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 75, 50) == {0, 255, 0, 255}
+
+      css_vars = %{"--brand" => "#0000FF", "accent" => "#000000"}
+      assert {:ok, data} = Converter.convert(svg, format: :png, css_vars: css_vars)
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 25, 50) == {0, 0, 255, 255}
+      assert TestHelper.pixel_at(image, 75, 50) == {0, 0, 0, 255}
+    end
+
+    test "returns error for invalid css_vars" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, css_vars: [{"--brand", 0}])
+
+      assert message =~ "css_vars entries must be {name, value} pairs of strings"
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, css_vars: %{"--brand color" => "#000000"})
+
+      assert message =~ ~S(Invalid css_vars name "--brand color")
+    end
+
+    test "returns error naming an invalid regex pattern" do
+      svg = TestHelper.replaceable_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, regex_replacements: %{"fill=(" => ""})
+
+      assert message =~ ~S(Invalid regex pattern "fill=(")
+    end
+
+    test "escape_replacements keeps a replacement containing & well-formed" do
+      svg = TestHelper.text_svg()
+      opts = [format: :png, font_dirs: [TestHelper.fonts_dir()], load_system_fonts: false]
+      replacements = %{"Svgager" => "Fish & Chips"}
+
+      assert {:error, {:parse_failed, _message}} =
+               Converter.convert(svg, [replacements: replacements] ++ opts)
+
+      assert {:ok, escaped} =
+               Converter.convert(svg,
+                 [replacements: replacements, escape_replacements: true] ++ opts
+               )
+
+      assert {:ok, expected} =
+               Converter.convert(String.replace(svg, "Svgager", "Fish &amp; Chips"), opts)
+
+      image = TestHelper.decode_png(escaped)
+      refute TestHelper.fully_transparent?(image)
+      assert image == TestHelper.decode_png(expected)
+    end
+
+    test "escape_replacements escapes quotes and brackets in attribute values" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+        <rect width="10" height="10" fill="#FF0000" aria-label="{{label}}"/>
+      </svg>
+      """
+
+      replacements = %{"{{label}}" => ~S(Say "hi" <now>)}
+
+      assert {:error, {:parse_failed, _message}} =
+               Converter.convert(svg, format: :png, replacements: replacements)
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 replacements: replacements,
+                 escape_replacements: true
+               )
+
+      assert data |> TestHelper.decode_png() |> TestHelper.pixel_at(5, 5) == {255, 0, 0, 255}
+    end
+
+    test "ignore_case_replacements replaces tokens regardless of case" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect width="50" height="100" fill="{{Color}}"/>
+        <rect x="50" width="50" height="100" fill="{{COLOR}}"/>
+      </svg>
+      """
+
+      replacements = %{"{{color}}" => "#00FF00"}
+
+      assert {:ok, exact} = Converter.convert(svg, format: :png, replacements: replacements)
+      assert exact |> TestHelper.decode_png() |> TestHelper.pixel_at(20, 50) == {0, 0, 0, 255}
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 replacements: replacements,
+                 ignore_case_replacements: true
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 20, 50) == {0, 255, 0, 255}
+      assert TestHelper.pixel_at(image, 70, 50) == {0, 255, 0, 255}
+    end
+
+    test "ignore_case_replacements respects max" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect width="50" height="100" fill="BLUE_TOKEN"/>
+        <rect x="50" width="50" height="100" fill="blue_token"/>
+      </svg>
+      """
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 replacements: [{"Blue_Token", "#0000FF", 1}, {"blue_token", "#FF0000"}],
+                 ignore_case_replacements: true
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 20, 50) == {0, 0, 255, 255}
+      assert TestHelper.pixel_at(image, 70, 50) == {255, 0, 0, 255}
+    end
+  end
+
+  describe "convert/2 - quality" do
+    test "lower JPEG quality produces smaller output" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, low} = Converter.convert(svg, format: :jpg, width: 200, quality: 10)
+      assert {:ok, high} = Converter.convert(svg, format: :jpg, width: 200, quality: 100)
+
+      assert TestHelper.valid_format?(low, :jpg)
+      assert TestHelper.valid_format?(high, :jpg)
+      assert byte_size(low) < byte_size(high)
+    end
+
+    test "omitting quality matches the default of 90" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, default} = Converter.convert(svg, format: :jpg, width: 200)
+      assert {:ok, explicit} = Converter.convert(svg, format: :jpg, width: 200, quality: 90)
+      assert default == explicit
+    end
+
+    test "quality is ignored for PNG" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, default} = Converter.convert(svg, format: :png, width: 100)
+      assert {:ok, low} = Converter.convert(svg, format: :png, width: 100, quality: 10)
+      assert default == low
+    end
+
+    test "WebP is encoded losslessly by default and ignores quality" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, default} = Converter.convert(svg, format: :webp, width: 200)
+      assert {:ok, low} = Converter.convert(svg, format: :webp, width: 200, quality: 10)
+      assert default == low
+      assert Converter.convert(svg, format: :webp, width: 200, lossless: true) == {:ok, default}
+
+      # Lossless WebP stores a VP8L chunk, lossy WebP a VP8 chunk
+      assert <<"RIFF", _size::little-32, "WEBP", "VP8L", _rest::binary>> = default
+    end
+
+    test "lossy WebP honors quality and is smaller than lossless" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="200" height="200">
+        <defs>
+          <radialGradient id="glow">
+            <stop offset="0" stop-color="#FF0000"/>
+            <stop offset="1" stop-color="#0000FF"/>
+          </radialGradient>
+        </defs>
+        <rect width="200" height="200" fill="url(#glow)"/>
+        <circle cx="100" cy="100" r="50" fill="#FFFF00"/>
+      </svg>
+      """
+
+      assert {:ok, lossless} = Converter.convert(svg, format: :webp)
+      assert {:ok, lossy} = Converter.convert(svg, format: :webp, lossless: false)
+      assert {:ok, low} = Converter.convert(svg, format: :webp, lossless: false, quality: 10)
+
+      assert TestHelper.valid_format?(lossy, :webp)
+      assert <<"RIFF", _size::little-32, "WEBP", "VP8 ", _rest::binary>> = lossy
+      assert byte_size(lossy) < byte_size(lossless)
+      assert byte_size(low) < byte_size(lossy)
+    end
+
+    test "returns error for a non-boolean lossless" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :webp, lossless: "no")
+
+      assert message =~ "lossless"
+    end
+  end
+
+  describe "convert/2 - progressive" do
+    test "writes a progressive JPEG with an SOF2 frame header" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :jpg, width: 200, progressive: true)
+      assert TestHelper.valid_format?(data, :jpg)
+      assert TestHelper.jpeg_progressive?(data)
+      assert TestHelper.get_jpeg_dimensions(data) == {200, 200}
+    end
+
+    test "writes baseline JPEGs by default" do
+      assert {:ok, data} = Converter.convert(TestHelper.complex_svg(), format: :jpg, width: 200)
+      refute TestHelper.jpeg_progressive?(data)
+    end
+
+    test "also applies to grayscale JPEGs" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.complex_svg(),
+                 format: :jpg,
+                 width: 200,
+                 grayscale: true,
+                 progressive: true
+               )
+
+      assert TestHelper.jpeg_progressive?(data)
+      assert TestHelper.jpeg_components(data) == 1
+    end
+  end
+
+  describe "convert/2 - subsampling" do
+    # Two-pixel red stripes on white starting at odd columns, so horizontally subsampled
+    # chroma averages the last column of each stripe with the white gap pixel after it
+    @stripes_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="80" height="40">
+      <rect width="80" height="40" fill="#FFFFFF"/>
+      <path d="#{for x <- 1..73//8, into: "", do: "M#{x} 0h2v40h-2z"}" fill="#FF0000"/>
+    </svg>
+    """
+
+    test "writes the requested chroma subsampling" do
+      for subsampling <- ["4:4:4", "4:2:2", "4:2:0"] do
+        assert {:ok, data} =
+                 Converter.convert(@stripes_svg, format: :jpg, subsampling: subsampling)
+
+        assert TestHelper.jpeg_subsampling(data) == subsampling
+      end
+    end
+
+    test "defaults to 4:4:4" do
+      assert {:ok, data} = Converter.convert(@stripes_svg, format: :jpg)
+      assert TestHelper.jpeg_subsampling(data) == "4:4:4"
+
+      assert {:ok, progressive} = Converter.convert(@stripes_svg, format: :jpg, progressive: true)
+      assert TestHelper.jpeg_subsampling(progressive) == "4:4:4"
+    end
+
+    test "4:4:4 keeps the red of fine stripes from bleeding into the gaps" do
+      # Render each JPEG back to PNG to read the gap pixel right of the first stripe
+      gap_pixel = fn jpg ->
+        embedded = """
+        <svg xmlns="http://www.w3.org/2000/svg" width="80" height="40">
+          <image width="80" height="40" href="data:image/jpeg;base64,#{Base.encode64(jpg)}"/>
+        </svg>
+        """
+
+        assert {:ok, png} = Converter.convert(embedded, format: :png)
+        png |> TestHelper.decode_png() |> TestHelper.pixel_at(3, 20)
+      end
+
+      assert {:ok, full} = Converter.convert(@stripes_svg, format: :jpg, subsampling: "4:4:4")
+      {r, g, b, 255} = gap_pixel.(full)
+      assert r - g <= 10 and r - b <= 10
+
+      for subsampling <- ["4:2:2", "4:2:0"] do
+        assert {:ok, reduced} =
+                 Converter.convert(@stripes_svg, format: :jpg, subsampling: subsampling)
+
+        {r, g, b, 255} = gap_pixel.(reduced)
+        assert r - g >= 30 and r - b >= 30
+      end
+    end
+
+    test "returns error for unknown subsampling" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(@stripes_svg, format: :jpg, subsampling: "4:1:1")
+
+      assert message =~ "subsampling must be one of"
+    end
+  end
+
+  describe "convert/2 - metadata" do
+    test "writes tEXt chunks for PNG output" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :png,
+                 metadata: [{"Title", "Logo"}, {"Author", "Jane Doe"}]
+               )
+
+      assert TestHelper.valid_png?(data)
+
+      assert TestHelper.png_text_chunks(data) == [
+               {"tEXt", "Title", "Logo"},
+               {"tEXt", "Author", "Jane Doe"}
+             ]
+    end
+
+    test "accepts a map and atom keywords" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :png,
+                 metadata: %{"Software" => "svgager"}
+               )
+
+      assert TestHelper.png_text_chunks(data) == [{"tEXt", "Software", "svgager"}]
+
+      assert {:ok, data} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, metadata: [Title: "Logo"])
+
+      assert TestHelper.png_text_chunks(data) == [{"tEXt", "Title", "Logo"}]
+    end
+
+    test "keeps Latin-1 text in tEXt and writes other text as iTXt" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :png,
+                 metadata: [{"Title", "Café"}, {"Description", "地図 🗺"}]
+               )
+
+      assert TestHelper.png_text_chunks(data) == [
+               {"tEXt", "Title", "Café"},
+               {"iTXt", "Description", "地図 🗺"}
+             ]
+    end
+
+    test "writes metadata to 16-bit PNGs" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :png,
+                 bit_depth: 16,
+                 metadata: [{"Title", "Logo"}]
+               )
+
+      assert TestHelper.png_bit_depth(data) == 16
+      assert TestHelper.png_text_chunks(data) == [{"tEXt", "Title", "Logo"}]
+    end
+
+    test "writes no text chunks by default" do
+      assert {:ok, data} = Converter.convert(TestHelper.simple_svg(), format: :png)
+      assert TestHelper.png_text_chunks(data) == []
+    end
+
+    test "returns error for non-PNG formats" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :jpg,
+                 metadata: [{"Title", "Logo"}]
+               )
+
+      assert message =~ "only supported for png"
+    end
+
+    test "returns error for invalid keywords" do
+      for keyword <- ["", String.duplicate("k", 80), "Line\nBreak", "地図"] do
+        assert {:error, {:invalid_option, message}} =
+                 Converter.convert(TestHelper.simple_svg(),
+                   format: :png,
+                   metadata: [{keyword, "text"}]
+                 )
+
+        assert message =~ "keywords must be 1 to 79 printable Latin-1 characters"
+      end
+    end
+
+    test "returns error for invalid metadata" do
+      for metadata <- ["Title", [{"Title", 1}], [:title]] do
+        assert {:error, {:invalid_option, message}} =
+                 Converter.convert(TestHelper.simple_svg(), format: :png, metadata: metadata)
+
+        assert message =~ "metadata"
+      end
+    end
+  end
+
+  describe "convert/2 - dpi_output" do
+    test "writes a pHYs chunk in pixels per meter" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, dpi_output: 300)
+
+      # 300 / 0.0254 = 11811.02
+      assert TestHelper.png_physical_dimensions(data) == {11811, 11811, 1}
+    end
+
+    test "accepts fractional DPIs" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, dpi_output: 72.0)
+
+      assert TestHelper.png_physical_dimensions(data) == {2835, 2835, 1}
+    end
+
+    test "doesn't change the rendered size" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, dpi_output: 300)
+
+      assert TestHelper.get_png_dimensions(data) == {100, 100}
+    end
+
+    test "writes no pHYs chunk by default" do
+      assert {:ok, data} = Converter.convert(TestHelper.simple_svg(), format: :png)
+      assert TestHelper.png_physical_dimensions(data) == nil
+    end
+
+    test "returns error for non-PNG formats" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :jpg, dpi_output: 300)
+
+      assert message =~ "only supported for png"
+    end
+
+    test "returns error for a non-positive DPI" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, dpi_output: 0)
+
+      assert message =~ "dpi_output"
+    end
+  end
+
+  describe "convert/2 - color profile" do
+    # An sRGB ICC profile, taken from the JPEG output tagged with srgb
+    setup do
+      {:ok, jpeg} = Converter.convert(TestHelper.simple_svg(), format: :jpg, srgb: true)
+      %{profile: TestHelper.jpeg_icc_profile(jpeg)}
+    end
+
+    test "srgb embeds an sRGB ICC profile in JPEG output", %{profile: profile} do
+      assert <<_header::binary-size(36), "acsp", _rest::binary>> = profile
+    end
+
+    test "srgb tags PNG output with an sRGB chunk" do
+      assert {:ok, data} = Converter.convert(TestHelper.simple_svg(), format: :png, srgb: true)
+      assert TestHelper.png_srgb?(data)
+      assert TestHelper.png_icc_profile(data) == nil
+    end
+
+    test "embeds icc_profile in PNG and JPEG output unchanged", %{profile: profile} do
+      assert {:ok, png} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, icc_profile: profile)
+
+      assert TestHelper.png_icc_profile(png) == profile
+      refute TestHelper.png_srgb?(png)
+
+      assert {:ok, jpeg} =
+               Converter.convert(TestHelper.simple_svg(), format: :jpg, icc_profile: profile)
+
+      assert TestHelper.jpeg_icc_profile(jpeg) == profile
+    end
+
+    test "writes no profile by default" do
+      assert {:ok, png} = Converter.convert(TestHelper.simple_svg(), format: :png)
+      assert TestHelper.png_icc_profile(png) == nil
+      refute TestHelper.png_srgb?(png)
+
+      assert {:ok, jpeg} = Converter.convert(TestHelper.simple_svg(), format: :jpg)
+      assert TestHelper.jpeg_icc_profile(jpeg) == nil
+    end
+
+    test "returns error for other formats, invalid profiles and both", %{profile: profile} do
+      svg = TestHelper.simple_svg()
+
+      for {opts, expected} <- [
+            {[format: :webp, icc_profile: profile], "only supported for png and jpg"},
+            {[format: :webp, srgb: true], "only supported for png and jpg"},
+            {[format: :png, icc_profile: "not a profile"], "could not be parsed"},
+            {[format: :png, icc_profile: profile, srgb: true], "can't be combined"},
+            {[format: :png, icc_profile: ""], "icc_profile must be a binary"},
+            {[format: :png, srgb: "yes"], "srgb must be a boolean"}
+          ] do
+        assert {:error, {:invalid_option, message}} = Converter.convert(svg, opts)
+        assert message =~ expected
+      end
+    end
+  end
+
+  describe "convert/2 - rendering hints" do
+    @circle_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="40" height="30">
+      <circle cx="20" cy="15" r="10" fill="#FF8800"/>
+    </svg>
+    """
+
+    test "shape_rendering crisp_edges turns off anti-aliasing" do
+      assert {:ok, smooth} = Converter.convert(@circle_svg, format: :png)
+
+      assert {:ok, crisp} =
+               Converter.convert(@circle_svg, format: :png, shape_rendering: :crisp_edges)
+
+      assert length(TestHelper.channel_values(smooth, :alpha)) > 2
+      assert TestHelper.channel_values(crisp, :alpha) == [0, 255]
+    end
+
+    test "shape_rendering doesn't override an explicit shape-rendering attribute" do
+      svg = String.replace(@circle_svg, "<circle", ~s(<circle shape-rendering="crispEdges"))
+
+      assert {:ok, data} =
+               Converter.convert(svg, format: :png, shape_rendering: :geometric_precision)
+
+      assert TestHelper.channel_values(data, :alpha) == [0, 255]
+    end
+
+    test "image_rendering optimize_speed scales embedded images without smoothing" do
+      checker = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="2" height="2">
+        <rect width="2" height="2" fill="#FFFFFF"/>
+        <rect width="1" height="1" fill="#000000"/>
+        <rect x="1" y="1" width="1" height="1" fill="#000000"/>
+      </svg>
+      """
+
+      assert {:ok, checker_png} = Converter.convert(checker, format: :png)
+
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+        <image width="40" height="40" href="data:image/png;base64,#{Base.encode64(checker_png)}"/>
+      </svg>
+      """
+
+      assert {:ok, smooth} = Converter.convert(svg, format: :png)
+      assert {:ok, sharp} = Converter.convert(svg, format: :png, image_rendering: :optimize_speed)
+
+      assert length(TestHelper.channel_values(smooth, :red)) > 2
+      assert TestHelper.channel_values(sharp, :red) == [0, 255]
+    end
+
+    test "image_rendering picks the filter for scaled-up embedded JPEG images" do
+      checker = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="2" height="2">
+        <rect width="2" height="2" fill="#FFFFFF"/>
+        <rect width="1" height="1" fill="#000000"/>
+        <rect x="1" y="1" width="1" height="1" fill="#000000"/>
+      </svg>
+      """
+
+      assert {:ok, checker_jpg} = Converter.convert(checker, format: :jpg, quality: 100)
+
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+        <image width="40" height="40" href="data:image/jpeg;base64,#{Base.encode64(checker_jpg)}"/>
+      </svg>
+      """
+
+      assert {:ok, quality} =
+               Converter.convert(svg, format: :png, image_rendering: :optimize_quality)
+
+      assert {:ok, speed} = Converter.convert(svg, format: :png, image_rendering: :optimize_speed)
+
+      assert TestHelper.decode_png(quality) != TestHelper.decode_png(speed)
+      assert length(TestHelper.channel_values(quality, :red)) > 2
+      assert TestHelper.channel_values(speed, :red) == [0, 255]
+    end
+
+    test "accepts every text_rendering value" do
+      for value <- [:optimize_speed, :optimize_legibility, :geometric_precision] do
+        assert {:ok, data} = Converter.convert(@circle_svg, format: :png, text_rendering: value)
+        assert TestHelper.valid_png?(data)
+      end
+    end
+
+    test "returns error listing the accepted values" do
+      for key <- [:shape_rendering, :text_rendering, :image_rendering] do
+        assert {:error, {:invalid_option, message}} =
+                 Converter.convert(@circle_svg, [{:format, :png}, {key, :blurry}])
+
+        assert message =~ "#{key} must be one of"
+        assert message =~ ":optimize_speed"
+      end
+    end
+  end
+
+  describe "convert/2 - languages" do
+    @switch_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+      <switch>
+        <rect systemLanguage="de" width="10" height="10" fill="#FF0000"/>
+        <rect systemLanguage="en" width="10" height="10" fill="#0000FF"/>
+        <rect width="10" height="10" fill="#00FF00"/>
+      </switch>
+    </svg>
+    """
+
+    test "renders the variant for the requested language" do
+      assert {:ok, data} = Converter.convert(@switch_svg, format: :png, languages: ["de"])
+      assert TestHelper.pixel_at(TestHelper.decode_png(data), 5, 5) == {255, 0, 0, 255}
+    end
+
+    test "defaults to English" do
+      assert {:ok, data} = Converter.convert(@switch_svg, format: :png)
+      assert TestHelper.pixel_at(TestHelper.decode_png(data), 5, 5) == {0, 0, 255, 255}
+    end
+
+    test "uses the first matching child, falling back to one without systemLanguage" do
+      assert {:ok, data} = Converter.convert(@switch_svg, format: :png, languages: ["fr", "en"])
+      assert TestHelper.pixel_at(TestHelper.decode_png(data), 5, 5) == {0, 0, 255, 255}
+
+      assert {:ok, data} = Converter.convert(@switch_svg, format: :png, languages: ["fr"])
+      assert TestHelper.pixel_at(TestHelper.decode_png(data), 5, 5) == {0, 255, 0, 255}
+    end
+
+    test "matches a language prefix of systemLanguage" do
+      svg = String.replace(@switch_svg, ~s(systemLanguage="de"), ~s(systemLanguage="de-AT"))
+      assert {:ok, data} = Converter.convert(svg, format: :png, languages: ["de"])
+      assert TestHelper.pixel_at(TestHelper.decode_png(data), 5, 5) == {255, 0, 0, 255}
+    end
+
+    test "returns error for invalid languages" do
+      for languages <- ["de", [:de], [""]] do
+        assert {:error, {:invalid_option, message}} =
+                 Converter.convert(@switch_svg, format: :png, languages: languages)
+
+        assert message =~ "languages must be"
+      end
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(@switch_svg, format: :png, languages: ["de DE"])
+
+      assert message =~ "BCP 47"
+    end
+  end
+
+  describe "convert/2 - antialias" do
+    @aa_circle_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="40" height="30">
+      <circle cx="20" cy="15" r="10" fill="#FF8800"/>
+    </svg>
+    """
+
+    test "edge pixels are partially covered by default" do
+      assert {:ok, data} = Converter.convert(@aa_circle_svg, format: :png)
+      assert length(TestHelper.channel_values(data, :alpha)) > 2
+    end
+
+    test "false leaves every pixel fully opaque or fully transparent" do
+      assert {:ok, data} = Converter.convert(@aa_circle_svg, format: :png, antialias: false)
+      assert TestHelper.channel_values(data, :alpha) == [0, 255]
+    end
+
+    test "false overrides shape-rendering in the SVG" do
+      svg =
+        String.replace(
+          @aa_circle_svg,
+          "<circle",
+          ~s(<circle style="shape-rendering: geometricPrecision !important")
+        )
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, antialias: false)
+      assert TestHelper.channel_values(data, :alpha) == [0, 255]
+    end
+
+    test "false applies to clip paths" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="40" height="30">
+        <clipPath id="clip"><circle cx="20" cy="15" r="10"/></clipPath>
+        <rect width="40" height="30" fill="#FF8800" clip-path="url(#clip)"/>
+      </svg>
+      """
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, antialias: false)
+      assert TestHelper.channel_values(data, :alpha) == [0, 255]
+    end
+
+    test "returns error for a non-boolean" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(@aa_circle_svg, format: :png, antialias: "no")
+
+      assert message =~ "antialias"
+    end
+  end
+
+  describe "convert/2 - zoom and offset" do
+    # A black square whose middle half is split into red, green, blue and yellow quarters
+    @quadrants_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+      <rect width="100" height="100" fill="#000000"/>
+      <rect x="25" y="25" width="25" height="25" fill="#FF0000"/>
+      <rect x="50" y="25" width="25" height="25" fill="#00FF00"/>
+      <rect x="25" y="50" width="25" height="25" fill="#0000FF"/>
+      <rect x="50" y="50" width="25" height="25" fill="#FFFF00"/>
+    </svg>
+    """
+
+    test "zoom 2.0 with a center offset renders the middle of the SVG enlarged" do
+      assert {:ok, %{data: data, width: 100, height: 100}} =
+               Converter.convert_with_info(@quadrants_svg,
+                 format: :png,
+                 zoom: 2.0,
+                 offset_x: 25,
+                 offset_y: 25
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 10, 10) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 90, 10) == {0, 255, 0, 255}
+      assert TestHelper.pixel_at(image, 10, 90) == {0, 0, 255, 255}
+      assert TestHelper.pixel_at(image, 90, 90) == {255, 255, 0, 255}
+    end
+
+    test "composes with the scale to the requested size" do
+      assert {:ok, data} =
+               Converter.convert(@quadrants_svg,
+                 format: :png,
+                 width: 200,
+                 zoom: 2.0,
+                 offset_x: 25,
+                 offset_y: 25
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 20, 20) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 180, 180) == {255, 255, 0, 255}
+    end
+
+    test "clips content moved off the canvas" do
+      assert {:ok, data} = Converter.convert(@quadrants_svg, format: :png, offset_x: 50)
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 10, 10) == {0, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 10, 50) == {255, 255, 0, 255}
+      assert {_r, _g, _b, 0} = TestHelper.pixel_at(image, 75, 50)
+    end
+
+    test "returns error for invalid values" do
+      for {key, value, expected} <- [
+            {:zoom, 0, "zoom must be a positive number"},
+            {:zoom, -2.0, "zoom must be a positive number"},
+            {:offset_x, "10", "offset_x must be a number"},
+            {:offset_y, :center, "offset_y must be a number"}
+          ] do
+        assert {:error, {:invalid_option, message}} =
+                 Converter.convert(@quadrants_svg, [{:format, :png}, {key, value}])
+
+        assert message =~ expected
+      end
+    end
+  end
+
+  describe "convert/2 - view_box" do
+    # Red left half and blue right half, drawn in a 10x10 user space scaled to 100x100
+    @halves_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100" viewBox="0 0 10 10">
+      <rect width="5" height="10" fill="#FF0000"/>
+      <rect x="5" width="5" height="10" fill="#0000FF"/>
+    </svg>
+    """
+
+    test "renders the window given in user units at the SVG's scale" do
+      assert {:ok, %{data: data, width: 50, height: 100}} =
+               Converter.convert_with_info(@halves_svg, format: :png, view_box: {5, 0, 5, 10})
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 0, 50) == {0, 0, 255, 255}
+      assert TestHelper.pixel_at(image, 49, 50) == {0, 0, 255, 255}
+    end
+
+    test "fits a shifted window to the requested size" do
+      assert {:ok, data} =
+               Converter.convert(@halves_svg,
+                 format: :png,
+                 width: 100,
+                 height: 100,
+                 view_box: {2.5, 0, 5, 10}
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 10, 50) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 40, 50) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 60, 50) == {0, 0, 255, 255}
+      assert TestHelper.pixel_at(image, 90, 50) == {0, 0, 255, 255}
+    end
+
+    test "maps user units through the viewBox of an SVG with a DOCTYPE" do
+      svg =
+        ~s(<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" ) <>
+          ~s("http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">) <> @halves_svg
+
+      assert {:ok, %{data: data, width: 50, height: 100}} =
+               Converter.convert_with_info(svg, format: :png, view_box: {5, 0, 5, 10})
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 0, 50) == {0, 0, 255, 255}
+    end
+
+    test "returns error for an invalid view_box or together with node_id" do
+      for value <- [{0, 0, 0, 10}, {0, 0, 10, -1}, {0, 0, 10}, "0 0 10 10"] do
+        assert {:error, {:invalid_option, message}} =
+                 Converter.convert(@halves_svg, format: :png, view_box: value)
+
+        assert message =~ "view_box must be a {min_x, min_y, width, height} tuple"
+      end
+
+      svg = String.replace(@halves_svg, "<rect width", ~s(<rect id="left" width))
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, view_box: {0, 0, 5, 5}, node_id: "left")
+
+      assert message =~ "can't be combined with node_id"
+    end
+  end
+
+  describe "convert/2 - timeout_ms" do
+    @blurred_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="1000" height="1000">
+      <filter id="blur"><feGaussianBlur stdDeviation="30"/></filter>
+      <g filter="url(#blur)">
+        <rect width="1000" height="1000" fill="#FF0000"/>
+        <circle cx="500" cy="500" r="400" fill="#0000FF"/>
+      </g>
+    </svg>
+    """
+
+    test "returns a timeout error when the render takes longer" do
+      assert {:error, {:timeout, message}} =
+               Converter.convert(@blurred_svg, format: :png, timeout_ms: 1)
+
+      assert message == "Conversion timed out after 1 ms"
+
+      assert {:error, {:timeout, _message}} =
+               Converter.convert_with_info(@blurred_svg, format: :jpg, timeout_ms: 1)
+    end
+
+    test "returns the same image when the render finishes in time" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, timeout_ms: 60_000)
+      assert {:ok, ^data} = Converter.convert(svg, format: :png)
+    end
+
+    test "times out individual batch jobs" do
+      assert [{:error, {:timeout, _}}, {:ok, png}] =
+               Converter.convert_batch([
+                 {@blurred_svg, format: :png, timeout_ms: 1},
+                 {TestHelper.simple_svg(), format: :png, timeout_ms: 60_000}
+               ])
+
+      assert TestHelper.valid_png?(png)
+    end
+
+    test "returns error for a non-positive or non-integer timeout" do
+      for timeout_ms <- [0, -5, 1.5, "100"] do
+        assert {:error, {:invalid_option, message}} =
+                 Converter.convert(TestHelper.simple_svg(), format: :png, timeout_ms: timeout_ms)
+
+        assert message =~ "timeout_ms must be a positive integer"
+      end
+    end
+  end
+
+  describe "convert/2 - default font" do
+    @describetag :fonts
+
+    test "renders text with system fonts" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.text_svg(), format: :png, font_family: "DejaVu Sans")
+
+      refute TestHelper.fully_transparent?(TestHelper.decode_png(data))
+    end
+
+    test "font_family changes how unstyled text renders" do
+      svg = TestHelper.text_svg()
+
+      assert {:ok, sans} = Converter.convert(svg, format: :png, font_family: "DejaVu Sans")
+      assert {:ok, serif} = Converter.convert(svg, format: :png, font_family: "DejaVu Serif")
+
+      assert TestHelper.decode_png(sans) != TestHelper.decode_png(serif)
+    end
+
+    test "font_size changes how unstyled text renders" do
+      svg = TestHelper.text_svg()
+
+      assert {:ok, default} = Converter.convert(svg, format: :png, font_family: "DejaVu Sans")
+
+      assert {:ok, large} =
+               Converter.convert(svg, format: :png, font_family: "DejaVu Sans", font_size: 24)
+
+      assert TestHelper.decode_png(default) != TestHelper.decode_png(large)
+    end
+  end
+
+  describe "convert/2 - system fonts" do
+    test "text is not rendered when system fonts are disabled" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.text_svg(),
+                 format: :png,
+                 font_family: "DejaVu Sans",
+                 load_system_fonts: false
+               )
+
+      assert TestHelper.fully_transparent?(TestHelper.decode_png(data))
+    end
+
+    test "renders text with fonts from font_dirs" do
+      assert {:ok, data} =
+               Converter.convert(TestHelper.text_svg(),
+                 format: :png,
+                 font_family: "DejaVu Sans Mono",
+                 load_system_fonts: false,
+                 font_dirs: [TestHelper.fonts_dir()]
+               )
+
+      refute TestHelper.fully_transparent?(TestHelper.decode_png(data))
+    end
+
+    test "skips missing font directories with a warning" do
+      log =
+        ExUnit.CaptureLog.capture_log(fn ->
+          assert {:ok, data} =
+                   Converter.convert(TestHelper.text_svg(),
+                     format: :png,
+                     font_family: "DejaVu Sans Mono",
+                     load_system_fonts: false,
+                     font_dirs: ["test/fixtures/missing", TestHelper.fonts_dir()]
+                   )
+
+          refute TestHelper.fully_transparent?(TestHelper.decode_png(data))
+        end)
+
+      assert log =~ ~S(Skipping font directory "test/fixtures/missing")
+    end
+
+    test "returns error for invalid font_dirs" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.text_svg(), format: :png, font_dirs: "fonts")
+
+      assert message =~ "font_dirs must be a list of strings"
+    end
+
+    test "returns error for non-boolean load_system_fonts" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.text_svg(), format: :png, load_system_fonts: nil)
+
+      assert message =~ "load_system_fonts must be a boolean"
+    end
+  end
+
+  describe "convert/2 - node_id" do
+    test "renders only the requested element, sized from its bounding box" do
+      assert {:ok, %{data: data, width: 20, height: 10}} =
+               Converter.convert_with_info(TestHelper.sprite_svg(),
+                 format: :png,
+                 node_id: "red-icon"
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 0, 0) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 19, 9) == {255, 0, 0, 255}
+    end
+
+    test "scales the element to the requested size" do
+      assert {:ok, %{width: 80, height: 40}} =
+               Converter.convert_with_info(TestHelper.sprite_svg(),
+                 format: :png,
+                 width: 80,
+                 node_id: "red-icon"
+               )
+    end
+
+    test "applies ancestor transforms" do
+      assert {:ok, %{data: data, width: 30, height: 30}} =
+               Converter.convert_with_info(TestHelper.sprite_svg(),
+                 format: :png,
+                 node_id: "green-icon"
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 15, 15) == {0, 255, 0, 255}
+      # Corners fall outside the circle and the background isn't rendered
+      assert {_r, _g, _b, 0} = TestHelper.pixel_at(image, 0, 0)
+    end
+
+    test "returns error for an unknown id" do
+      assert {:error, {:node_not_found, message}} =
+               Converter.convert(TestHelper.sprite_svg(), format: :png, node_id: "missing")
+
+      assert message =~ ~S(no element with id "missing")
+    end
+
+    test "returns error for a non-string node_id" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.sprite_svg(), format: :png, node_id: :icon)
+
+      assert message =~ "node_id must be a non-empty string"
+    end
+  end
+
+  describe "validate/2" do
+    test "returns :ok for a well-formed SVG" do
+      assert :ok = Converter.validate(TestHelper.simple_svg())
+      assert :ok = Converter.validate(:zlib.gzip(TestHelper.simple_svg()))
+    end
+
+    test "accepts an SVG without a usable intrinsic size" do
+      assert :ok = Converter.validate(~s(<svg xmlns="http://www.w3.org/2000/svg" width="100%"/>))
+    end
+
+    test "returns a descriptive error for a broken SVG" do
+      broken = ~s(<svg xmlns="http://www.w3.org/2000/svg"><rect></svg>)
+
+      assert {:error, {:parse_failed, message}} = Converter.validate(broken)
+      assert message =~ "expected 'rect' tag, not 'svg' at 1:47"
+    end
+
+    test "applies replacements before parsing" do
+      broken = ~s(<svg xmlns="http://www.w3.org/2000/svg"><rect></svg>)
+
+      assert :ok = Converter.validate(broken, replacements: %{"<rect>" => "<rect/>"})
+    end
+  end
+
+  describe "element_ids/2" do
+    test "returns exactly the ids of the groups in document order" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="30" height="10">
+        <defs>
+          <linearGradient id="fade"><stop offset="0" stop-color="#FF0000"/></linearGradient>
+        </defs>
+        <g id="first"><rect width="10" height="10" fill="url(#fade)"/></g>
+        <g id="second"><rect x="10" width="10" height="10"/></g>
+        <g id="third"><rect x="20" width="10" height="10"/></g>
+        <g><rect width="5" height="5"/></g>
+      </svg>
+      """
+
+      assert {:ok, ["first", "second", "third"]} = Converter.element_ids(svg)
+    end
+
+    test "includes nested elements and every id can be rendered" do
+      svg = TestHelper.sprite_svg()
+
+      assert {:ok, ids} = Converter.element_ids(svg)
+      assert ids == ["background", "red-icon", "green-icon"]
+
+      for id <- ids do
+        assert {:ok, _data} = Converter.convert(svg, format: :png, node_id: id)
+      end
+    end
+
+    test "returns error for invalid SVG" do
+      assert {:error, {:parse_failed, _}} = Converter.element_ids("not an svg")
+    end
+  end
+
+  describe "complexity/2" do
+    @font_opts [font_dirs: [TestHelper.fonts_dir()], load_system_fonts: false]
+
+    test "counts the paths, groups, text nodes and images of the rendered tree" do
+      dot = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="1" height="1">
+        <rect width="1" height="1"/>
+      </svg>
+      """
+
+      assert {:ok, dot_png} = Converter.convert(dot, format: :png)
+
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+        <defs><clipPath id="clip"><circle r="5"/></clipPath></defs>
+        <g id="icons">
+          <rect width="10" height="10"/>
+          <circle cx="20" cy="5" r="5" clip-path="url(#clip)"/>
+          <path d="M0 20 L10 30" stroke="black"/>
+        </g>
+        <g opacity="0.5"><rect y="30" width="10" height="10"/></g>
+        <text x="0" y="40" font-family="DejaVu Sans Mono">Hi</text>
+        <image width="4" height="4" href="data:image/png;base64,#{Base.encode64(dot_png)}"/>
+      </svg>
+      """
+
+      # The clip path's circle is a path too, and usvg wraps the clipped circle and the image
+      # in groups of their own
+      assert {:ok, %{paths: 5, groups: 4, texts: 1, images: 1}} =
+               Converter.complexity(svg, @font_opts)
+
+      assert {:ok, %{texts: 0}} = Converter.complexity(svg, load_system_fonts: false)
+    end
+
+    test "returns error for invalid SVG" do
+      assert {:error, {:parse_failed, _}} = Converter.complexity("not an svg")
+    end
+  end
+
+  describe "convert/2 - supersampling" do
+    test "smooths edges by rendering at a multiple of the output size" do
+      # Anti-aliasing is disabled for this line, so without supersampling every pixel is
+      # either fully covered or empty
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+        <line x1="0" y1="3" x2="40" y2="37" stroke="#000000" shape-rendering="crispEdges"/>
+      </svg>
+      """
+
+      alphas = fn ssaa ->
+        assert {:ok, data} = Converter.convert(svg, format: :png, ssaa: ssaa)
+        assert {40, 40} = TestHelper.get_png_dimensions(data)
+        {_width, _height, pixels} = TestHelper.decode_png(data)
+        for <<_r, _g, _b, a <- pixels>>, do: a
+      end
+
+      partial_coverage = fn values -> Enum.count(values, &(&1 > 0 and &1 < 255)) end
+
+      assert partial_coverage.(alphas.(1)) == 0
+      assert partial_coverage.(alphas.(4)) > partial_coverage.(alphas.(2))
+      assert partial_coverage.(alphas.(2)) > 0
+    end
+
+    test "keeps solid areas and the requested dimensions unchanged" do
+      svg = TestHelper.solid_svg("#FF0000")
+
+      assert {:ok, %{data: data, width: 30, height: 15}} =
+               Converter.convert_with_info(svg, format: :png, width: 30, height: 15, ssaa: 4)
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 0, 0) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 29, 14) == {255, 0, 0, 255}
+
+      assert {:ok, %{width: 15, height: 30}} =
+               Converter.convert_with_info(svg,
+                 format: :jpg,
+                 width: 30,
+                 height: 15,
+                 ssaa: 2,
+                 rotate: 90
+               )
+    end
+
+    test "resize_filter nearest keeps the edges blockier than lanczos3" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+        <circle cx="20" cy="20" r="15" fill="#000000" shape-rendering="crispEdges"/>
+      </svg>
+      """
+
+      partial_coverage = fn filter ->
+        assert {:ok, data} = Converter.convert(svg, format: :png, ssaa: 4, resize_filter: filter)
+        {_width, _height, pixels} = TestHelper.decode_png(data)
+        Enum.count(for <<_r, _g, _b, a <- pixels>>, a > 0 and a < 255, do: a)
+      end
+
+      # Nearest picks a single one of the 16 samples per pixel, so edges stay hard
+      assert partial_coverage.(:nearest) == 0
+      assert partial_coverage.(:lanczos3) > 0
+      assert partial_coverage.(:triangle) > 0
+      assert partial_coverage.(:catmull) > 0
+    end
+
+    test "returns error for an unknown resize_filter" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(),
+                 format: :png,
+                 ssaa: 2,
+                 resize_filter: :bicubic
+               )
+
+      assert message =~ "resize_filter must be one of"
+    end
+
+    test "returns error for a factor outside 1 to 8" do
+      for ssaa <- [0, 9, 2.0] do
+        assert {:error, {:invalid_option, message}} =
+                 Converter.convert(TestHelper.simple_svg(), format: :png, ssaa: ssaa)
+
+        assert message =~ "ssaa must be an integer between 1 and 8"
+      end
+    end
+  end
+
+  describe "convert/2 - grayscale" do
+    test "PNG output has equal red, green and blue values and keeps alpha" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+        <rect width="10" height="10" fill="#FF0000"/>
+        <rect x="10" width="10" height="10" fill="#00FF00" fill-opacity="0.5"/>
+      </svg>
+      """
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, grayscale: true)
+      image = TestHelper.decode_png(data)
+
+      assert {gray, gray, gray, 255} = TestHelper.pixel_at(image, 5, 5)
+      assert gray not in [0, 255]
+      assert {gray, gray, gray, alpha} = TestHelper.pixel_at(image, 15, 5)
+      assert alpha in 127..128
+
+      assert {:ok, color} = Converter.convert(svg, format: :png)
+      assert TestHelper.pixel_at(TestHelper.decode_png(color), 5, 5) == {255, 0, 0, 255}
+    end
+
+    test "JPEG output is single-channel" do
+      svg = TestHelper.solid_svg("#0000FF")
+
+      assert {:ok, gray} = Converter.convert(svg, format: :jpg, grayscale: true)
+      assert TestHelper.valid_format?(gray, :jpg)
+      assert TestHelper.jpeg_components(gray) == 1
+      assert TestHelper.get_jpeg_dimensions(gray) == {10, 10}
+
+      assert {:ok, color} = Converter.convert(svg, format: :jpg)
+      assert TestHelper.jpeg_components(color) == 3
+    end
+
+    test "returns error for a non-boolean value" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, grayscale: "yes")
+
+      assert message =~ "grayscale must be a boolean"
+    end
+  end
+
+  describe "convert/2 - palette" do
+    setup do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="64" height="16">
+        <defs>
+          <linearGradient id="fade">
+            <stop offset="0" stop-color="#000"/>
+            <stop offset="1" stop-color="#FFF"/>
+          </linearGradient>
+        </defs>
+        <rect width="64" height="16" fill="url(#fade)"/>
+      </svg>
+      """
+
+      %{svg: svg, palette: ["black", "#555555", "#AAAAAA", "white"]}
+    end
+
+    test "maps every pixel to a member of the palette", %{svg: svg, palette: palette} do
+      members = [{0, 0, 0, 255}, {85, 85, 85, 255}, {170, 170, 170, 255}, {255, 255, 255, 255}]
+
+      for dither <- [false, true] do
+        assert {:ok, data} =
+                 Converter.convert(svg, format: :png, palette: palette, palette_dither: dither)
+
+        {_width, _height, pixels} = TestHelper.decode_png(data)
+        colors = for <<r, g, b, a <- pixels>>, uniq: true, do: {r, g, b, a}
+        assert Enum.sort(colors) == members
+      end
+    end
+
+    test "applies to unencoded pixels too", %{svg: svg, palette: palette} do
+      assert {:ok, %{data: data}} = Converter.convert_to_rgba(svg, channels: 3, palette: palette)
+
+      colors = for <<r, g, b <- data>>, uniq: true, do: {r, g, b}
+      assert Enum.sort(colors) == [{0, 0, 0}, {85, 85, 85}, {170, 170, 170}, {255, 255, 255}]
+    end
+
+    @tag :tmp_dir
+    test "applies to PNG files streamed to disk",
+         %{svg: svg, palette: palette, tmp_dir: tmp_dir} do
+      path = Path.join(tmp_dir, "palette.png")
+
+      for dither <- [false, true] do
+        opts = [format: :png, palette: palette, palette_dither: dither]
+
+        assert :ok = Converter.convert_to_file(svg, path, opts)
+        assert {:ok, data} = Converter.convert(svg, opts)
+        assert TestHelper.decode_png(File.read!(path)) == TestHelper.decode_png(data)
+      end
+
+      assert {:error, {:invalid_color, _}} =
+               Converter.convert_to_file(svg, path, format: :png, palette: ["nope"])
+    end
+
+    test "applies to animated GIF frames", %{svg: svg} do
+      assert {:ok, gif} =
+               Converter.convert_to_animated_gif([svg, svg], palette: ["black", "white"])
+
+      # Render the GIF's first frame back to PNG to read its pixels
+      embedded = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="64" height="16">
+        <image width="64" height="16" href="data:image/gif;base64,#{Base.encode64(gif)}"/>
+      </svg>
+      """
+
+      assert {:ok, png} = Converter.convert(embedded, format: :png)
+      {_width, _height, pixels} = TestHelper.decode_png(png)
+      colors = for <<r, g, b, a <- pixels>>, uniq: true, do: {r, g, b, a}
+      assert Enum.sort(colors) == [{0, 0, 0, 255}, {255, 255, 255, 255}]
+    end
+
+    test "returns error for an invalid palette color", %{svg: svg} do
+      assert {:error, {:invalid_color, message}} =
+               Converter.convert(svg, format: :png, palette: ["black", "nope"])
+
+      assert message =~ ~S(Invalid color: "nope")
+    end
+  end
+
+  describe "convert/2 - opacity" do
+    test "halves the alpha of opaque pixels at 0.5" do
+      svg = TestHelper.solid_svg("#FF0000")
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, opacity: 0.5)
+      assert {_r, 0, 0, alpha} = TestHelper.pixel_at(TestHelper.decode_png(data), 5, 5)
+      assert alpha in 127..128
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, opacity: 0)
+      assert TestHelper.fully_transparent?(TestHelper.decode_png(data))
+
+      assert Converter.convert(svg, format: :png, opacity: 1) ==
+               Converter.convert(svg, format: :png)
+    end
+
+    test "fades the content over an opaque background when flattened" do
+      svg = TestHelper.solid_svg("#FF0000")
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 opacity: 0.5,
+                 flatten: true,
+                 background_color: "FFFFFF"
+               )
+
+      assert {255, green, blue, 255} = TestHelper.pixel_at(TestHelper.decode_png(data), 5, 5)
+      assert green in 127..128 and blue in 127..128
+    end
+
+    test "returns error for values outside 0 to 1" do
+      for opacity <- [-0.1, 1.5, "0.5"] do
+        assert {:error, {:invalid_option, message}} =
+                 Converter.convert(TestHelper.simple_svg(), format: :png, opacity: opacity)
+
+        assert message =~ "opacity must be a number between 0 and 1"
+      end
+    end
+  end
+
+  describe "convert/2 - bit_depth" do
+    test "16-bit PNG output reports depth 16 in its header" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, width: 50, bit_depth: 16)
+      assert TestHelper.valid_format?(data, :png)
+      assert TestHelper.png_bit_depth(data) == 16
+      assert TestHelper.get_png_dimensions(data) == {50, 50}
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, width: 50, bit_depth: 8)
+      assert TestHelper.png_bit_depth(data) == 8
+    end
+
+    test "returns error for 16-bit output in other formats" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :jpg, bit_depth: 16)
+
+      assert message =~ "16-bit output is only supported for png"
+    end
+
+    test "returns error for unsupported bit depths" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, bit_depth: 12)
+
+      assert message =~ "bit_depth must be 8 or 16"
+    end
+  end
+
+  describe "convert/2 - scale" do
+    test "multiplies the intrinsic size" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="30" height="20">
+        <rect width="30" height="20" fill="#FF0000"/>
+      </svg>
+      """
+
+      assert {:ok, data} = Converter.convert(svg, format: :png)
+      assert TestHelper.get_png_dimensions(data) == {30, 20}
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, scale: 2.0)
+      assert TestHelper.get_png_dimensions(data) == {60, 40}
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, scale: 0.5)
+      assert TestHelper.get_png_dimensions(data) == {15, 10}
+
+      # 17.4x11.6 rounds to the nearest pixels
+      assert {:ok, data} = Converter.convert(svg, format: :png, scale: 0.58)
+      assert TestHelper.get_png_dimensions(data) == {17, 12}
+    end
+
+    test "returns error when combined with width or height" do
+      svg = TestHelper.simple_svg()
+
+      for size <- [[width: 100], [height: 100]] do
+        assert {:error, {:invalid_option, message}} =
+                 Converter.convert(svg, [format: :png, scale: 2] ++ size)
+
+        assert message =~ "can't be combined with width or height"
+      end
+    end
+
+    test "returns error for a non-positive scale" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, scale: 0)
+
+      assert message =~ "scale must be a positive number"
+    end
+  end
+
+  describe "convert/2 - dpr" do
+    test "multiplies the intrinsic size and percentages but not explicit pixels" do
+      svg = ~s(<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50"/>)
+
+      assert {:ok, %{width: 200, height: 100}} =
+               Converter.convert_with_info(svg, format: :png, dpr: 2)
+
+      assert {:ok, %{width: 150, height: 75}} =
+               Converter.convert_with_info(svg, format: :png, width: "75%", dpr: 2)
+
+      assert {:ok, %{width: 80, height: 40}} =
+               Converter.convert_with_info(svg, format: :png, width: 80, dpr: 2)
+    end
+
+    test "returns error for a non-positive dpr or one combined with scale" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_option, message}} = Converter.convert(svg, format: :png, dpr: 0)
+      assert message =~ "dpr must be a positive number"
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, dpr: 2, scale: 2)
+
+      assert message =~ "can't be combined with scale"
+    end
+  end
+
+  describe "convert/2 - dither" do
+    # A smooth two-axis gradient needs far more than the 256 colors a GIF palette holds
+    @gradient_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="128" height="128">
+      <defs>
+        <linearGradient id="hue">
+          <stop offset="0" stop-color="#FF0000"/>
+          <stop offset="0.5" stop-color="#00FF00"/>
+          <stop offset="1" stop-color="#0000FF"/>
+        </linearGradient>
+        <linearGradient id="shade" x2="0" y2="1">
+          <stop offset="0" stop-color="#000000" stop-opacity="0"/>
+          <stop offset="1" stop-color="#000000"/>
+        </linearGradient>
+      </defs>
+      <rect width="128" height="128" fill="url(#hue)"/>
+      <rect width="128" height="128" fill="url(#shade)"/>
+    </svg>
+    """
+
+    test "dithers quantized GIF output" do
+      assert {:ok, plain} = Converter.convert(@gradient_svg, format: :gif)
+      assert {:ok, dithered} = Converter.convert(@gradient_svg, format: :gif, dither: true)
+
+      assert TestHelper.valid_format?(dithered, :gif)
+      assert TestHelper.get_gif_dimensions(dithered) == {128, 128}
+      assert dithered != plain
+
+      # Dithering trades runs of equal indices for noise, which compresses worse
+      assert byte_size(dithered) > byte_size(plain)
+    end
+
+    test "leaves GIFs that fit the palette unchanged" do
+      svg = TestHelper.simple_svg()
+
+      assert Converter.convert(svg, format: :gif, width: 50, dither: true) ==
+               Converter.convert(svg, format: :gif, width: 50)
+    end
+
+    test "returns error for a non-boolean value" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :gif, dither: "yes")
+
+      assert message =~ "dither"
+    end
+  end
+
+  describe "convert/2 - alpha_threshold" do
+    @circle_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+      <circle cx="20" cy="20" r="15" fill="#0000FF"/>
+    </svg>
+    """
+
+    test "makes more edge pixels transparent in GIF output as it rises" do
+      assert {:ok, default} = Converter.convert(@circle_svg, format: :gif)
+      assert {:ok, low} = Converter.convert(@circle_svg, format: :gif, alpha_threshold: 1)
+      assert {:ok, high} = Converter.convert(@circle_svg, format: :gif, alpha_threshold: 250)
+
+      assert Converter.convert(@circle_svg, format: :gif, alpha_threshold: 128) == {:ok, default}
+
+      # Render each GIF back to RGBA through an SVG image to count its transparent pixels
+      transparent_pixels = fn gif ->
+        svg = """
+        <svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+          <image width="40" height="40" href="data:image/gif;base64,#{Base.encode64(gif)}"/>
+        </svg>
+        """
+
+        assert {:ok, %{data: rgba}} = Converter.convert_to_rgba(svg)
+        length(for <<_::24, alpha <- rgba>>, alpha == 0, do: alpha)
+      end
+
+      assert transparent_pixels.(low) < transparent_pixels.(default)
+      assert transparent_pixels.(default) < transparent_pixels.(high)
+    end
+
+    test "only thresholds ICO entries when given" do
+      assert {:ok, smooth} = Converter.convert_to_ico(@circle_svg, sizes: [32])
+      assert {:ok, thresholded} =
+               Converter.convert_to_ico(@circle_svg, sizes: [32], alpha_threshold: 128)
+
+      assert thresholded != smooth
+    end
+
+    test "returns error for values outside 1..255" do
+      for value <- [0, 256, 0.5, "128"] do
+        assert {:error, {:invalid_option, message}} =
+                 Converter.convert(@circle_svg, format: :gif, alpha_threshold: value)
+
+        assert message =~ "alpha_threshold must be an integer between 1 and 255"
+      end
+    end
+  end
+
+  describe "convert/2 - max_pixels" do
+    test "rejects a huge render quickly under the default limit" do
+      svg = TestHelper.simple_svg()
+
+      {micros, result} =
+        :timer.tc(fn -> Converter.convert(svg, format: :png, width: 100_000, height: 100_000) end)
+
+      assert {:error, {:invalid_dimensions, message}} = result
+      assert message =~ "100000x100000"
+      assert message =~ "more than the limit of 67108864"
+      assert micros < 1_000_000
+    end
+
+    test "applies a custom limit to the output size" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, _data} =
+               Converter.convert(svg, format: :png, width: 10, height: 10, max_pixels: 100)
+
+      assert {:error, {:invalid_dimensions, message}} =
+               Converter.convert(svg, format: :png, width: 11, height: 10, max_pixels: 100)
+
+      assert message =~ "more than the limit of 100"
+    end
+
+    test "counts supersampling and padding toward the limit" do
+      svg = TestHelper.simple_svg()
+      opts = [format: :png, width: 10, height: 10, max_pixels: 100]
+
+      assert {:error, {:invalid_dimensions, _message}} = Converter.convert(svg, [ssaa: 2] ++ opts)
+      assert {:error, {:invalid_dimensions, _message}} =
+               Converter.convert(svg, [padding: 1] ++ opts)
+    end
+
+    test "returns error for a non-positive limit" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, max_pixels: 0)
+
+      assert message =~ "max_pixels must be a positive integer"
+    end
+  end
+
+  describe "convert/2 - tree cache" do
+    test "repeated cached conversions are byte-identical to uncached ones" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, uncached} = Converter.convert(svg, format: :png, width: 120)
+      assert {:ok, first} = Converter.convert(svg, format: :png, width: 120, cache: true)
+      assert {:ok, second} = Converter.convert(svg, format: :png, width: 120, cache: true)
+      assert first == uncached
+      assert second == first
+    end
+
+    test "a cached tree is reused across sizes and formats" do
+      svg = TestHelper.complex_svg()
+
+      for opts <- [[format: :png, width: 60], [format: :jpg, width: 200], [format: :webp]] do
+        assert Converter.convert(svg, [cache: true] ++ opts) == Converter.convert(svg, opts)
+      end
+    end
+
+    test "the cache is keyed by the SVG after replacements" do
+      svg = TestHelper.replaceable_svg()
+      opts = [format: :png, width: 100, cache: true]
+
+      assert {:ok, original} = Converter.convert(svg, opts)
+      assert {:ok, replaced} = Converter.convert(svg, [replacements: %{"blue" => "red"}] ++ opts)
+      assert replaced != original
+
+      assert {:ok, ^replaced} =
+               Converter.convert(svg, [replacements: %{"blue" => "red"}, cache: false] ++ opts)
+
+      assert {:ok, ^original} = Converter.convert(svg, opts)
+    end
+
+    test "returns error for non-boolean cache" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, cache: "yes")
+
+      assert message =~ "cache must be a boolean"
+    end
+  end
+
+  describe "convert_with_info/2" do
+    test "returns the data together with explicit dimensions" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, %{data: data, width: 300, height: 200}} =
+               Converter.convert_with_info(svg, format: :png, width: 300, height: 200)
+
+      assert {300, 200} = TestHelper.get_png_dimensions(data)
+    end
+
+    test "reports the derived height when only width is given" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+        <rect width="200" height="100" fill="#FF0000"/>
+      </svg>
+      """
+
+      assert {:ok, %{width: 400, height: 200}} =
+               Converter.convert_with_info(svg, format: :jpg, width: 400)
+    end
+
+    test "returns the same bytes as convert/2" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :webp, width: 150)
+      assert {:ok, %{data: ^data}} = Converter.convert_with_info(svg, format: :webp, width: 150)
+    end
+
+    test "returns the byte size of the data" do
+      for format <- [:png, :jpg, :gif] do
+        assert {:ok, %{data: data, byte_size: size}} =
+                 Converter.convert_with_info(TestHelper.complex_svg(), format: format)
+
+        assert size == byte_size(data)
+      end
+    end
+
+    test "has no warnings for a faithful render" do
+      assert {:ok, %{warnings: []}} =
+               Converter.convert_with_info(TestHelper.simple_svg(), format: :png)
+    end
+
+    test "warns about unresolved url() and href references" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"
+           width="20" height="20">
+        <defs><linearGradient id="fade"/></defs>
+        <rect width="20" height="20" fill="url(#glow)" stroke="url(#fade)"/>
+        <rect width="10" height="10" style="filter: url('#blur')"/>
+        <use xlink:href="#icon"/>
+      </svg>
+      """
+
+      assert {:ok, %{warnings: warnings}} = Converter.convert_with_info(svg, format: :png)
+
+      assert warnings == [
+               ~S(Unresolved reference to "#glow": no element has that id),
+               ~S(Unresolved reference to "#blur": no element has that id),
+               ~S(Unresolved reference to "#icon": no element has that id)
+             ]
+    end
+
+    test "fails on warnings in strict mode" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="20" height="20">
+        <rect width="20" height="20" fill="url(#missing)"/>
+      </svg>
+      """
+
+      assert {:ok, %{warnings: [_warning]}} = Converter.convert_with_info(svg, format: :png)
+
+      assert {:error, {:parse_failed, message}} =
+               Converter.convert(svg, format: :png, strict: true)
+
+      assert message =~ ~S(strict mode: Unresolved reference to "#missing")
+
+      doctype = ~s(<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "svg11.dtd">)
+
+      assert {:error, {:parse_failed, message}} =
+               Converter.convert(doctype <> svg, format: :png, strict: true)
+
+      assert message =~ ~S(strict mode: Unresolved reference to "#missing")
+
+      assert {:ok, _data} = Converter.convert(TestHelper.simple_svg(), format: :png, strict: true)
+    end
+
+    test "warns once about a font-family with no available font" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="100" height="40">
+        <text y="15" font-family="Missing Sans, DejaVu Sans Mono">Found</text>
+        <text y="30" font-family="Missing Sans">Lost</text>
+        <text y="30" font-family="Missing Sans">Lost again</text>
+      </svg>
+      """
+
+      assert {:ok, %{warnings: warnings}} =
+               Converter.convert_with_info(svg,
+                 format: :png,
+                 font_dirs: [TestHelper.fonts_dir()],
+                 load_system_fonts: false
+               )
+
+      assert warnings == [
+               ~S(No font found for font-family "Missing Sans"; the text was not rendered)
+             ]
+    end
+
+    test "renders embedded data URL images whatever their declared MIME type" do
+      # A 2x2 red PNG
+      png =
+        "iVBORw0KGgoAAAANSUhEUgAAAAIAAAACCAYAAABytg0kAAAAHUlEQVR4" <>
+          "AQESAO3/AP8AAP//AAD/AP8AAP//AAD/R8oH+YOsvdsAAAAASUVORK5CYII="
+
+      for mime <- ["image/png", "image/jpeg", "application/octet-stream"] do
+        svg = """
+        <svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+          <image width="10" height="10" href="data:#{mime};base64,#{png}"/>
+        </svg>
+        """
+
+        assert {:ok, %{data: data, warnings: []}} = Converter.convert_with_info(svg, format: :png)
+        assert data |> TestHelper.decode_png() |> TestHelper.pixel_at(5, 5) == {255, 0, 0, 255}
+      end
+    end
+
+    test "warns about an embedded image that can't be decoded" do
+      truncated_png = "iVBORw0KGgoAAAANSUhEUgAAAAIAAAACCAYAAABytg0kAAAAHUlEQVR4"
+
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+        <image width="10" height="10" href="data:image/png;base64,#{truncated_png}"/>
+      </svg>
+      """
+
+      assert {:ok, %{data: data, warnings: warnings}} =
+               Converter.convert_with_info(svg, format: :png)
+
+      assert warnings == ["Embedded image (PNG) could not be decoded; it was not rendered"]
+      assert data |> TestHelper.decode_png() |> TestHelper.pixel_at(5, 5) == {0, 0, 0, 0}
+    end
+
+    test "reports warnings for cached trees too" do
+      svg = ~S(<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">) <>
+              ~S(<rect width="10" height="10" fill="url(#cached-glow)"/></svg>)
+
+      for _ <- 1..2 do
+        assert {:ok, %{warnings: [warning]}} =
+                 Converter.convert_with_info(svg, format: :png, cache: true)
+
+        assert warning =~ "#cached-glow"
+      end
+    end
+
+    test "returns errors like convert/2" do
+      assert {:error, {:parse_failed, message}} =
+               Converter.convert_with_info(TestHelper.invalid_svg(), format: :png, width: 100)
+
+      assert message =~ "Failed to parse SVG"
+    end
+  end
+
+  describe "convert_to_file/3" do
+    @describetag :tmp_dir
+
+    test "writes the image to the path", %{tmp_dir: tmp_dir} do
+      path = Path.join(tmp_dir, "output.png")
+
+      assert :ok =
+               Converter.convert_to_file(TestHelper.simple_svg(), path,
+                 format: :png,
+                 width: 300,
+                 height: 200
+               )
+
+      assert File.exists?(path)
+      data = File.read!(path)
+      assert TestHelper.valid_png?(data)
+      assert {300, 200} = TestHelper.get_png_dimensions(data)
+    end
+
+    test "writes the same bytes as convert/2 and replaces an existing file",
+         %{tmp_dir: tmp_dir} do
+      svg = TestHelper.complex_svg()
+      path = Path.join(tmp_dir, "output.jpg")
+      File.write!(path, "stale")
+
+      assert :ok = Converter.convert_to_file(svg, path, format: :jpg, width: 150)
+      assert {:ok, data} = Converter.convert(svg, format: :jpg, width: 150)
+      assert File.read!(path) == data
+    end
+
+    test "streams PNGs with the same pixels and chunks as convert/2", %{tmp_dir: tmp_dir} do
+      svg = TestHelper.complex_svg()
+      path = Path.join(tmp_dir, "output.png")
+      opts = [format: :png, width: 150, metadata: [{"Title", "Logo"}], dpi_output: 300]
+
+      assert :ok = Converter.convert_to_file(svg, path, opts)
+      assert {:ok, data} = Converter.convert(svg, opts)
+
+      file = File.read!(path)
+      assert TestHelper.decode_png(file) == TestHelper.decode_png(data)
+      assert TestHelper.png_text_chunks(file) == [{"tEXt", "Title", "Logo"}]
+      assert TestHelper.png_physical_dimensions(file) == {11811, 11811, 1}
+    end
+
+    test "streams semi-transparent pixels with straight alpha", %{tmp_dir: tmp_dir} do
+      svg = TestHelper.solid_svg("#FF0000")
+      path = Path.join(tmp_dir, "output.png")
+
+      assert :ok = Converter.convert_to_file(svg, path, format: :png, opacity: 0.5)
+      assert {:ok, data} = Converter.convert(svg, format: :png, opacity: 0.5)
+
+      image = path |> File.read!() |> TestHelper.decode_png()
+      assert image == TestHelper.decode_png(data)
+      assert {255, 0, 0, _alpha} = TestHelper.pixel_at(image, 5, 5)
+    end
+
+    test "streams 16-bit PNGs", %{tmp_dir: tmp_dir} do
+      path = Path.join(tmp_dir, "output.png")
+
+      assert :ok =
+               Converter.convert_to_file(TestHelper.simple_svg(), path,
+                 format: :png,
+                 bit_depth: 16
+               )
+
+      assert TestHelper.png_bit_depth(File.read!(path)) == 16
+    end
+
+    test "writes a large PNG", %{tmp_dir: tmp_dir} do
+      path = Path.join(tmp_dir, "large.png")
+
+      assert :ok =
+               Converter.convert_to_file(TestHelper.complex_svg(), path,
+                 format: :png,
+                 width: 4000,
+                 height: 4000
+               )
+
+      data = File.read!(path)
+      assert TestHelper.valid_png?(data)
+      assert TestHelper.get_png_dimensions(data) == {4000, 4000}
+    end
+
+    test "returns write_failed when the parent directory is missing", %{tmp_dir: tmp_dir} do
+      path = Path.join([tmp_dir, "missing", "output.png"])
+
+      assert {:error, {:write_failed, message}} =
+               Converter.convert_to_file(TestHelper.simple_svg(), path, format: :png)
+
+      assert message =~ "does not exist"
+      refute File.exists?(path)
+    end
+
+    test "returns conversion errors without writing", %{tmp_dir: tmp_dir} do
+      path = Path.join(tmp_dir, "output.png")
+
+      assert {:error, {:parse_failed, _message}} =
+               Converter.convert_to_file(TestHelper.invalid_svg(), path, format: :png)
+
+      refute File.exists?(path)
+    end
+
+    test "rejects a path that isn't a string" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert_to_file(TestHelper.simple_svg(), nil, format: :png)
+
+      assert message =~ "path"
+    end
+  end
+
+  describe "convert_to_data_uri/2" do
+    test "prefixes the base64 payload with the format's MIME type" do
+      svg = TestHelper.simple_svg()
+      mime_types = [png: "image/png", jpg: "image/jpeg", jpeg: "image/jpeg", webp: "image/webp"]
+
+      for {format, mime} <- mime_types do
+        prefix = "data:#{mime};base64,"
+        assert {:ok, uri} = Converter.convert_to_data_uri(svg, format: format, width: 50)
+        assert String.starts_with?(uri, prefix)
+
+        payload = String.replace_prefix(uri, prefix, "")
+        assert {:ok, data} = Converter.convert(svg, format: format, width: 50)
+        assert Base.decode64!(payload) == data
+      end
+    end
+
+    test "returns errors like convert/2" do
+      assert {:error, {:parse_failed, _message}} =
+               Converter.convert_to_data_uri(TestHelper.invalid_svg(), format: :png)
+    end
+  end
+
+  describe "mime_type/1" do
+    test "returns the canonical MIME type of each format" do
+      for {format, mime} <- [
+            png: "image/png",
+            jpg: "image/jpeg",
+            jpeg: "image/jpeg",
+            gif: "image/gif",
+            webp: "image/webp",
+            bmp: "image/bmp",
+            tiff: "image/tiff",
+            tif: "image/tiff",
+            avif: "image/avif",
+            qoi: "image/qoi",
+            tga: "image/x-tga",
+            ppm: "image/x-portable-pixmap",
+            ff: "image/x-farbfeld",
+            farbfeld: "image/x-farbfeld",
+            exr: "image/x-exr"
+          ] do
+        assert {:ok, ^mime} = Converter.mime_type(format)
+      end
+    end
+
+    test "rejects unsupported formats like convert/2" do
+      assert {:error, {:unsupported_format, message}} = Converter.mime_type(:svg)
+      assert {:error, {:unsupported_format, ^message}} = Converter.convert("<svg/>", format: :svg)
+    end
+  end
+
+  describe "versions/0" do
+    test "reports a version for each rendering crate" do
+      versions = Converter.versions()
+
+      assert versions |> Map.keys() |> Enum.sort() == [:image, :resvg, :tiny_skia, :usvg]
+
+      for {_crate, version} <- versions do
+        assert is_binary(version) and version != ""
+      end
+    end
+  end
+
+  describe "extension/1" do
+    test "returns the canonical extension of each format" do
+      for {format, extension} <- [
+            png: "png",
+            jpg: "jpg",
+            jpeg: "jpg",
+            gif: "gif",
+            webp: "webp",
+            bmp: "bmp",
+            tiff: "tiff",
+            tif: "tiff",
+            avif: "avif",
+            qoi: "qoi",
+            tga: "tga",
+            ppm: "ppm",
+            ff: "ff",
+            farbfeld: "ff",
+            exr: "exr"
+          ] do
+        assert {:ok, ^extension} = Converter.extension(format)
+      end
+    end
+
+    test "rejects unsupported formats like convert/2" do
+      assert {:error, {:unsupported_format, message}} = Converter.extension(:svg)
+      assert {:error, {:unsupported_format, ^message}} = Converter.convert("<svg/>", format: :svg)
+    end
+  end
+
+  describe "convert_batch/2" do
+    test "returns the same bytes as convert/2 for each job, in order" do
+      jobs = [
+        {TestHelper.simple_svg(), format: :png, width: 100},
+        {TestHelper.complex_svg(), format: :jpg, width: 200, background_color: "000000"},
+        {TestHelper.simple_svg(), format: :webp, width: 50, replacements: %{"blue" => "red"}}
+      ]
+
+      expected = Enum.map(jobs, fn {svg, opts} -> Converter.convert(svg, opts) end)
+
+      assert Converter.convert_batch(jobs) == expected
+      assert Enum.all?(expected, &match?({:ok, _}, &1))
+    end
+
+    test "returns per-job errors without failing the rest of the batch" do
+      svg = TestHelper.simple_svg()
+
+      results =
+        Converter.convert_batch([
+          {svg, format: :png, width: 100},
+          {TestHelper.invalid_svg(), format: :png, width: 100},
+          {svg, format: :psd, width: 100},
+          {svg, format: :png, width: 0},
+          {svg, format: :jpg, width: 100, background_color: "notacolor"},
+          :not_a_job,
+          {svg, format: :gif, width: 60}
+        ])
+
+      assert [
+               {:ok, png},
+               {:error, {:parse_failed, _}},
+               {:error, {:unsupported_format, _}},
+               {:error, {:invalid_dimensions, _}},
+               {:error, {:invalid_color, _}},
+               {:error, {:invalid_option, message}},
+               {:ok, gif}
+             ] = results
+
+      assert TestHelper.valid_format?(png, :png)
+      assert TestHelper.valid_format?(gif, :gif)
+      assert message =~ "batch jobs must be {svg_string, opts} tuples"
+    end
+
+    test "returns an empty list for no jobs" do
+      assert Converter.convert_batch([]) == []
+    end
+
+    test "renders same-size jobs independently of each other" do
+      opaque = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect width="100" height="100" fill="#FF0000"/>
+      </svg>
+      """
+
+      transparent = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <circle cx="50" cy="50" r="20" fill="#0000FF"/>
+      </svg>
+      """
+
+      jobs =
+        for svg <- [opaque, transparent, transparent, opaque, transparent],
+            opts <- [[format: :png], [format: :png, ssaa: 2, opacity: 0.5], [format: :jpg]] do
+          {svg, opts}
+        end
+
+      expected = Enum.map(jobs, fn {svg, opts} -> Converter.convert(svg, opts) end)
+
+      # A single worker renders every job in turn, so each one reuses the previous pixmaps
+      assert Converter.convert_batch(jobs, max_concurrency: 1) == expected
+      assert Converter.convert_batch(jobs) == expected
+
+      {:ok, png} = Converter.convert_batch(jobs, max_concurrency: 1) |> Enum.at(3)
+      image = TestHelper.decode_png(png)
+      assert {_r, _g, _b, 0} = TestHelper.pixel_at(image, 0, 0)
+      assert TestHelper.pixel_at(image, 50, 50) == {0, 0, 255, 255}
+    end
+
+    test "returns identical results regardless of max_concurrency" do
+      valid_jobs =
+        for width <- [40, 80, 120, 160], format <- [:png, :jpg, :webp] do
+          {TestHelper.complex_svg(), format: format, width: width}
+        end
+
+      jobs = valid_jobs ++ [{TestHelper.invalid_svg(), format: :png, width: 100}]
+
+      expected = Converter.convert_batch(jobs)
+
+      for max_concurrency <- [1, 2, 64] do
+        assert Converter.convert_batch(jobs, max_concurrency: max_concurrency) == expected
+      end
+    end
+
+    test "returns error for invalid max_concurrency" do
+      jobs = [{TestHelper.simple_svg(), format: :png, width: 100}]
+
+      for max_concurrency <- [0, -1, 1.5, :all] do
+        assert {:error, {:invalid_option, message}} =
+                 Converter.convert_batch(jobs, max_concurrency: max_concurrency)
+
+        assert message =~ "max_concurrency must be a positive integer"
+      end
+    end
+  end
+
+  describe "convert_to_ico/2" do
+    test "packs one PNG entry per requested size" do
+      assert {:ok, ico} = Converter.convert_to_ico(TestHelper.simple_svg())
+
+      entries = TestHelper.ico_entries(ico)
+
+      assert Enum.map(entries, fn {width, height, _png} -> {width, height} end) ==
+               [{16, 16}, {32, 32}, {48, 48}]
+
+      for {width, height, png} <- entries do
+        assert TestHelper.valid_format?(png, :png)
+        assert TestHelper.get_png_dimensions(png) == {width, height}
+      end
+    end
+
+    test "supports custom sizes up to 256" do
+      assert {:ok, ico} = Converter.convert_to_ico(TestHelper.complex_svg(), sizes: [24, 256])
+
+      assert [{24, 24, small}, {256, 256, large}] = TestHelper.ico_entries(ico)
+      assert TestHelper.get_png_dimensions(small) == {24, 24}
+      assert TestHelper.get_png_dimensions(large) == {256, 256}
+    end
+
+    test "entries match convert/2 at the same size" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, ico} = Converter.convert_to_ico(svg, sizes: [32], fit: :contain)
+      assert [{32, 32, png}] = TestHelper.ico_entries(ico)
+
+      assert {:ok, ^png} =
+               Converter.convert(svg, format: :png, width: 32, height: 32, fit: :contain)
+    end
+
+    test "returns error for invalid sizes" do
+      svg = TestHelper.simple_svg()
+
+      for sizes <- [[], [0], [16, 512], [16.0], "16"] do
+        assert {:error, {:invalid_dimensions, message}} =
+                 Converter.convert_to_ico(svg, sizes: sizes)
+
+        assert message =~ "sizes must be a non-empty list of integers from 1 to 256"
+      end
+    end
+
+    test "returns errors like convert/2" do
+      assert {:error, {:parse_failed, message}} =
+               Converter.convert_to_ico(TestHelper.invalid_svg())
+
+      assert message =~ "Failed to parse SVG"
+    end
+  end
+
+  describe "convert_to_favicon/2" do
+    test "returns the ICO and PNG icons with the expected dimensions" do
+      assert {:ok, favicon} = Converter.convert_to_favicon(TestHelper.complex_svg())
+
+      assert Keyword.keys(favicon) == [:favicon, :apple_touch_icon, :icon_192, :icon_512]
+
+      assert [{16, 16, _}, {32, 32, _}, {48, 48, _}] = TestHelper.ico_entries(favicon[:favicon])
+
+      for {name, size} <- [apple_touch_icon: 180, icon_192: 192, icon_512: 512] do
+        assert TestHelper.valid_format?(favicon[name], :png)
+        assert TestHelper.get_png_dimensions(favicon[name]) == {size, size}
+      end
+    end
+
+    test "matches the individual conversions" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, favicon} = Converter.convert_to_favicon(svg, fit: :contain)
+      assert {:ok, ico} = Converter.convert_to_ico(svg, fit: :contain)
+      assert favicon[:favicon] == ico
+
+      assert {:ok, icon} =
+               Converter.convert(svg, format: :png, width: 512, height: 512, fit: :contain)
+
+      assert favicon[:icon_512] == icon
+    end
+
+    test "returns errors like convert/2" do
+      assert {:error, {:parse_failed, _}} =
+               Converter.convert_to_favicon(TestHelper.invalid_svg())
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert_to_favicon(TestHelper.simple_svg(), fit: :zoom)
+
+      assert message =~ "fit must be one of"
+    end
+  end
+
+  describe "convert_to_animated_gif/2" do
+    test "produces one GIF frame per SVG at the requested delay" do
+      frames = Enum.map(["red", "lime", "blue"], &TestHelper.solid_svg/1)
+
+      assert {:ok, gif} = Converter.convert_to_animated_gif(frames, width: 40, delays: 250)
+
+      assert TestHelper.valid_format?(gif, :gif)
+      assert TestHelper.get_gif_dimensions(gif) == {40, 40}
+      assert TestHelper.gif_frame_delays(gif) == [250, 250, 250]
+    end
+
+    test "supports per-frame delays and loop counts" do
+      frames = Enum.map(["red", "lime", "blue"], &TestHelper.solid_svg/1)
+
+      assert {:ok, looping} = Converter.convert_to_animated_gif(frames, delays: [100, 200, 500])
+      assert TestHelper.gif_frame_delays(looping) == [100, 200, 500]
+      assert TestHelper.gif_loop_count(looping) == 0
+
+      assert {:ok, repeating} = Converter.convert_to_animated_gif(frames, loop: 3)
+      assert TestHelper.gif_frame_delays(repeating) == [100, 100, 100]
+      assert TestHelper.gif_loop_count(repeating) == 3
+    end
+
+    test "keeps the colors of a translucent background" do
+      svg = ~s(<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"/>)
+
+      assert {:ok, gif} =
+               Converter.convert_to_animated_gif([svg, svg], background_color: "FF000080")
+
+      # Render the GIF's first frame back to PNG to read its pixels
+      embedded = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+        <image width="10" height="10" href="data:image/gif;base64,#{Base.encode64(gif)}"/>
+      </svg>
+      """
+
+      assert {:ok, png} = Converter.convert(embedded, format: :png)
+      assert {255, 0, 0, 255} = png |> TestHelper.decode_png() |> TestHelper.pixel_at(0, 0)
+    end
+
+    test "renders grayscale frames" do
+      frames = Enum.map(["red", "lime"], &TestHelper.solid_svg/1)
+
+      assert {:ok, gif} = Converter.convert_to_animated_gif(frames, grayscale: true)
+
+      # Render the GIF's first frame back to PNG to read its pixels
+      embedded = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+        <image width="10" height="10" href="data:image/gif;base64,#{Base.encode64(gif)}"/>
+      </svg>
+      """
+
+      assert {:ok, png} = Converter.convert(embedded, format: :png)
+      assert {r, r, r, 255} = png |> TestHelper.decode_png() |> TestHelper.pixel_at(5, 5)
+      assert r in 40..80
+    end
+
+    test "makes a translucent background transparent below the alpha threshold" do
+      svg = ~s(<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"/>)
+      opts = [background_color: "FF000080", alpha_threshold: 200]
+
+      assert {:ok, gif} = Converter.convert_to_animated_gif([svg, svg], opts)
+
+      # Render the GIF's first frame back to PNG to read its pixels
+      embedded = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+        <image width="10" height="10" href="data:image/gif;base64,#{Base.encode64(gif)}"/>
+      </svg>
+      """
+
+      assert {:ok, png} = Converter.convert(embedded, format: :png)
+      assert {_, _, _, 0} = png |> TestHelper.decode_png() |> TestHelper.pixel_at(0, 0)
+    end
+
+    test "dithers quantized frames" do
+      frames = [@gradient_svg, @gradient_svg]
+
+      assert {:ok, plain} = Converter.convert_to_animated_gif(frames)
+      assert {:ok, dithered} = Converter.convert_to_animated_gif(frames, dither: true)
+
+      # Dithering trades runs of equal indices for noise, which compresses worse
+      assert byte_size(dithered) > byte_size(plain)
+    end
+
+    test "returns error when frames render at different sizes" do
+      frames = [TestHelper.solid_svg("red", 10), TestHelper.solid_svg("lime", 20)]
+
+      assert {:error, {:invalid_dimensions, message}} =
+               Converter.convert_to_animated_gif(frames)
+
+      assert message =~ "frame 1 is 20x20, but the first frame is 10x10"
+
+      assert {:ok, gif} = Converter.convert_to_animated_gif(frames, width: 30, height: 30)
+      assert length(TestHelper.gif_frame_delays(gif)) == 2
+    end
+
+    test "returns error for invalid frames, delays or loop" do
+      frames = [TestHelper.solid_svg("red"), TestHelper.solid_svg("blue")]
+
+      assert {:error, {:invalid_option, message}} = Converter.convert_to_animated_gif([])
+      assert message =~ "frames must be a non-empty list of SVG binaries"
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert_to_animated_gif(frames, delays: [100])
+
+      assert message =~ "delays must be a non-negative integer or a list of 2"
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert_to_animated_gif(frames, loop: -1)
+
+      assert message =~ "loop must be :infinite or an integer from 0 to 65535"
+    end
+  end
+
+  describe "convert_to_sprite_sheet/2" do
+    test "packs sprites into a grid and reports their rects" do
+      icons = Enum.map(["red", "lime", "blue", "yellow"], &TestHelper.solid_svg/1)
+
+      assert {:ok, sheet} =
+               Converter.convert_to_sprite_sheet(icons, columns: 2, cell_size: 16, gutter: 4)
+
+      assert %{width: 36, height: 36, data: png} = sheet
+      assert TestHelper.valid_format?(png, :png)
+      assert TestHelper.get_png_dimensions(png) == {36, 36}
+
+      assert sheet.sprites == [
+               %{x: 0, y: 0, width: 16, height: 16},
+               %{x: 20, y: 0, width: 16, height: 16},
+               %{x: 0, y: 20, width: 16, height: 16},
+               %{x: 20, y: 20, width: 16, height: 16}
+             ]
+
+      image = TestHelper.decode_png(png)
+      assert TestHelper.pixel_at(image, 8, 8) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 28, 28) == {255, 255, 0, 255}
+      assert TestHelper.pixel_at(image, 18, 8) == {0, 0, 0, 0}
+      assert TestHelper.pixel_at(image, 8, 18) == {0, 0, 0, 0}
+    end
+
+    test "defaults to a square-ish grid and supports rectangular cells" do
+      icons = Enum.map(["red", "lime", "blue"], &TestHelper.solid_svg/1)
+
+      assert {:ok, sheet} = Converter.convert_to_sprite_sheet(icons, cell_size: {20, 10})
+
+      assert {sheet.width, sheet.height} == {40, 20}
+      assert Enum.map(sheet.sprites, &{&1.x, &1.y}) == [{0, 0}, {20, 0}, {0, 10}]
+    end
+
+    test "returns error for invalid sprites or grid options" do
+      icons = [TestHelper.solid_svg("red")]
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert_to_sprite_sheet([], cell_size: 16)
+
+      assert message =~ "svgs must be a non-empty list of SVG binaries"
+
+      assert {:error, {:invalid_option, message}} = Converter.convert_to_sprite_sheet(icons)
+      assert message =~ "cell_size option is required"
+
+      assert {:error, {:invalid_dimensions, _}} =
+               Converter.convert_to_sprite_sheet(icons, cell_size: {16, 0})
+
+      assert {:error, {:invalid_option, _}} =
+               Converter.convert_to_sprite_sheet(icons, cell_size: 16, columns: 0)
+
+      assert {:error, {:invalid_option, _}} =
+               Converter.convert_to_sprite_sheet(icons, cell_size: 16, gutter: -1)
+    end
+  end
+
+  describe "convert_to_tiles/2" do
+    @tiles_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="512" height="512">
+      <rect width="256" height="256" fill="#FF0000"/>
+      <rect x="256" width="256" height="256" fill="#00FF00"/>
+      <rect y="256" width="256" height="256" fill="#0000FF"/>
+      <rect x="256" y="256" width="256" height="256" fill="#FFFF00"/>
+    </svg>
+    """
+
+    test "slices a 512x512 render into four 256x256 tiles, row by row" do
+      assert {:ok, tiles} = Converter.convert_to_tiles(@tiles_svg, tile_size: 256)
+      assert Enum.map(tiles, &elem(&1, 0)) == [{0, 0}, {1, 0}, {0, 1}, {1, 1}]
+
+      colors = %{
+        {0, 0} => {255, 0, 0, 255},
+        {1, 0} => {0, 255, 0, 255},
+        {0, 1} => {0, 0, 255, 255},
+        {1, 1} => {255, 255, 0, 255}
+      }
+
+      for {key, png} <- tiles do
+        assert TestHelper.get_png_dimensions(png) == {256, 256}
+        image = TestHelper.decode_png(png)
+        assert TestHelper.pixel_at(image, 0, 0) == colors[key]
+        assert TestHelper.pixel_at(image, 255, 255) == colors[key]
+      end
+    end
+
+    test "defaults to 256 pixel tiles and ignores the format" do
+      assert {:ok, tiles} = Converter.convert_to_tiles(@tiles_svg, format: :jpg)
+      assert length(tiles) == 4
+      assert Enum.all?(tiles, fn {_key, png} -> TestHelper.valid_format?(png, :png) end)
+    end
+
+    test "pads edge tiles to the full tile size with transparency" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="300" height="200">
+        <rect width="300" height="200" fill="#FF0000"/>
+      </svg>
+      """
+
+      assert {:ok, [{{0, 0}, left}, {{1, 0}, right}]} =
+               Converter.convert_to_tiles(svg, tile_size: 256)
+
+      left = TestHelper.decode_png(left)
+      right = TestHelper.decode_png(right)
+      assert TestHelper.pixel_at(left, 255, 199) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(left, 255, 200) == {0, 0, 0, 0}
+      assert TestHelper.pixel_at(right, 43, 100) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(right, 44, 100) == {0, 0, 0, 0}
+      assert TestHelper.pixel_at(right, 255, 255) == {0, 0, 0, 0}
+    end
+
+    test "pads edge tiles with the background color when flattening" do
+      assert {:ok, [{{0, 0}, tile}, {{1, 0}, _}, {{0, 1}, _}, {{1, 1}, _}]} =
+               Converter.convert_to_tiles(TestHelper.solid_svg("#FF0000", 300),
+                 tile_size: {256, 256},
+                 background_color: "#0000FF",
+                 flatten: true
+               )
+
+      corner = TestHelper.decode_png(tile)
+      assert TestHelper.pixel_at(corner, 0, 0) == {255, 0, 0, 255}
+
+      assert {:ok, tiles} =
+               Converter.convert_to_tiles(TestHelper.solid_svg("#FF0000", 300),
+                 background_color: "#0000FF",
+                 flatten: true
+               )
+
+      {{1, 1}, edge} = List.last(tiles)
+      edge = TestHelper.decode_png(edge)
+      assert TestHelper.pixel_at(edge, 43, 43) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(edge, 44, 44) == {0, 0, 255, 255}
+    end
+
+    test "rejects an invalid tile_size" do
+      for tile_size <- [0, -1, {256, 0}, :large, 25.6] do
+        assert {:error, {:invalid_dimensions, message}} =
+                 Converter.convert_to_tiles(@tiles_svg, tile_size: tile_size)
+
+        assert message =~ "tile_size must be a positive integer or a {width, height} tuple"
+      end
+    end
+  end
+
+  describe "convert_multi/3" do
+    @multi_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+      <circle cx="50" cy="50" r="40" fill="#FF0000"/>
+    </svg>
+    """
+
+    test "returns an entry per format, in order, from a single render" do
+      assert {:ok, [png: png, jpg: jpg]} = Converter.convert_multi(@multi_svg, [:png, :jpg])
+      assert TestHelper.valid_format?(png, :png)
+      assert TestHelper.valid_format?(jpg, :jpg)
+    end
+
+    test "keeps PNG transparent and flattens JPEG, like separate conversions" do
+      assert {:ok, [png: png, jpg: jpg]} =
+               Converter.convert_multi(@multi_svg, [:png, :jpg], width: 50, quality: 80)
+
+      assert {:ok, ^png} = Converter.convert(@multi_svg, format: :png, width: 50)
+      assert {:ok, ^jpg} = Converter.convert(@multi_svg, format: :jpg, width: 50, quality: 80)
+      assert {_, _, _, 0} = TestHelper.pixel_at(TestHelper.decode_png(png), 0, 0)
+    end
+
+    test "rejects an empty or unsupported format list" do
+      assert {:error, {:invalid_option, _}} = Converter.convert_multi(@multi_svg, [])
+      assert {:error, {:invalid_option, _}} = Converter.convert_multi(@multi_svg, :png)
+
+      assert {:error, {:unsupported_format, message}} =
+               Converter.convert_multi(@multi_svg, [:png, :svg])
+
+      assert message =~ "svg"
+    end
+  end
+
+  describe "convert/2 - error handling" do
+    test "returns error when format is missing" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_option, message}} = Converter.convert(svg, width: 100)
+      assert message == "format option is required"
+    end
+
+    test "returns error for unsupported format" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:unsupported_format, message}} =
+               Converter.convert(svg, format: :psd, width: 100)
+
+      assert message =~ "unsupported format"
+      assert message =~ ":psd"
+    end
+
+    test "returns error for invalid SVG" do
+      svg = TestHelper.invalid_svg()
+
+      assert {:error, {:parse_failed, message}} = Converter.convert(svg, format: :png, width: 100)
+      assert message =~ "Failed to parse SVG"
+    end
+
+    test "returns error for negative width" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_dimensions, message}} =
+               Converter.convert(svg, format: :png, width: -100)
+
+      assert message =~ "width must be a positive integer"
+    end
+
+    test "returns error for zero width" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_dimensions, message}} =
+               Converter.convert(svg, format: :png, width: 0)
+
+      assert message =~ "width must be a positive integer"
+    end
+
+    test "returns error for negative height" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_dimensions, message}} =
+               Converter.convert(svg, format: :png, height: -100)
+
+      assert message =~ "height must be a positive integer"
+    end
+
+    test "returns error for zero height" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_dimensions, message}} =
+               Converter.convert(svg, format: :png, height: 0)
+
+      assert message =~ "height must be a positive integer"
+    end
+
+    test "returns error for a width string that isn't pixels or a percentage" do
+      svg = TestHelper.simple_svg()
+
+      for width <- ["wide", "0%", "-50%"] do
+        assert {:error, {:invalid_dimensions, message}} =
+                 Converter.convert(svg, format: :png, width: width)
+
+        assert message =~ "width must be a number of pixels or a positive percentage"
+      end
+    end
+
+    test "returns error for non-integer height" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_dimensions, message}} =
+               Converter.convert(svg, format: :png, height: 99.5)
+
+      assert message =~ "height must be a positive integer"
+    end
+
+    test "returns error for zero quality" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :jpg, width: 100, quality: 0)
+
+      assert message =~ "quality must be an integer between 1 and 100"
+    end
+
+    test "returns error for quality above 100" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :jpg, width: 100, quality: 101)
+
+      assert message =~ "quality must be an integer between 1 and 100"
+    end
+
+    test "returns error for invalid replacements (not a map)" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, width: 100, replacements: "invalid")
+
+      assert message =~ "replacements must be a map"
+    end
+
+    test "returns error for invalid regex replacements (not a map)" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, width: 100, regex_replacements: "fill")
+
+      assert message =~ "regex_replacements must be a map or a list"
+    end
+
+    test "returns error for malformed replacement list entries" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, width: 100, replacements: ["fill"])
+
+      assert message =~ "replacements entries must be"
+      assert message =~ ~S(got: "fill")
+    end
+
+    test "returns error for a non-positive replacement max" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 100,
+                 replacements: [{"#FF0000", "#0000FF", 0}]
+               )
+
+      assert message =~ "positive integer max"
+    end
+
+    test "returns error for invalid font_family" do
+      svg = TestHelper.text_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, font_family: :serif)
+
+      assert message =~ "font_family must be a non-empty string"
+    end
+
+    test "returns error for non-positive font_size" do
+      svg = TestHelper.text_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, font_size: 0)
+
+      assert message =~ "font_size must be a positive number"
+    end
+
+    test "returns error for non-UTF-8 SVG data" do
+      svg = <<"<svg xmlns=\"http://www.w3.org/2000/svg\">", 0xFF, 0xFE, "</svg>">>
+
+      assert {:error, {:parse_failed, message}} = Converter.convert(svg, format: :png, width: 100)
+      assert message =~ "not valid UTF-8"
+      assert message =~ "offset 40"
+    end
+
+    test "returns error for empty SVG string" do
+      assert {:error, {:parse_failed, message}} = Converter.convert("", format: :png, width: 100)
+      assert message =~ "Failed to parse SVG"
+    end
+
+    test "returns error instead of crashing when rendering panics" do
+      # The huge blur radius overflows inside resvg, which panics in the debug build the
+      # test environment compiles
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+        <filter id="blur"><feGaussianBlur stdDeviation="1e30"/></filter>
+        <rect width="10" height="10" filter="url(#blur)"/>
+      </svg>
+      """
+
+      assert {:error, {:render_failed, message}} = Converter.convert(svg, format: :png)
+      assert message =~ "Conversion panicked"
+
+      assert {:ok, _data} = Converter.convert(TestHelper.simple_svg(), format: :png)
+    end
+
+    test "returns error for non-string SVG input" do
+      assert_raise FunctionClauseError, fn ->
+        Converter.convert(123, format: :png, width: 100)
+      end
+    end
+  end
 
   describe "convert/2 - edge cases" do
     test "handles SVG with special characters" do
@@ -391,4 +3729,152 @@ defmodule Svgager.ConverterTest do
       assert TestHelper.valid_format?(data, :png)
     end
   end
+
+  describe "convert_to_rgba/2" do
+    test "returns width * height * 4 bytes of the pixels convert/2 encodes" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, %{data: data, width: 50, height: 50, channels: 4}} =
+               Converter.convert_to_rgba(svg, width: 50)
+
+      assert byte_size(data) == 50 * 50 * 4
+      assert {:ok, png} = Converter.convert(svg, format: :png, width: 50)
+      assert {50, 50, ^data} = TestHelper.decode_png(png)
+    end
+
+    test "returns RGB flattened onto the background color with 3 channels" do
+      assert {:ok, %{data: data, width: 100, height: 100, channels: 3}} =
+               Converter.convert_to_rgba(TestHelper.simple_svg(), channels: 3)
+
+      assert byte_size(data) == 100 * 100 * 3
+      # The transparent corner becomes white and the red rect is unchanged
+      assert <<255, 255, 255, _rest::binary>> = data
+      assert binary_part(data, (50 * 100 + 50) * 3, 3) == <<255, 0, 0>>
+
+      assert {:ok, %{data: <<0, 0, 255, _rest::binary>>}} =
+               Converter.convert_to_rgba(TestHelper.simple_svg(),
+                 channels: 3,
+                 background_color: "#0000FF"
+               )
+    end
+
+    test "composites semi-transparent pixels over the background with 3 channels" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="4" height="4">
+        <rect width="4" height="4" fill="#FF0000" fill-opacity="0.5"/>
+      </svg>
+      """
+
+      assert {:ok, %{data: <<255, green, blue, _rest::binary>>}} =
+               Converter.convert_to_rgba(svg, channels: 3)
+
+      assert green in 127..128 and blue == green
+
+      assert {:ok, %{data: <<red, 0, 0, _rest::binary>>}} =
+               Converter.convert_to_rgba(svg, channels: 3, background_color: "#000000")
+
+      assert red in 127..128
+    end
+
+    test "uses a translucent background color at full opacity with 3 channels" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="4" height="4">
+        <rect x="2" width="2" height="4" fill="#0000FF" fill-opacity="0.5"/>
+      </svg>
+      """
+
+      assert {:ok, %{data: data}} =
+               Converter.convert_to_rgba(svg, channels: 3, background_color: "FF000080")
+
+      # The empty half is the background's color and the rect blends over it as if opaque
+      assert <<255, 0, 0, _rest::binary>> = data
+      assert <<red, 0, blue>> = binary_part(data, 3 * 3, 3)
+      assert red in 127..128 and blue in 127..128
+
+      assert {:ok, %{data: <<0, 0, 0, _rest::binary>>}} =
+               Converter.convert_to_rgba(svg, channels: 3, background_color: "transparent")
+    end
+
+    test "returns straight alpha by default and premultiplied alpha on request" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="4" height="4">
+        <rect width="4" height="4" fill="#FF0000" fill-opacity="0.5"/>
+      </svg>
+      """
+
+      assert {:ok, %{data: <<255, 0, 0, alpha, _rest::binary>>}} = Converter.convert_to_rgba(svg)
+      assert alpha in 127..128
+
+      assert {:ok, %{data: <<red, 0, 0, ^alpha, _rest::binary>>}} =
+               Converter.convert_to_rgba(svg, premultiplied: true)
+
+      assert red == alpha
+    end
+
+    test "returns error for invalid channels or premultiplied" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert_to_rgba(TestHelper.simple_svg(), channels: 1)
+
+      assert message =~ "channels must be 3 or 4"
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert_to_rgba(TestHelper.simple_svg(), premultiplied: :yes)
+
+      assert message =~ "premultiplied must be a boolean"
+    end
+  end
+
+  describe "convert/2 - resource_dir" do
+    @describetag :tmp_dir
+
+    setup %{tmp_dir: tmp_dir} do
+      assets = Path.join(tmp_dir, "assets")
+      File.mkdir_p!(assets)
+      {:ok, red} = Converter.convert(TestHelper.solid_svg("#FF0000", 4), format: :png)
+      File.write!(Path.join(assets, "logo.png"), red)
+      File.write!(Path.join(tmp_dir, "secret.png"), red)
+
+      image_svg = fn href ->
+        """
+        <svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+          <image width="10" height="10" href="#{href}"/>
+        </svg>
+        """
+      end
+
+      %{assets: assets, image_svg: image_svg}
+    end
+
+    test "loads relative hrefs from the directory", %{assets: assets, image_svg: image_svg} do
+      assert {:ok, %{data: data, warnings: []}} =
+               Converter.convert_with_info(image_svg.("./logo.png"),
+                 format: :png,
+                 resource_dir: assets
+               )
+
+      assert data |> TestHelper.decode_png() |> TestHelper.pixel_at(5, 5) == {255, 0, 0, 255}
+    end
+
+    test "refuses hrefs that escape the directory", context do
+      %{assets: assets, tmp_dir: tmp_dir, image_svg: image_svg} = context
+      for href <- ["../secret.png", Path.join(tmp_dir, "secret.png")] do
+        assert {:ok, %{data: data, warnings: [warning]}} =
+                 Converter.convert_with_info(image_svg.(href), format: :png, resource_dir: assets)
+
+        assert warning =~ ~s(Image "#{href}")
+        assert warning =~ "it was not rendered"
+        assert data |> TestHelper.decode_png() |> TestHelper.pixel_at(5, 5) == {0, 0, 0, 0}
+      end
+    end
+
+    test "returns error for a missing directory", %{tmp_dir: tmp_dir, image_svg: image_svg} do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(image_svg.("logo.png"),
+                 format: :png,
+                 resource_dir: Path.join(tmp_dir, "missing")
+               )
+
+      assert message =~ "Invalid resource_dir"
+    end
+  end
 end
@@ -96,6 +96,31 @@ defmodule Svgager.DimensionTest do
     end
   end
 
+  describe "dimension verification - BMP" do
+    test "BMP output has correct dimensions" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :bmp, width: 320, height: 240)
+      assert {320, 240} = TestHelper.get_bmp_dimensions(data)
+    end
+
+    test "BMP maintains aspect ratio with width only" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :bmp, width: 150)
+      assert {150, 150} = TestHelper.get_bmp_dimensions(data)
+    end
+  end
+
+  describe "dimension verification - TIFF" do
+    test "TIFF output has correct dimensions" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :tiff, width: 320, height: 240)
+      assert {320, 240} = TestHelper.get_tiff_dimensions(data)
+    end
+  end
+
   describe "dimension verification - complex SVG" do
     test "maintains correct dimensions for complex SVG" do
       svg = TestHelper.complex_svg()
@@ -141,6 +166,25 @@ defmodule Svgager.DimensionTest do
       assert {300, 150} = TestHelper.get_png_dimensions(data)
     end
 
+    test "rounds the derived dimension to the nearest pixel" do
+      # 3:2, so the derived sides are 66.67px, which truncation would turn into 66
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="300" height="200">
+        <rect width="300" height="200" fill="#0000FF"/>
+      </svg>
+      """
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, width: 100)
+      assert {100, 67} = TestHelper.get_png_dimensions(data)
+
+      assert {:ok, %{width: 67, height: 45}} =
+               Converter.convert_with_info(svg, format: :png, width: 67)
+
+      tall = String.replace(svg, ~s(width="300" height="200"), ~s(width="200" height="300"))
+      assert {:ok, data} = Converter.convert(tall, format: :png, height: 100)
+      assert {67, 100} = TestHelper.get_png_dimensions(data)
+    end
+
     test "allows distortion when both dimensions specified" do
       # Create a 100x100 SVG
       svg = TestHelper.simple_svg()
@@ -151,6 +195,400 @@ defmodule Svgager.DimensionTest do
     end
   end
 
+  describe "fit modes" do
+    # Full-bleed square SVG so every rendered pixel is opaque red
+    @square_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+      <rect width="100" height="100" fill="#FF0000"/>
+    </svg>
+    """
+
+    test "fill stretches the SVG across the whole box" do
+      assert {:ok, data} =
+               Converter.convert(@square_svg, format: :png, width: 200, height: 100, fit: :fill)
+
+      image = TestHelper.decode_png(data)
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(image, 0, 50)
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(image, 199, 50)
+    end
+
+    test "contain centers an undistorted square inside a wide box" do
+      assert {:ok, data} =
+               Converter.convert(@square_svg,
+                 format: :png,
+                 width: 200,
+                 height: 100,
+                 fit: :contain
+               )
+
+      image = TestHelper.decode_png(data)
+      assert {200, 100, _} = image
+
+      # 50px transparent bars on the left and right, 100x100 content in the middle
+      assert {_, _, _, 0} = TestHelper.pixel_at(image, 49, 50)
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(image, 50, 50)
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(image, 149, 50)
+      assert {_, _, _, 0} = TestHelper.pixel_at(image, 150, 50)
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(image, 100, 0)
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(image, 100, 99)
+    end
+
+    test "cover fills the whole box" do
+      assert {:ok, data} =
+               Converter.convert(@square_svg, format: :png, width: 200, height: 100, fit: :cover)
+
+      image = TestHelper.decode_png(data)
+      assert {200, 100, _} = image
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(image, 0, 0)
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(image, 199, 99)
+    end
+
+    test "preserve_aspect letterboxes a square into a wide box with equal bars" do
+      assert {:ok, data} =
+               Converter.convert(@square_svg,
+                 format: :png,
+                 width: 300,
+                 height: 100,
+                 preserve_aspect: true,
+                 background_color: "#0000FF",
+                 flatten: true
+               )
+
+      image = TestHelper.decode_png(data)
+      assert {300, 100, _} = image
+
+      background? = &(TestHelper.pixel_at(image, &1, 50) == {0, 0, 255, 255})
+      left_bar = 0..299 |> Enum.take_while(background?) |> length()
+      right_bar = 299..0//-1 |> Enum.take_while(background?) |> length()
+      assert left_bar == 100
+      assert right_bar == 100
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(image, 150, 50)
+    end
+
+    test "preserve_aspect matches fit: :contain and conflicts with other fit modes" do
+      opts = [format: :png, width: 200, height: 100]
+
+      assert Converter.convert(@square_svg, [preserve_aspect: true] ++ opts) ==
+               Converter.convert(@square_svg, [fit: :contain] ++ opts)
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(@square_svg, [preserve_aspect: true, fit: :cover] ++ opts)
+
+      assert message =~ "can't be combined with fit cover"
+    end
+
+    test "returns error for unknown fit mode" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(@square_svg, format: :png, width: 200, height: 100, fit: :zoom)
+
+      assert message =~ "fit must be one of"
+    end
+
+    test "honors the SVG's preserveAspectRatio when no fit is given" do
+      svg = fn aspect ->
+        """
+        <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100" viewBox="0 0 100 100"
+             preserveAspectRatio="#{aspect}">
+          <rect width="100" height="100" fill="#FF0000"/>
+        </svg>
+        """
+      end
+
+      opts = [format: :png, width: 200, height: 100]
+
+      # meet letterboxes like a browser: 50px transparent bars around the 100x100 content
+      assert {:ok, data} = Converter.convert(svg.("xMidYMid meet"), opts)
+      image = TestHelper.decode_png(data)
+      assert {_, _, _, 0} = TestHelper.pixel_at(image, 49, 50)
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(image, 50, 50)
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(image, 149, 50)
+      assert {_, _, _, 0} = TestHelper.pixel_at(image, 150, 50)
+      assert {:ok, ^data} = Converter.convert(@square_svg, [fit: :contain] ++ opts)
+
+      assert {:ok, data} = Converter.convert(svg.("xMinYMid meet"), opts)
+      image = TestHelper.decode_png(data)
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(image, 0, 50)
+      assert {_, _, _, 0} = TestHelper.pixel_at(image, 100, 50)
+
+      assert {:ok, data} = Converter.convert(svg.("none"), opts)
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(TestHelper.decode_png(data), 199, 50)
+
+      # An explicit fit takes precedence
+      assert {:ok, data} = Converter.convert(svg.("xMidYMid meet"), [fit: :fill] ++ opts)
+      assert {255, 0, 0, 255} = TestHelper.pixel_at(TestHelper.decode_png(data), 0, 50)
+    end
+  end
+
+  describe "crop" do
+    test "crops the center of the rendered image" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, %{data: data, width: 50, height: 50}} =
+               Converter.convert_with_info(svg, format: :png, crop: {25, 25, 50, 50})
+
+      assert {50, 50} = TestHelper.get_png_dimensions(data)
+      # simple_svg's red rect covers 10..90, so the whole crop is red
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 0, 0) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 49, 49) == {255, 0, 0, 255}
+    end
+
+    test "crops after scaling to the requested size" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :jpg, width: 200, crop: {0, 0, 120, 80})
+      assert {120, 80} = TestHelper.get_jpeg_dimensions(data)
+    end
+
+    test "clamps an out-of-bounds rectangle to the image" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, %{width: 20, height: 30}} =
+               Converter.convert_with_info(svg, format: :png, crop: {80, 70, 50, 50})
+    end
+
+    test "returns error when the rectangle lies outside the image" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_dimensions, message}} =
+               Converter.convert(svg, format: :png, crop: {100, 0, 10, 10})
+
+      assert message =~ "does not overlap the 100x100 image"
+    end
+
+    test "returns error for a malformed crop" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, crop: {0, 0, 0, 10})
+
+      assert message =~ "crop must be an {x, y, width, height} tuple"
+    end
+  end
+
+  describe "trim" do
+    @padded_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="400" height="400" viewBox="0 0 400 400">
+      <rect x="190" y="180" width="20" height="40" fill="#FF0000" />
+    </svg>
+    """
+
+    test "trims transparent margins around the content" do
+      assert {:ok, untrimmed} = Converter.convert(@padded_svg, format: :png)
+
+      assert {:ok, %{data: trimmed, width: 20, height: 40}} =
+               Converter.convert_with_info(@padded_svg, format: :png, trim: true)
+
+      assert byte_size(trimmed) < byte_size(untrimmed)
+      image = TestHelper.decode_png(trimmed)
+      assert TestHelper.pixel_at(image, 0, 0) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 19, 39) == {255, 0, 0, 255}
+    end
+
+    test "trims background-colored margins for opaque formats" do
+      assert {:ok, %{width: 20, height: 40}} =
+               Converter.convert_with_info(@padded_svg, format: :jpg, trim: true)
+    end
+
+    test "returns a fully transparent image unchanged" do
+      svg = ~S(<svg xmlns="http://www.w3.org/2000/svg" width="40" height="30"></svg>)
+
+      assert {:ok, %{width: 40, height: 30}} =
+               Converter.convert_with_info(svg, format: :png, trim: true)
+    end
+
+    test "crop applies to the trimmed image" do
+      assert {:ok, %{width: 20, height: 10}} =
+               Converter.convert_with_info(@padded_svg,
+                 format: :png,
+                 trim: true,
+                 crop: {0, 0, 50, 10}
+               )
+    end
+  end
+
+  describe "rotate" do
+    # A 40x20 canvas with a red square in its top-left corner
+    @corner_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="40" height="20" viewBox="0 0 40 20">
+      <rect width="10" height="10" fill="#FF0000" />
+    </svg>
+    """
+
+    test "90 degree rotation swaps width and height" do
+      assert {:ok, %{data: data, width: 20, height: 40}} =
+               Converter.convert_with_info(@corner_svg, format: :png, rotate: 90)
+
+      assert {20, 40} = TestHelper.get_png_dimensions(data)
+    end
+
+    test "rotates clockwise" do
+      assert {:ok, data} = Converter.convert(@corner_svg, format: :png, rotate: 90)
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 19, 0) == {255, 0, 0, 255}
+      assert {_r, _g, _b, 0} = TestHelper.pixel_at(image, 0, 0)
+    end
+
+    test "180 degree rotation keeps dimensions and moves content to the opposite corner" do
+      assert {:ok, %{data: data, width: 40, height: 20}} =
+               Converter.convert_with_info(@corner_svg, format: :png, rotate: 180)
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 39, 19) == {255, 0, 0, 255}
+    end
+
+    test "requested dimensions describe the unrotated image" do
+      assert {:ok, %{width: 50, height: 100}} =
+               Converter.convert_with_info(@corner_svg, format: :png, width: 100, rotate: 270)
+    end
+
+    test "returns error for angles other than right angles" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(@corner_svg, format: :png, rotate: 45)
+
+      assert message =~ "rotate must be one of [0, 90, 180, 270]"
+    end
+  end
+
+  describe "flip" do
+    test "flip_h mirrors the output horizontally" do
+      assert {:ok, data} = Converter.convert(@corner_svg, format: :png, flip_h: true)
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 39, 0) == {255, 0, 0, 255}
+      assert {_r, _g, _b, 0} = TestHelper.pixel_at(image, 0, 0)
+    end
+
+    test "flip_v mirrors the output vertically" do
+      assert {:ok, data} = Converter.convert(@corner_svg, format: :png, flip_v: true)
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 0, 19) == {255, 0, 0, 255}
+      assert {_r, _g, _b, 0} = TestHelper.pixel_at(image, 0, 0)
+    end
+
+    test "flipping both ways equals a 180 degree rotation" do
+      assert {:ok, flipped} =
+               Converter.convert(@corner_svg, format: :png, flip_h: true, flip_v: true)
+
+      assert {:ok, rotated} = Converter.convert(@corner_svg, format: :png, rotate: 180)
+      assert TestHelper.decode_png(flipped) == TestHelper.decode_png(rotated)
+    end
+
+    test "flipping twice returns the original pixels" do
+      assert {:ok, original} = Converter.convert(@corner_svg, format: :png)
+
+      # Both flips are a half turn, which a further half turn undoes
+      assert {:ok, restored} =
+               Converter.convert(@corner_svg,
+                 format: :png,
+                 flip_h: true,
+                 flip_v: true,
+                 rotate: 180
+               )
+
+      assert TestHelper.decode_png(original) == TestHelper.decode_png(restored)
+    end
+
+    test "returns error for non-boolean flips" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(@corner_svg, format: :png, flip_h: 1)
+
+      assert message =~ "flip_h must be a boolean"
+    end
+  end
+
+  describe "padding" do
+    test "adds padding on every side of the requested size" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, %{data: data, width: 70, height: 70}} =
+               Converter.convert_with_info(svg, format: :png, width: 50, padding: 10)
+
+      assert {70, 70} = TestHelper.get_png_dimensions(data)
+    end
+
+    test "padding carries the background color" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect width="100" height="100" fill="#FF0000" />
+      </svg>
+      """
+
+      assert {:ok, data} =
+               Converter.convert(svg,
+                 format: :png,
+                 width: 50,
+                 padding: 10,
+                 flatten: true,
+                 background_color: "00FF00"
+               )
+
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 0, 0) == {0, 255, 0, 255}
+      assert TestHelper.pixel_at(image, 69, 69) == {0, 255, 0, 255}
+      assert TestHelper.pixel_at(image, 10, 10) == {255, 0, 0, 255}
+    end
+
+    test "padding is transparent for PNG" do
+      svg = TestHelper.simple_svg()
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, padding: 5)
+      assert {_r, _g, _b, 0} = data |> TestHelper.decode_png() |> TestHelper.pixel_at(0, 0)
+    end
+
+    test "padding is applied after trim" do
+      svg = TestHelper.simple_svg()
+
+      # simple_svg's rect is 80x80 once its transparent margin is trimmed
+      assert {:ok, %{width: 88, height: 88}} =
+               Converter.convert_with_info(svg, format: :png, trim: true, padding: 4)
+    end
+
+    test "returns error for negative padding" do
+      svg = TestHelper.simple_svg()
+
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(svg, format: :png, padding: -1)
+
+      assert message =~ "padding must be a non-negative integer"
+    end
+  end
+
+  describe "dpi" do
+    # 2in x 1in, so 192x96 at the default 96 DPI
+    @mm_svg """
+    <svg xmlns="http://www.w3.org/2000/svg" width="50.8mm" height="25.4mm" viewBox="0 0 20 10">
+      <rect width="20" height="10" fill="#FF0000" />
+    </svg>
+    """
+
+    test "physical units resolve at 96 DPI by default" do
+      assert {:ok, %{width: 192, height: 96}} =
+               Converter.convert_with_info(@mm_svg, format: :png)
+    end
+
+    test "higher DPI produces proportionally more pixels" do
+      assert {:ok, %{data: data, width: 600, height: 300}} =
+               Converter.convert_with_info(@mm_svg, format: :png, dpi: 300)
+
+      assert TestHelper.get_dimensions(data, :png) == {600, 300}
+    end
+
+    test "explicit dimensions take precedence over DPI" do
+      assert {:ok, %{width: 100, height: 50}} =
+               Converter.convert_with_info(@mm_svg, format: :png, width: 100, dpi: 300)
+    end
+
+    test "returns error for non-positive dpi" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(@mm_svg, format: :png, dpi: 0)
+
+      assert message =~ "dpi must be a positive number"
+    end
+  end
+
   describe "byte size verification" do
     test "PNG output has reasonable byte size" do
       svg = TestHelper.simple_svg()
@@ -188,6 +626,39 @@ defmodule Svgager.DimensionTest do
       assert byte_size(webp) > 100
     end
 
+    test "fast PNG compression produces larger files than best" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, fast} =
+               Converter.convert(svg, format: :png, width: 400, png_compression: :fast)
+
+      assert {:ok, best} =
+               Converter.convert(svg, format: :png, width: 400, png_compression: :best)
+
+      assert TestHelper.valid_png?(fast)
+      assert TestHelper.valid_png?(best)
+      assert byte_size(fast) > byte_size(best)
+      assert TestHelper.decode_png(fast) == TestHelper.decode_png(best)
+    end
+
+    test "PNG compression defaults to fast" do
+      svg = TestHelper.complex_svg()
+
+      assert {:ok, default} = Converter.convert(svg, format: :png, width: 200)
+
+      assert {:ok, fast} =
+               Converter.convert(svg, format: :png, width: 200, png_compression: :fast)
+
+      assert default == fast
+    end
+
+    test "returns error for unknown PNG compression" do
+      assert {:error, {:invalid_option, message}} =
+               Converter.convert(TestHelper.simple_svg(), format: :png, png_compression: :max)
+
+      assert message =~ "png_compression must be one of"
+    end
+
     test "complex SVG produces larger files than simple SVG" do
       simple = TestHelper.simple_svg()
       complex = TestHelper.complex_svg()
@@ -202,6 +673,110 @@ defmodule Svgager.DimensionTest do
     end
   end
 
+  describe "dimension verification - intrinsic size fallback" do
+    test "viewBox-only SVG uses the viewBox size" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 30 20">
+        <rect width="30" height="20" fill="#FF0000"/>
+      </svg>
+      """
+
+      assert {:ok, %{width: 30, height: 20}} = Converter.convert_with_info(svg, format: :png)
+
+      assert {:ok, %{width: 60, height: 40}} =
+               Converter.convert_with_info(svg, format: :png, width: 60)
+    end
+
+    test "percentage size falls back to the viewBox size" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="50%" height="100%" viewBox="0 0 30 20">
+        <rect width="30" height="20" fill="#FF0000"/>
+      </svg>
+      """
+
+      assert {:ok, %{data: data, width: 30, height: 20}} =
+               Converter.convert_with_info(svg, format: :png)
+
+      # The viewBox content fills the whole output rather than being letterboxed
+      image = TestHelper.decode_png(data)
+      assert TestHelper.pixel_at(image, 0, 0) == {255, 0, 0, 255}
+      assert TestHelper.pixel_at(image, 29, 19) == {255, 0, 0, 255}
+    end
+
+    test "returns error when there is no usable intrinsic size" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="100%">
+        <rect width="30" height="20" fill="#FF0000"/>
+      </svg>
+      """
+
+      assert {:error, {:invalid_dimensions, message}} =
+               Converter.convert(svg, format: :png, width: 60)
+
+      assert message =~ "supply an explicit width and height"
+    end
+
+    test "explicit width and height work without an intrinsic size" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg">
+        <rect width="30" height="20" fill="#FF0000"/>
+      </svg>
+      """
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, width: 60, height: 40)
+      assert {60, 40} = TestHelper.get_png_dimensions(data)
+    end
+  end
+
+  describe "intrinsic_size/2" do
+    test "returns the declared size without rendering" do
+      svg = ~s(<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50"/>)
+
+      assert {:ok, {100.0, 50.0}} = Converter.intrinsic_size(svg)
+      assert {:ok, {100.0, 50.0}} = Converter.intrinsic_size(svg, width: 10, node_id: "missing")
+    end
+
+    test "falls back to the viewBox for missing or percentage sizes" do
+      view_box_only = ~s(<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 30 20"/>)
+      percentage =
+        ~s(<svg xmlns="http://www.w3.org/2000/svg" width="50%" viewBox="0 0 40.5 20"/>)
+
+      assert {:ok, {30.0, 20.0}} = Converter.intrinsic_size(view_box_only)
+      assert {:ok, {40.5, 20.0}} = Converter.intrinsic_size(percentage)
+    end
+
+    test "falls back to the viewBox for SVGs with a DOCTYPE" do
+      doctype =
+        ~s(<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" ) <>
+          ~s("http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">)
+
+      percentage =
+        doctype <> ~s(<svg xmlns="http://www.w3.org/2000/svg" width="50%" viewBox="0 0 30 20"/>)
+
+      no_view_box = doctype <> ~s(<svg xmlns="http://www.w3.org/2000/svg" width="100%"/>)
+
+      assert {:ok, {30.0, 20.0}} = Converter.intrinsic_size(percentage)
+      assert {:error, {:invalid_dimensions, _}} = Converter.intrinsic_size(no_view_box)
+    end
+
+    test "applies replacements and dpi before measuring" do
+      svg = ~s(<svg xmlns="http://www.w3.org/2000/svg" width="1in" height="2in"/>)
+
+      assert {:ok, {96.0, 192.0}} = Converter.intrinsic_size(svg)
+      assert {:ok, {72.0, 144.0}} = Converter.intrinsic_size(svg, dpi: 72)
+
+      replacements = %{~s(height="2in") => ~s(height="1in")}
+      assert {:ok, {96.0, 96.0}} = Converter.intrinsic_size(svg, replacements: replacements)
+    end
+
+    test "returns error when there is no usable intrinsic size" do
+      svg = ~s(<svg xmlns="http://www.w3.org/2000/svg" width="100%"/>)
+
+      assert {:error, {:invalid_dimensions, _}} = Converter.intrinsic_size(svg)
+      assert {:error, {:parse_failed, _}} = Converter.intrinsic_size("not an svg")
+    end
+  end
+
   describe "dimension verification - edge cases" do
     test "very small dimensions work correctly" do
       svg = TestHelper.simple_svg()
@@ -217,6 +792,29 @@ defmodule Svgager.DimensionTest do
       assert {2000, 1500} = TestHelper.get_png_dimensions(data)
     end
 
+    test "derived dimension is clamped to 1 for extreme aspect ratios" do
+      # 1000x1 SVG scaled to 3px wide would derive a height of 0.003px
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="1000" height="1">
+        <rect width="1000" height="1" fill="#FF0000"/>
+      </svg>
+      """
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, width: 3)
+      assert {3, 1} = TestHelper.get_png_dimensions(data)
+    end
+
+    test "derived width is clamped to 1 for extreme aspect ratios" do
+      svg = """
+      <svg xmlns="http://www.w3.org/2000/svg" width="1" height="1000">
+        <rect width="1" height="1000" fill="#FF0000"/>
+      </svg>
+      """
+
+      assert {:ok, data} = Converter.convert(svg, format: :png, height: 3)
+      assert {1, 3} = TestHelper.get_png_dimensions(data)
+    end
+
     test "odd dimensions work correctly" do
       svg = TestHelper.simple_svg()
 
@@ -231,7 +829,7 @@ defmodule Svgager.DimensionTest do
       width = 250
       height = 200
 
-      formats = [:png, :jpg, :jpeg, :gif, :webp]
+      formats = [:png, :jpg, :jpeg, :gif, :webp, :qoi, :tga, :ppm]
 
       for format <- formats do
         assert {:ok, data} = Converter.convert(svg, format: format, width: width, height: height)
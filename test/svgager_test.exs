@@ -54,7 +54,9 @@ defmodule SvgagerTest do
     end
 
     test "handles errors through main API" do
-      assert {:error, message} = Svgager.convert("invalid svg", format: :png, width: 100)
+      assert {:error, {:parse_failed, message}} =
+               Svgager.convert("invalid svg", format: :png, width: 100)
+
       assert is_binary(message)
       assert message =~ "Failed to parse SVG"
     end
@@ -62,7 +64,7 @@ defmodule SvgagerTest do
     test "validates options through main API" do
       svg = TestHelper.simple_svg()
 
-      assert {:error, message} = Svgager.convert(svg, width: 100)
+      assert {:error, {:invalid_option, message}} = Svgager.convert(svg, width: 100)
       assert message == "format option is required"
     end
   end
@@ -253,5 +255,26 @@ defmodule SvgagerTest do
       assert is_binary(data)
       assert byte_size(data) > 0
     end
+
+    @tag :slow
+    test "concurrent conversions run on dirty schedulers without stalling other processes" do
+      svg = TestHelper.simple_svg()
+      {:ok, expected} = Svgager.convert(svg, format: :png, width: 2000, height: 2000)
+
+      conversions =
+        Task.async(fn ->
+          1..8
+          |> Task.async_stream(fn _ ->
+            Svgager.convert(svg, format: :png, width: 2000, height: 2000)
+          end)
+          |> Enum.map(fn {:ok, result} -> result end)
+        end)
+
+      parent = self()
+      spawn(fn -> send(parent, :still_responsive) end)
+      assert_receive :still_responsive, 1000
+
+      assert Task.await(conversions, 60_000) == List.duplicate({:ok, expected}, 8)
+    end
   end
 end
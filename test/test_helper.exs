@@ -1 +1,3 @@
-ExUnit.start()
+# Tests tagged :fonts need DejaVu Sans and DejaVu Serif installed on the host;
+# run them with `mix test --include fonts`
+ExUnit.start(exclude: [:fonts])